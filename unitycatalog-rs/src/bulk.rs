@@ -0,0 +1,109 @@
+use crate::errors::{UCRSError, UCRSResult};
+use futures_util::stream::{self, StreamExt};
+use std::future::Future;
+
+/// Outcome of one item in a bulk call, keyed by the object's full name.
+#[derive(Debug)]
+pub struct BulkItem<T> {
+    pub key: String,
+    pub result: UCRSResult<T>
+}
+
+/// Per-item results of a bulk call, in the order the items were given.
+/// A failed item never stops the others from running.
+#[derive(Debug)]
+pub struct BulkReport<T> {
+    pub items: Vec<BulkItem<T>>
+}
+
+impl<T> BulkReport<T> {
+    pub fn successes(&self) -> impl Iterator<Item = (&str, &T)> {
+        self.items.iter()
+            .filter_map(|i| i.result.as_ref().ok().map(|v| (i.key.as_str(), v)))
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = (&str, &UCRSError)> {
+        self.items.iter()
+            .filter_map(|i| i.result.as_ref().err().map(|e| (i.key.as_str(), e)))
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.items.iter().all(|i| i.result.is_ok())
+    }
+}
+
+/// Runs `f` over `items` with at most `concurrency` calls in flight.
+pub(crate) async fn run<I, T, F, Fut>(items: I, concurrency: usize, f: F) -> BulkReport<T>
+    where
+        I: IntoIterator,
+        F: Fn(I::Item) -> (String, Fut),
+        Fut: Future<Output = UCRSResult<T>> {
+    // Unordered so one slow item doesn't hold back the rest; the index puts
+    // the report back in input order.
+    let mut items: Vec<(usize, BulkItem<T>)> = stream::iter(items.into_iter().enumerate())
+        .map(|(i, item)| {
+            let (key, fut) = f(item);
+            async move {
+                (i, BulkItem {
+                    key,
+                    result: fut.await
+                })
+            }
+        })
+        .buffer_unordered(concurrency.max(1))
+        .collect()
+        .await;
+    items.sort_by_key(|(i, _)| *i);
+    BulkReport { items: items.into_iter().map(|(_, item)| item).collect() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_run_keeps_order_and_bounds_concurrency() {
+        let in_flight = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+
+        let report = run(0..10u64, 3, |i| {
+            let (in_flight, peak) = (&in_flight, &peak);
+            (i.to_string(), async move {
+                let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(10 - i)).await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                if i % 4 == 0 {
+                    Err(UCRSError::TableNotFound(i.to_string()))
+                } else {
+                    Ok(i * 2)
+                }
+            })
+        }).await;
+
+        assert_eq!(peak.load(Ordering::SeqCst), 3);
+        assert!(!report.is_success());
+        assert_eq!(report.items.iter().map(|i| i.key.as_str()).collect::<Vec<_>>(),
+            vec!["0", "1", "2", "3", "4", "5", "6", "7", "8", "9"]);
+        assert_eq!(report.failures().map(|(k, _)| k).collect::<Vec<_>>(), vec!["0", "4", "8"]);
+        assert_eq!(report.successes().map(|(_, v)| *v).sum::<u64>(), 2 * (1 + 2 + 3 + 5 + 6 + 7 + 9));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_slow_item_does_not_block_others() {
+        let finished = std::sync::Mutex::new(Vec::new());
+        let report = run(0..6u64, 2, |i| {
+            let finished = &finished;
+            (i.to_string(), async move {
+                tokio::time::sleep(Duration::from_millis(if i == 0 { 100 } else { 1 })).await;
+                finished.lock().unwrap().push(i);
+                Ok::<_, UCRSError>(i)
+            })
+        }).await;
+
+        assert_eq!(*finished.lock().unwrap(), vec![1, 2, 3, 4, 5, 0]);
+        assert_eq!(report.successes().map(|(_, v)| *v).collect::<Vec<_>>(), vec![0, 1, 2, 3, 4, 5]);
+    }
+}
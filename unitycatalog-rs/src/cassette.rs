@@ -0,0 +1,467 @@
+use crate::errors::{UCRSError, UCRSResult};
+use crate::middleware::{Middleware, RequestContext, ResponseContext};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+const REDACTED: &str = "REDACTED";
+const DEFAULT_REDACTED_HEADERS: [&str; 4] = ["authorization", "proxy-authorization", "cookie", "set-cookie"];
+const SECRET_KEY_MARKERS: [&str; 5] = ["password", "secret", "token", "private_key", "key_json"];
+
+/// Whether a field or option name looks like it holds a secret.
+pub fn is_secret_key(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    SECRET_KEY_MARKERS.iter().any(|m| key.contains(m))
+}
+
+/// A recorded sequence of request/response pairs, stored as JSON.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Cassette {
+    pub interactions: Vec<Interaction>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Interaction {
+    pub request: RecordedRequest,
+    pub response: RecordedResponse
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RecordedRequest {
+    pub method: String,
+    /// Path and query; the host is left out so cassettes replay against any server.
+    pub path: String,
+    pub headers: BTreeMap<String, String>,
+    pub body: Option<String>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RecordedResponse {
+    pub status: u16,
+    pub headers: BTreeMap<String, String>,
+    pub body: String
+}
+
+impl Cassette {
+    pub fn load(path: impl AsRef<Path>) -> UCRSResult<Self> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(UCRSError::CassetteIOError)?;
+        serde_json::from_str(&contents)
+            .map_err(UCRSError::JSONParsingError)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> UCRSResult<()> {
+        let contents = serde_json::to_string_pretty(self)
+            .map_err(UCRSError::JSONFormattingError)?;
+        if let Some(parent) = path.as_ref().parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(UCRSError::CassetteIOError)?;
+        }
+        std::fs::write(path, contents)
+            .map_err(UCRSError::CassetteIOError)
+    }
+}
+
+fn path_and_query(request: &RequestContext) -> String {
+    match request.url.query() {
+        Some(query) => format!("{}?{}", request.url.path(), query),
+        None => request.url.path().to_owned()
+    }
+}
+
+fn record_headers(headers: &HeaderMap, redact: &[String]) -> BTreeMap<String, String> {
+    headers.iter()
+        .map(|(name, value)| {
+            let value = if redact.iter().any(|r| r == name.as_str()) {
+                REDACTED.to_owned()
+            } else {
+                String::from_utf8_lossy(value.as_bytes()).into_owned()
+            };
+            (name.as_str().to_owned(), value)
+        })
+        .collect()
+}
+
+/// Whether a JSON body field holds a secret: anything named like one, bar
+/// pagination tokens, or listed explicitly.
+fn is_secret_field(key: &str, redact: &[String]) -> bool {
+    let key = key.to_ascii_lowercase();
+    redact.contains(&key) || (is_secret_key(&key) && !key.ends_with("page_token"))
+}
+
+fn redact_value(value: &mut serde_json::Value, redact: &[String]) {
+    match value {
+        serde_json::Value::Object(fields) => fields.iter_mut().for_each(|(key, value)| {
+            // Only scalars are replaced, so the body keeps its shape and still
+            // parses when it is replayed.
+            if value.is_string() && is_secret_field(key, redact) {
+                *value = serde_json::Value::String(REDACTED.to_owned());
+            } else {
+                redact_value(value, redact);
+            }
+        }),
+        serde_json::Value::Array(items) => items.iter_mut().for_each(|v| redact_value(v, redact)),
+        _ => {}
+    }
+}
+
+/// Redacts secret fields of a JSON body; other bodies are kept as they are.
+fn record_body(body: &str, redact: &[String]) -> String {
+    match serde_json::from_str::<serde_json::Value>(body) {
+        Ok(mut value) => {
+            redact_value(&mut value, redact);
+            value.to_string()
+        },
+        Err(_) => body.to_owned()
+    }
+}
+
+/// Middleware that records every request/response pair the client sees.
+/// Sensitive headers and JSON body fields are replaced with `REDACTED` before
+/// they are stored.
+pub struct Recorder {
+    path: PathBuf,
+    redact: Vec<String>,
+    redact_fields: Vec<String>,
+    cassette: Mutex<Cassette>
+}
+
+impl Recorder {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            redact: DEFAULT_REDACTED_HEADERS.iter().map(|h| h.to_string()).collect(),
+            redact_fields: vec![],
+            cassette: Mutex::new(Cassette::default())
+        }
+    }
+
+    pub fn redact_header(mut self, name: &str) -> Self {
+        self.redact.push(name.to_ascii_lowercase());
+        self
+    }
+
+    /// Redacts JSON body fields with this name, in requests and responses,
+    /// on top of those that look like secrets.
+    pub fn redact_body_field(mut self, name: &str) -> Self {
+        self.redact_fields.push(name.to_ascii_lowercase());
+        self
+    }
+
+    pub fn cassette(&self) -> Cassette {
+        self.cassette.lock().unwrap().clone()
+    }
+
+    pub fn save(&self) -> UCRSResult<()> {
+        self.cassette().save(&self.path)
+    }
+}
+
+impl Middleware for Recorder {
+    fn on_response(&self, request: &RequestContext, response: &ResponseContext) {
+        let interaction = Interaction {
+            request: RecordedRequest {
+                method: request.method.to_string(),
+                path: path_and_query(request),
+                headers: record_headers(&request.headers, &self.redact),
+                body: request.body.as_deref().map(|b| record_body(b, &self.redact_fields))
+            },
+            response: RecordedResponse {
+                status: response.status.as_u16(),
+                headers: record_headers(&response.headers, &self.redact),
+                body: record_body(&response.body, &self.redact_fields)
+            }
+        };
+        self.cassette.lock().unwrap().interactions.push(interaction);
+    }
+}
+
+/// Which parts of a request must agree with a recorded one for it to replay.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MatchOn {
+    pub method: bool,
+    pub path: bool,
+    pub body: bool
+}
+
+impl Default for MatchOn {
+    fn default() -> Self {
+        Self {
+            method: true,
+            path: true,
+            body: true
+        }
+    }
+}
+
+/// Middleware that answers requests from a cassette instead of the network.
+///
+/// Each recorded interaction is served at most once, in recording order, so a
+/// cassette can hold several different answers to the same request.
+pub struct Replayer {
+    cassette: Cassette,
+    match_on: MatchOn,
+    used: Mutex<Vec<bool>>
+}
+
+impl Replayer {
+    pub fn new(cassette: Cassette) -> Self {
+        let used = Mutex::new(vec![false; cassette.interactions.len()]);
+        Self {
+            cassette,
+            match_on: MatchOn::default(),
+            used
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> UCRSResult<Self> {
+        Cassette::load(path).map(Self::new)
+    }
+
+    pub fn match_on(mut self, match_on: MatchOn) -> Self {
+        self.match_on = match_on;
+        self
+    }
+
+    fn matches(&self, recorded: &RecordedRequest, request: &RequestContext) -> bool {
+        (!self.match_on.method || recorded.method == request.method.as_str())
+            && (!self.match_on.path || recorded.path == path_and_query(request))
+            && (!self.match_on.body || bodies_match(recorded.body.as_deref(), request.body.as_deref()))
+    }
+}
+
+/// A redacted field in a recording matches whatever value is sent now.
+fn values_match(recorded: &serde_json::Value, actual: &serde_json::Value) -> bool {
+    use serde_json::Value;
+    match (recorded, actual) {
+        (Value::String(r), Value::String(_)) if r == REDACTED => true,
+        (Value::Object(r), Value::Object(a)) => r.len() == a.len()
+            && r.iter().all(|(k, r)| a.get(k).is_some_and(|a| values_match(r, a))),
+        (Value::Array(r), Value::Array(a)) => r.len() == a.len()
+            && r.iter().zip(a).all(|(r, a)| values_match(r, a)),
+        (r, a) => r == a
+    }
+}
+
+fn bodies_match(recorded: Option<&str>, actual: Option<&str>) -> bool {
+    match (recorded, actual) {
+        (Some(recorded), Some(actual)) => {
+            // Compare as JSON where possible so key order doesn't matter.
+            match (serde_json::from_str::<serde_json::Value>(recorded), serde_json::from_str::<serde_json::Value>(actual)) {
+                (Ok(recorded), Ok(actual)) => values_match(&recorded, &actual),
+                _ => recorded == actual
+            }
+        },
+        (recorded, actual) => recorded == actual
+    }
+}
+
+impl Middleware for Replayer {
+    fn respond(&self, request: &RequestContext) -> Option<UCRSResult<ResponseContext>> {
+        let mut used = self.used.lock().unwrap();
+        let found = self.cassette.interactions.iter()
+            .enumerate()
+            .find(|(i, interaction)| !used[*i] && self.matches(&interaction.request, request));
+
+        let Some((i, interaction)) = found else {
+            return Some(Err(UCRSError::CassetteMismatch(format!("{} {}", request.method, path_and_query(request)))));
+        };
+        used[i] = true;
+
+        let recorded = &interaction.response;
+        let status = match StatusCode::from_u16(recorded.status) {
+            Ok(status) => status,
+            Err(_) => return Some(Err(UCRSError::CassetteMismatch(format!("invalid status {}", recorded.status))))
+        };
+        let headers = recorded.headers.iter()
+            .filter_map(|(name, value)| Some((
+                HeaderName::from_bytes(name.as_bytes()).ok()?,
+                HeaderValue::from_str(value).ok()?
+            )))
+            .collect();
+        Some(Ok(ResponseContext {
+            status,
+            headers,
+            body: recorded.body.clone()
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::oss::api::catalogs::{CatalogsClient, CreateCatalogBuilder};
+    use crate::oss::api::connections::{ConnectionOptions, ConnectionType, ConnectionsClient, CreateConnectionBuilder};
+    use crate::oss::api::temporary_credentials::{TableOperation, TemporaryCredentialsClient};
+    use crate::request::RequestClient;
+    use crate::testing::test_utils::temp_dir;
+    use std::sync::Arc;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    struct Auth;
+
+    impl Middleware for Auth {
+        fn on_request(&self, request: &mut reqwest::Request) -> UCRSResult<()> {
+            request.headers_mut().insert("authorization", HeaderValue::from_static("Bearer secret"));
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_record_and_replay() -> UCRSResult<()> {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/2.1/unity-catalog/catalogs"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"name":"mycatalog","id":"1"}"#))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/catalogs"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"catalogs":[{"name":"mycatalog"}]}"#))
+            .mount(&server)
+            .await;
+
+        let dir = temp_dir();
+        let cassette_path = dir.path().join("record_and_replay.json");
+        let recorder = Arc::new(Recorder::new(&cassette_path));
+        let rc = RequestClient::new(&server.uri(), true)?
+            .with_middleware(Auth)
+            .with_middleware(recorder.clone());
+        let client = CatalogsClient::new(&rc);
+        let create = || CreateCatalogBuilder::default()
+            .name("mycatalog".to_owned())
+            .comment(None)
+            .properties(None)
+            .build()
+            .unwrap();
+        let created = client.create(create()).await?;
+        let listed = client.list(None, Some(10)).await?;
+        recorder.save()?;
+
+        let cassette = Cassette::load(&cassette_path)?;
+        assert_eq!(cassette.interactions.len(), 2);
+        assert_eq!(cassette.interactions[0].request.headers["authorization"], REDACTED);
+        assert_eq!(cassette.interactions[1].request.path, "/api/2.1/unity-catalog/catalogs?max_results=10");
+
+        let offline = RequestClient::new("http://localhost:1", true)?
+            .with_middleware(Replayer::load(&cassette_path)?);
+        let client = CatalogsClient::new(&offline);
+        assert_eq!(client.create(create()).await?, created);
+        assert_eq!(client.list(None, Some(10)).await?, listed);
+
+        // Each interaction replays once.
+        let res = client.list(None, Some(10)).await;
+        assert!(matches!(res, Err(UCRSError::CassetteMismatch(ref m)) if m == "GET /api/2.1/unity-catalog/catalogs?max_results=10"));
+
+        // A different query doesn't match.
+        let offline = RequestClient::new("http://localhost:1", true)?
+            .with_middleware(Replayer::load(&cassette_path)?);
+        let res = CatalogsClient::new(&offline).list(None, Some(20)).await;
+        assert!(matches!(res, Err(UCRSError::CassetteMismatch(ref m)) if m == "GET /api/2.1/unity-catalog/catalogs?max_results=20"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_secrets_are_redacted() -> UCRSResult<()> {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/2.1/unity-catalog/temporary-table-credentials"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "aws_temp_credentials": {
+                    "access_key_id": "AKIA1",
+                    "secret_access_key": "hunter2",
+                    "session_token": "hunter3"
+                },
+                "expiration_time": 1700000000000i64
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/2.1/unity-catalog/connections"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "name": "pg",
+                "connection_type": "POSTGRESQL",
+                "options": {"host": "db.internal", "user": "admin"}
+            })))
+            .mount(&server)
+            .await;
+
+        let dir = temp_dir();
+        let cassette_path = dir.path().join("secrets.json");
+        let recorder = Arc::new(Recorder::new(&cassette_path).redact_body_field("host"));
+        let rc = RequestClient::new(&server.uri(), true)?
+            .with_middleware(recorder.clone());
+        let credentials = TemporaryCredentialsClient::new(&rc).table("t1", TableOperation::READ).await?;
+        let connection = || CreateConnectionBuilder::default()
+            .name("pg".to_owned())
+            .connection_type(ConnectionType::POSTGRESQL)
+            .options(ConnectionOptions::new().with("host", "db.internal").with("user", "admin").with("password", "hunter4"))
+            .build()
+            .unwrap();
+        ConnectionsClient::new(&rc).create(connection()).await?;
+        recorder.save()?;
+
+        let contents = std::fs::read_to_string(&cassette_path).map_err(UCRSError::CassetteIOError)?;
+        for secret in ["hunter2", "hunter3", "hunter4", "db.internal"] {
+            assert!(!contents.contains(secret), "{} was recorded", secret);
+        }
+        assert!(contents.contains("AKIA1"));
+        assert!(contents.contains("admin"));
+
+        // Redacted recordings still replay, whatever the secret sent now is.
+        let offline = RequestClient::new("http://localhost:1", true)?
+            .with_middleware(Replayer::load(&cassette_path)?);
+        let replayed = TemporaryCredentialsClient::new(&offline).table("t1", TableOperation::READ).await?;
+        assert_eq!(replayed.aws_temp_credentials().unwrap().secret_access_key(), REDACTED);
+        assert_eq!(replayed.expiration_time(), credentials.expiration_time());
+        ConnectionsClient::new(&offline).create(connection()).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_match_on_body() -> UCRSResult<()> {
+        let recorded = |body: &str| Interaction {
+            request: RecordedRequest {
+                method: "POST".to_owned(),
+                path: "/api/2.1/unity-catalog/catalogs".to_owned(),
+                headers: BTreeMap::new(),
+                body: Some(body.to_owned())
+            },
+            response: RecordedResponse {
+                status: 200,
+                headers: BTreeMap::new(),
+                body: format!(r#"{{"comment":{}}}"#, serde_json::to_string(body).unwrap())
+            }
+        };
+        let cassette = Cassette {
+            interactions: vec![
+                recorded(r#"{"name":"a","comment":null,"properties":null}"#),
+                recorded(r#"{"name":"b","comment":null,"properties":null}"#)
+            ]
+        };
+        let create = |name: &str| CreateCatalogBuilder::default()
+            .name(name.to_owned())
+            .comment(None)
+            .properties(None)
+            .build()
+            .unwrap();
+
+        let rc = RequestClient::new("http://localhost:1", true)?
+            .with_middleware(Replayer::new(cassette.clone()));
+        let client = CatalogsClient::new(&rc);
+        let b = client.create(create("b")).await?;
+        assert_eq!(serde_json::to_value(&b).unwrap()["comment"], r#"{"name":"b","comment":null,"properties":null}"#);
+
+        let rc = RequestClient::new("http://localhost:1", true)?
+            .with_middleware(Replayer::new(cassette).match_on(MatchOn { body: false, ..Default::default() }));
+        let client = CatalogsClient::new(&rc);
+        let b = client.create(create("b")).await?;
+        assert_eq!(serde_json::to_value(&b).unwrap()["comment"], r#"{"name":"a","comment":null,"properties":null}"#);
+        Ok(())
+    }
+}
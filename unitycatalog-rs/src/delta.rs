@@ -0,0 +1,164 @@
+use crate::errors::{UCRSError, UCRSResult};
+use crate::oss::api::tables::{DataSourceFormat, TableInfo, TablesClient};
+use crate::oss::api::temporary_credentials::TableOperation;
+use crate::refresh::CredentialCache;
+use crate::request::RequestClient;
+use crate::store::{table_location, table_store};
+use deltalake::logstore::{default_logstore, logstore_factories, LogStoreFactory, LogStoreRef, ObjectStoreRef, StorageConfig};
+use deltalake::{DeltaResult, DeltaTable, DeltaTableBuilder, DeltaVersion};
+use object_store::ObjectStore;
+use std::sync::{Arc, Once};
+use url::Url;
+
+/// Tables are opened through stores registered under `unity://<table_id>/`,
+/// so tables sharing a bucket never share credentials.
+const STORE_SCHEME: &str = "unity";
+
+/// Opens the Delta table `full_name` using credentials vended once for
+/// `operation`. They are not refreshed, so long-lived handles should come
+/// from [`open_table_refreshing`] instead.
+pub async fn open_table(client: &RequestClient, full_name: &str, operation: TableOperation, version: DeltaVersion) -> UCRSResult<DeltaTable> {
+    let info = get_delta_table(client, full_name).await?;
+    let store = table_store(client, &info, operation).await?;
+    load(store, &info, version).await
+}
+
+/// Like [`open_table`], but the table's store renews its credentials through
+/// `credentials` before they expire.
+pub async fn open_table_refreshing(credentials: &Arc<CredentialCache>, full_name: &str, operation: TableOperation, version: DeltaVersion) -> UCRSResult<DeltaTable> {
+    let info = get_delta_table(credentials.client(), full_name).await?;
+    let store = credentials.table_store(&info, operation).await?;
+    load(store, &info, version).await
+}
+
+async fn get_delta_table(client: &RequestClient, full_name: &str) -> UCRSResult<TableInfo> {
+    let info = TablesClient::new(client).get(full_name).await?;
+    match info.data_source_format() {
+        Some(DataSourceFormat::DELTA) => Ok(info),
+        _ => Err(UCRSError::NotADeltaTable(full_name.to_owned()))
+    }
+}
+
+async fn load(store: Arc<dyn ObjectStore>, info: &TableInfo, version: DeltaVersion) -> UCRSResult<DeltaTable> {
+    let (table_id, _) = table_location(info)?;
+    let url = store_url(table_id)?;
+    load_from_store(store, &url, version).await.map_err(UCRSError::DeltaError)
+}
+
+/// The URL a table's store is registered under.
+pub(crate) fn store_url(table_id: &str) -> UCRSResult<Url> {
+    Url::parse(&format!("{}://{}/", STORE_SCHEME, table_id)).map_err(UCRSError::MalformedURL)
+}
+
+pub(crate) async fn load_from_store(store: Arc<dyn ObjectStore>, url: &Url, version: DeltaVersion) -> DeltaResult<DeltaTable> {
+    register_store_scheme();
+    let builder = DeltaTableBuilder::from_url(url.clone())?
+        .with_storage_backend(store, url.clone());
+    let builder = match version {
+        DeltaVersion::Newest => builder,
+        DeltaVersion::Version(version) => builder.with_version(version),
+        DeltaVersion::Timestamp(timestamp) => builder.with_timestamp(timestamp)
+    };
+    builder.load().await
+}
+
+#[derive(Debug, Clone, Default)]
+struct UnityLogStoreFactory;
+
+impl LogStoreFactory for UnityLogStoreFactory {
+    fn with_options(&self, prefixed_store: ObjectStoreRef, root_store: ObjectStoreRef, location: &Url, options: &StorageConfig) -> DeltaResult<LogStoreRef> {
+        Ok(default_logstore(prefixed_store, root_store, location, options))
+    }
+}
+
+/// delta-rs only opens locations whose scheme has a registered log store.
+fn register_store_scheme() {
+    static REGISTER: Once = Once::new();
+    REGISTER.call_once(|| {
+        let scheme = Url::parse(&format!("{}://", STORE_SCHEME)).unwrap();
+        logstore_factories().insert(scheme, Arc::new(UnityLogStoreFactory));
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::test_utils::{mount_responses, temp_dir};
+    use chrono::Utc;
+    use deltalake::kernel::DataType;
+    use serde_json::json;
+    use std::collections::HashMap;
+    use std::time::Duration;
+    use wiremock::MockServer;
+
+    async fn mock_tables(location: &Url) -> MockServer {
+        let server = MockServer::start().await;
+        mount_responses(&server, [
+            ("GET", "/api/2.1/unity-catalog/tables/unity.default.orders", json!({
+                "name": "orders",
+                "table_id": "t-orders",
+                "data_source_format": "DELTA",
+                "storage_location": location.as_str()
+            })),
+            ("GET", "/api/2.1/unity-catalog/tables/unity.default.events", json!({
+                "name": "events",
+                "table_id": "t-events",
+                "data_source_format": "PARQUET",
+                "storage_location": location.as_str()
+            })),
+            ("POST", "/api/2.1/unity-catalog/temporary-table-credentials", json!({}))
+        ]).await;
+        server
+    }
+
+    async fn create_table(location: &Url) -> DeltaTable {
+        DeltaTable::try_from_url(location.clone()).await.unwrap()
+            .create()
+            .with_column("id", DataType::LONG, false, None)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_open_table() -> UCRSResult<()> {
+        let dir = temp_dir();
+        let location = Url::from_directory_path(dir.path()).unwrap();
+        create_table(&location).await;
+        let server = mock_tables(&location).await;
+
+        let rc = RequestClient::new(&server.uri(), true)?;
+        let table = open_table(&rc, "unity.default.orders", TableOperation::READ, DeltaVersion::Newest).await?;
+        assert_eq!(table.version(), Some(0));
+        let res = open_table(&rc, "unity.default.orders", TableOperation::READ, DeltaVersion::Version(1)).await;
+        assert!(matches!(res, Err(UCRSError::DeltaError(_))));
+        let res = open_table(&rc, "unity.default.events", TableOperation::READ, DeltaVersion::Newest).await;
+        assert!(matches!(res, Err(UCRSError::NotADeltaTable(ref name)) if name == "unity.default.events"));
+
+        let credentials = Arc::new(CredentialCache::new(Arc::new(rc)));
+        let table = open_table_refreshing(&credentials, "unity.default.orders", TableOperation::READ, DeltaVersion::Version(0)).await?;
+        assert_eq!(table.version(), Some(0));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_open_table_at_timestamp() -> UCRSResult<()> {
+        let dir = temp_dir();
+        let location = Url::from_directory_path(dir.path()).unwrap();
+        let table = create_table(&location).await;
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let between = Utc::now();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        table.set_tbl_properties()
+            .with_properties(HashMap::from([("delta.appendOnly".to_owned(), "true".to_owned())]))
+            .await
+            .unwrap();
+        let server = mock_tables(&location).await;
+
+        let rc = RequestClient::new(&server.uri(), true)?;
+        let table = open_table(&rc, "unity.default.orders", TableOperation::READ, DeltaVersion::Timestamp(between)).await?;
+        assert_eq!(table.version(), Some(0));
+        let table = open_table(&rc, "unity.default.orders", TableOperation::READ, DeltaVersion::Timestamp(Utc::now())).await?;
+        assert_eq!(table.version(), Some(1));
+        Ok(())
+    }
+}
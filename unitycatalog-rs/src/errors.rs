@@ -13,7 +13,21 @@ pub enum UCRSError {
     #[error("Request error with response")]
     RequestErrorWithResponse(#[source] reqwest::Error, String),
     #[error("JSON Parsing error")]
-    JSONParsingError(#[source] reqwest::Error),
+    JSONParsingError(#[source] serde_json::Error),
+    #[error("Request rejected by middleware: {0}")]
+    MiddlewareError(String),
+    #[error("Error reading or writing cassette")]
+    CassetteIOError(#[source] std::io::Error),
+    #[error("No recorded interaction matches {0}")]
+    CassetteMismatch(String),
+    #[error("Invalid name pattern {0}: {1}")]
+    InvalidPattern(String, String),
+    #[error("Invalid definition for table {0}: {1}")]
+    InvalidTableDefinition(String, String),
+    #[error("Invalid constraint {0}: {1}")]
+    InvalidConstraint(String, String),
+    #[error("Unsupported backup format version {0}")]
+    UnsupportedBackupVersion(u32),
     #[error("Duplicate Catalog name")]
     DuplicateCatalogName(String),
     #[error("Duplicate Schema")]
@@ -25,7 +39,53 @@ pub enum UCRSError {
     #[error("Schema not found")]
     SchemaNotFound(String),
     #[error("Table not found")]
-    TableNotFound(String)
+    TableNotFound(String),
+    #[error("Function not found")]
+    FunctionNotFound(String),
+    #[error("Volume not found")]
+    VolumeNotFound(String),
+    #[error("Duplicate storage credential name")]
+    DuplicateStorageCredentialName(String),
+    #[error("Storage credential not found")]
+    StorageCredentialNotFound(String),
+    #[error("Duplicate external location name")]
+    DuplicateExternalLocationName(String),
+    #[error("External location not found")]
+    ExternalLocationNotFound(String),
+    #[error("Duplicate connection name")]
+    DuplicateConnectionName(String),
+    #[error("Connection not found")]
+    ConnectionNotFound(String),
+    #[error("{0} is not served by this server flavor")]
+    UnsupportedByFlavor(String),
+    #[cfg(feature = "object-store")]
+    #[error("No usable storage location for {0}")]
+    UnsupportedStorageLocation(String),
+    #[cfg(feature = "object-store")]
+    #[error("No temporary credentials vended for {0}")]
+    MissingTemporaryCredentials(String),
+    #[cfg(feature = "object-store")]
+    #[error("Object store error")]
+    ObjectStoreError(#[source] object_store::Error),
+    #[cfg(feature = "deltalake")]
+    #[error("{0} is not a Delta table")]
+    NotADeltaTable(String),
+    #[cfg(feature = "deltalake")]
+    #[error("Delta table error")]
+    DeltaError(#[source] deltalake::DeltaTableError),
+    #[cfg(feature = "parquet")]
+    #[error("Parquet error")]
+    ParquetError(#[source] parquet::errors::ParquetError)
 }
 
-pub type UCRSResult<T> = Result<T, UCRSError>;
\ No newline at end of file
+pub type UCRSResult<T> = Result<T, UCRSError>;
+
+impl UCRSError {
+    /// HTTP status of the failed response, if the server sent one.
+    pub fn status(&self) -> Option<reqwest::StatusCode> {
+        match self {
+            UCRSError::RequestError(e) | UCRSError::RequestErrorWithResponse(e, _) => e.status(),
+            _ => None
+        }
+    }
+}
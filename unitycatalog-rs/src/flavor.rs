@@ -0,0 +1,122 @@
+use crate::errors::{UCRSError, UCRSResult};
+use url::Url;
+
+/// Which Unity Catalog implementation a `RequestClient` talks to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Flavor {
+    /// The open source server. DELETE answers with a plain-text `200 OK`,
+    /// lists page by default, and tags aren't served.
+    #[default]
+    Oss,
+    /// Databricks-hosted Unity Catalog. DELETE answers with an empty JSON
+    /// object, and lists only page when `max_results` is sent.
+    Databricks
+}
+
+const TAGS_ROUTE: &str = "/api/2.1/unity-catalog/entity-tag-assignments";
+
+impl Flavor {
+    /// Adds the paging query parameters for a list call.
+    pub(crate) fn page(&self, url: &mut Url, page_token: Option<String>, max_results: Option<i32>) {
+        if let Some(token) = page_token {
+            url.query_pairs_mut().append_pair("page_token", &token);
+        }
+        // Without max_results Databricks returns every object in one
+        // (deprecated) unpaged response; 0 asks for the server's page size.
+        let max_results = match self {
+            Flavor::Oss => max_results,
+            Flavor::Databricks => Some(max_results.unwrap_or(0))
+        };
+        if let Some(max_results) = max_results {
+            url.query_pairs_mut().append_pair("max_results", &max_results.to_string());
+        }
+    }
+
+    /// Checks the body of a successful DELETE. Anything but the flavor's
+    /// acknowledgement, an empty body or JSON, such as an HTML page from a
+    /// proxy, is rejected.
+    pub(crate) fn check_delete_body(&self, body: &str) -> UCRSResult<()> {
+        match self {
+            Flavor::Oss if body.trim() == "200 OK" => Ok(()),
+            _ if body.trim().is_empty() => Ok(()),
+            _ => serde_json::from_str::<serde_json::Value>(body)
+                .map(|_| ())
+                .map_err(UCRSError::JSONParsingError)
+        }
+    }
+
+    /// The base route of tag assignments.
+    pub(crate) fn tags_route(&self) -> UCRSResult<&'static str> {
+        match self {
+            Flavor::Oss => Err(UCRSError::UnsupportedByFlavor("Tag assignment".to_owned())),
+            Flavor::Databricks => Ok(TAGS_ROUTE)
+        }
+    }
+
+    /// Whether update bodies carry the unknown fields read from the server.
+    /// The OSS server only accepts the fields this crate models.
+    pub(crate) fn sends_extra_fields(&self) -> bool {
+        matches!(self, Flavor::Databricks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::oss::api::catalogs::CatalogsClient;
+    use crate::request::RequestClient;
+    use wiremock::matchers::{method, path, query_param, query_param_is_missing};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_delete_bodies() -> UCRSResult<()> {
+        let server = MockServer::start().await;
+        for (name, body) in [("oss", "200 OK"), ("databricks", "{}"), ("empty", ""), ("broken", "<html>")] {
+            Mock::given(method("DELETE"))
+                .and(path(format!("/api/2.1/unity-catalog/catalogs/{}", name)))
+                .respond_with(ResponseTemplate::new(200).set_body_string(body))
+                .mount(&server)
+                .await;
+        }
+
+        let oss = RequestClient::new(&server.uri(), true)?;
+        let catalogs = CatalogsClient::new(&oss);
+        catalogs.delete("oss", false).await?;
+        catalogs.delete("empty", false).await?;
+        assert!(matches!(catalogs.delete("broken", false).await, Err(UCRSError::JSONParsingError(_))));
+
+        let databricks = RequestClient::new(&server.uri(), true)?.with_flavor(Flavor::Databricks);
+        let catalogs = CatalogsClient::new(&databricks);
+        catalogs.delete("databricks", false).await?;
+        catalogs.delete("empty", false).await?;
+        assert!(matches!(catalogs.delete("broken", false).await, Err(UCRSError::JSONParsingError(_))));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_paging() -> UCRSResult<()> {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/catalogs"))
+            .and(query_param_is_missing("max_results"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"catalogs": [{"name": "oss"}]})))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/catalogs"))
+            .and(query_param("max_results", "0"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "catalogs": [{"name": "databricks"}],
+                "next_page_token": ""
+            })))
+            .mount(&server)
+            .await;
+
+        let oss = RequestClient::new(&server.uri(), true)?;
+        let names = |c: Vec<crate::oss::api::catalogs::CatalogInfo>| c.iter().map(|c| c.name().unwrap().to_owned()).collect::<Vec<_>>();
+        assert_eq!(names(CatalogsClient::new(&oss).list_all().await?), vec!["oss"]);
+        let databricks = RequestClient::new(&server.uri(), true)?.with_flavor(Flavor::Databricks);
+        assert_eq!(names(CatalogsClient::new(&databricks).list_all().await?), vec!["databricks"]);
+        Ok(())
+    }
+}
@@ -0,0 +1,465 @@
+use crate::errors::{UCRSError, UCRSResult};
+use crate::oss::api::tables::{ColumnInfo, ColumnTypeName, CreateTable, DataSourceFormat, TableType, TablesClient};
+use arrow_schema::{DataType, Field, Fields};
+use futures_util::{StreamExt, TryStreamExt};
+use object_store::local::LocalFileSystem;
+use object_store::path::Path;
+use object_store::{ObjectMeta, ObjectStore, ObjectStoreExt};
+use parquet::arrow::parquet_to_arrow_schema;
+use parquet::file::metadata::{FooterTail, ParquetMetaDataReader};
+use parquet::file::FOOTER_SIZE;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use url::Url;
+
+const HIVE_DEFAULT_PARTITION: &str = "__HIVE_DEFAULT_PARTITION__";
+const CONCURRENT_FOOTER_READS: usize = 16;
+/// Spark's integral types, narrowest first.
+const INTEGRAL: [&str; 4] = ["byte", "short", "integer", "long"];
+const MAX_DECIMAL_PRECISION: i32 = 38;
+
+/// Builds an external `PARQUET` table from the footers of every `.parquet`
+/// file under `store`, which must be rooted at `storage_location`.
+/// Columns missing from some files become nullable, and `key=value`
+/// directories become partition columns after the data columns.
+pub async fn infer_parquet_table(store: &Arc<dyn ObjectStore>, storage_location: &str, catalog_name: &str, schema_name: &str, name: &str) -> UCRSResult<CreateTable> {
+    let full_name = TablesClient::full_name(catalog_name, schema_name, name);
+    let invalid = |reason: String| UCRSError::InvalidTableDefinition(full_name.clone(), reason);
+
+    let mut files = store.list(None)
+        .try_filter(|meta| std::future::ready(is_data_file(&meta.location)))
+        .try_collect::<Vec<_>>()
+        .await
+        .map_err(UCRSError::ObjectStoreError)?;
+    if files.is_empty() {
+        return Err(invalid(format!("no Parquet files under {}", storage_location)));
+    }
+    // Listing order isn't specified; sorting keeps the column order stable.
+    files.sort_by(|a, b| a.location.as_ref().cmp(b.location.as_ref()));
+
+    let partitions = partition_columns(&files).map_err(invalid)?;
+    let schemas = futures_util::stream::iter(&files)
+        .map(|meta| read_schema(store, meta))
+        .buffered(CONCURRENT_FOOTER_READS)
+        .try_collect::<Vec<_>>()
+        .await?;
+    let mut fields: Option<Vec<Value>> = None;
+    for schema in schemas {
+        let schema = schema.map_err(invalid)?;
+        fields = Some(match fields {
+            Some(fields) => merge_fields(&fields, &schema).map_err(invalid)?,
+            None => schema
+        });
+    }
+    let fields = fields.unwrap_or_default();
+
+    let mut columns = Vec::with_capacity(fields.len() + partitions.len());
+    for field in &fields {
+        if partitions.iter().any(|p| p["name"] == field["name"]) {
+            return Err(invalid(format!("partition column {} is also stored in the data files", field["name"].as_str().unwrap_or_default())));
+        }
+        columns.push(column_info(field, columns.len(), None));
+    }
+    for (index, field) in partitions.iter().enumerate() {
+        columns.push(column_info(field, columns.len(), Some(index)));
+    }
+
+    Ok(CreateTable::builder()
+        .name(name.to_owned())
+        .catalog_name(catalog_name.to_owned())
+        .schema_name(schema_name.to_owned())
+        .table_type(TableType::EXTERNAL)
+        .data_source_format(DataSourceFormat::PARQUET)
+        .columns(columns)
+        .storage_location(Some(storage_location.to_owned()))
+        .build()
+        .expect("all required fields set"))
+}
+
+/// [`infer_parquet_table`] for a directory on the local filesystem.
+pub async fn infer_local_parquet_table(path: &std::path::Path, catalog_name: &str, schema_name: &str, name: &str) -> UCRSResult<CreateTable> {
+    let store: Arc<dyn ObjectStore> = Arc::new(LocalFileSystem::new_with_prefix(path).map_err(UCRSError::ObjectStoreError)?);
+    let location = Url::from_directory_path(path)
+        .map_err(|_| UCRSError::UnsupportedStorageLocation(path.display().to_string()))?;
+    infer_parquet_table(&store, location.as_str(), catalog_name, schema_name, name).await
+}
+
+/// Whether Spark ignores this path segment: `_SUCCESS`, `.crc` files,
+/// `_delta_log`, `_temporary`, `.spark-staging` and the like.
+fn is_hidden(part: &str) -> bool {
+    part.starts_with(['_', '.']) && !part.contains('=')
+}
+
+fn is_data_file(location: &Path) -> bool {
+    !location.parts().any(|part| is_hidden(part.as_ref())) && location.as_ref().ends_with(".parquet")
+}
+
+/// Reads just the footer: the trailing length and magic, then the metadata.
+async fn read_schema(store: &Arc<dyn ObjectStore>, meta: &ObjectMeta) -> UCRSResult<Result<Vec<Value>, String>> {
+    let not_parquet = || Ok(Err(format!("{} is not a Parquet file", meta.location)));
+    let Some(footer_start) = meta.size.checked_sub(FOOTER_SIZE as u64) else { return not_parquet() };
+    let tail = store.get_range(&meta.location, footer_start..meta.size).await.map_err(UCRSError::ObjectStoreError)?;
+    let Ok(footer) = FooterTail::try_from(tail.as_ref()) else { return not_parquet() };
+    let Some(metadata_start) = footer_start.checked_sub(footer.metadata_length() as u64) else { return not_parquet() };
+    let bytes = store.get_range(&meta.location, metadata_start..footer_start).await.map_err(UCRSError::ObjectStoreError)?;
+    let metadata = ParquetMetaDataReader::decode_metadata(&bytes).map_err(UCRSError::ParquetError)?;
+    let file = metadata.file_metadata();
+    let schema = parquet_to_arrow_schema(file.schema_descr(), file.key_value_metadata()).map_err(UCRSError::ParquetError)?;
+    Ok(schema.fields().iter()
+        .map(|field| field_json(field))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("{} in {}", e, meta.location)))
+}
+
+/// Partition columns as Spark struct fields, in directory order. Files under
+/// hidden directories are skipped; every other file must sit under the same
+/// partition keys. As in Hive, partition columns are always nullable.
+fn partition_columns(files: &[ObjectMeta]) -> Result<Vec<Value>, String> {
+    let mut keys: Option<Vec<String>> = None;
+    let mut values: Vec<Vec<String>> = Vec::new();
+    for meta in files {
+        let parts = meta.location.parts().collect::<Vec<_>>();
+        if parts[..parts.len() - 1].iter().any(|part| is_hidden(part.as_ref())) {
+            continue;
+        }
+        let mut file_keys = Vec::new();
+        for (i, part) in parts[..parts.len() - 1].iter().enumerate() {
+            let Some((key, value)) = part.as_ref().split_once('=') else {
+                return Err(format!("{} is not a key=value partition directory", part.as_ref()));
+            };
+            file_keys.push(key.to_owned());
+            if values.len() <= i {
+                values.push(Vec::new());
+            }
+            values[i].push(value.to_owned());
+        }
+        match &keys {
+            Some(keys) if *keys != file_keys =>
+                return Err(format!("{} is not partitioned by {}", meta.location, keys.join(", "))),
+            Some(_) => {},
+            None => keys = Some(file_keys)
+        }
+    }
+    Ok(keys.unwrap_or_default().into_iter().zip(values)
+        .map(|(key, values)| json!({"name": key, "type": partition_type(&values), "nullable": true, "metadata": {}}))
+        .collect())
+}
+
+/// The narrowest of `integer`, `long`, `double` and `date` that fits every
+/// value, falling back to `string`.
+fn partition_type(values: &[String]) -> &'static str {
+    let date = regex::Regex::new(r"^\d{4}-\d{2}-\d{2}$").unwrap();
+    let values = values.iter().filter(|v| *v != HIVE_DEFAULT_PARTITION).collect::<Vec<_>>();
+    if values.is_empty() {
+        "string"
+    } else if values.iter().all(|v| v.parse::<i32>().is_ok()) {
+        "integer"
+    } else if values.iter().all(|v| v.parse::<i64>().is_ok()) {
+        "long"
+    } else if values.iter().all(|v| v.parse::<f64>().is_ok()) {
+        "double"
+    } else if values.iter().all(|v| date.is_match(v)) {
+        "date"
+    } else {
+        "string"
+    }
+}
+
+/// Columns are described with Spark's JSON schema, which is what
+/// `type_json` holds and what readers of the table parse.
+fn field_json(field: &Field) -> Result<Value, String> {
+    Ok(json!({
+        "name": field.name(),
+        "type": type_json(field.data_type()).map_err(|t| format!("column {} has unsupported type {}", field.name(), t))?,
+        "nullable": field.is_nullable(),
+        "metadata": {}
+    }))
+}
+
+fn struct_json(fields: &Fields) -> Result<Value, String> {
+    let fields = fields.iter().map(|f| field_json(f)).collect::<Result<Vec<_>, _>>()?;
+    Ok(json!({"type": "struct", "fields": fields}))
+}
+
+fn type_json(data_type: &DataType) -> Result<Value, String> {
+    let name = match data_type {
+        DataType::Null => "null",
+        DataType::Boolean => "boolean",
+        DataType::Int8 => "byte",
+        DataType::Int16 | DataType::UInt8 => "short",
+        DataType::Int32 | DataType::UInt16 => "integer",
+        DataType::Int64 | DataType::UInt32 => "long",
+        DataType::UInt64 => "decimal(20,0)",
+        DataType::Float16 | DataType::Float32 => "float",
+        DataType::Float64 => "double",
+        DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View => "string",
+        DataType::Binary | DataType::LargeBinary | DataType::BinaryView | DataType::FixedSizeBinary(_) => "binary",
+        DataType::Date32 | DataType::Date64 => "date",
+        DataType::Timestamp(_, Some(_)) => "timestamp",
+        DataType::Timestamp(_, None) => "timestamp_ntz",
+        DataType::Decimal32(p, s) | DataType::Decimal64(p, s) => return Ok(json!(format!("decimal({},{})", p, s))),
+        DataType::Decimal128(p, s) | DataType::Decimal256(p, s) => return Ok(json!(format!("decimal({},{})", p, s))),
+        DataType::List(element) | DataType::LargeList(element) | DataType::FixedSizeList(element, _) => return Ok(json!({
+            "type": "array",
+            "elementType": type_json(element.data_type())?,
+            "containsNull": element.is_nullable()
+        })),
+        DataType::Struct(fields) => return struct_json(fields),
+        DataType::Map(entries, _) => match entries.data_type() {
+            DataType::Struct(kv) if kv.len() == 2 => return Ok(json!({
+                "type": "map",
+                "keyType": type_json(kv[0].data_type())?,
+                "valueType": type_json(kv[1].data_type())?,
+                "valueContainsNull": kv[1].is_nullable()
+            })),
+            _ => return Err(data_type.to_string())
+        },
+        DataType::Dictionary(_, values) => return type_json(values),
+        _ => return Err(data_type.to_string())
+    };
+    Ok(json!(name))
+}
+
+/// Fields present in only one of `a` and `b` become nullable.
+fn merge_fields(a: &[Value], b: &[Value]) -> Result<Vec<Value>, String> {
+    let mut merged = Vec::with_capacity(a.len().max(b.len()));
+    for field in a {
+        match b.iter().find(|f| f["name"] == field["name"]) {
+            Some(other) => {
+                let data_type = merge_types(&field["type"], &other["type"])
+                    .ok_or_else(|| format!("column {} has conflicting types {} and {}", field["name"].as_str().unwrap_or_default(), type_text(&field["type"]), type_text(&other["type"])))?;
+                let nullable = field["nullable"] == true || other["nullable"] == true;
+                merged.push(json!({"name": field["name"], "type": data_type, "nullable": nullable, "metadata": {}}));
+            },
+            None => merged.push(nullable(field))
+        }
+    }
+    for field in b.iter().filter(|f| !a.iter().any(|m| m["name"] == f["name"])) {
+        merged.push(nullable(field));
+    }
+    Ok(merged)
+}
+
+fn nullable(field: &Value) -> Value {
+    let mut field = field.clone();
+    field["nullable"] = json!(true);
+    field
+}
+
+/// Widens the way Spark's schema merging does: integral types to the wider
+/// of the two, `float` to `double`, and decimals to a precision and scale
+/// that hold both.
+fn merge_types(a: &Value, b: &Value) -> Option<Value> {
+    if a == b || *b == "null" {
+        return Some(a.clone());
+    }
+    if *a == "null" {
+        return Some(b.clone());
+    }
+    if let (Some(a), Some(b)) = (a.as_str(), b.as_str()) {
+        return merge_primitives(a, b).map(Value::from);
+    }
+    let either = |key: &str| json!(a[key] == true || b[key] == true);
+    match (a["type"].as_str()?, b["type"].as_str()?) {
+        ("struct", "struct") => {
+            let fields = merge_fields(a["fields"].as_array()?, b["fields"].as_array()?).ok()?;
+            Some(json!({"type": "struct", "fields": fields}))
+        },
+        ("array", "array") => Some(json!({
+            "type": "array",
+            "elementType": merge_types(&a["elementType"], &b["elementType"])?,
+            "containsNull": either("containsNull")
+        })),
+        ("map", "map") => Some(json!({
+            "type": "map",
+            "keyType": merge_types(&a["keyType"], &b["keyType"])?,
+            "valueType": merge_types(&a["valueType"], &b["valueType"])?,
+            "valueContainsNull": either("valueContainsNull")
+        })),
+        _ => None
+    }
+}
+
+fn merge_primitives(a: &str, b: &str) -> Option<String> {
+    let integral = |t: &str| INTEGRAL.iter().position(|i| *i == t);
+    if let (Some(a), Some(b)) = (integral(a), integral(b)) {
+        return Some(INTEGRAL[a.max(b)].to_owned());
+    }
+    match (a, b) {
+        ("float", "double") | ("double", "float") => Some("double".to_owned()),
+        _ => {
+            let (p1, s1) = decimal_precision(&json!(a))?;
+            let (p2, s2) = decimal_precision(&json!(b))?;
+            let scale = s1.max(s2);
+            let precision = (p1 - s1).max(p2 - s2) + scale;
+            (precision <= MAX_DECIMAL_PRECISION).then(|| format!("decimal({},{})", precision, scale))
+        }
+    }
+}
+
+fn column_info(field: &Value, position: usize, partition_index: Option<usize>) -> ColumnInfo {
+    let data_type = &field["type"];
+    let (precision, scale) = decimal_precision(data_type).unwrap_or((0, 0));
+    let mut builder = ColumnInfo::builder();
+    builder
+        .name(field["name"].as_str().unwrap_or_default().to_owned())
+        .type_text(type_text(data_type))
+        .type_json(field.to_string())
+        .type_name(type_name(data_type))
+        .type_precision(precision)
+        .type_scale(scale)
+        .position(position as u32)
+        .nullable(field["nullable"] == true);
+    if let Some(index) = partition_index {
+        builder.partition_index(index as i32);
+    }
+    builder.build().expect("ColumnInfo fields all have defaults")
+}
+
+fn decimal_precision(data_type: &Value) -> Option<(i32, i32)> {
+    let (precision, scale) = data_type.as_str()?
+        .strip_prefix("decimal(")?
+        .strip_suffix(')')?
+        .split_once(',')?;
+    Some((precision.parse().ok()?, scale.parse().ok()?))
+}
+
+fn type_name(data_type: &Value) -> ColumnTypeName {
+    match data_type.as_str().or(data_type["type"].as_str()).unwrap_or_default() {
+        "null" => ColumnTypeName::NULL,
+        "boolean" => ColumnTypeName::BOOLEAN,
+        "byte" => ColumnTypeName::BYTE,
+        "short" => ColumnTypeName::SHORT,
+        "integer" => ColumnTypeName::INT,
+        "long" => ColumnTypeName::LONG,
+        "float" => ColumnTypeName::FLOAT,
+        "double" => ColumnTypeName::DOUBLE,
+        "string" => ColumnTypeName::STRING,
+        "binary" => ColumnTypeName::BINARY,
+        "date" => ColumnTypeName::DATE,
+        "timestamp" => ColumnTypeName::TIMESTAMP,
+        "timestamp_ntz" => ColumnTypeName::TIMESTAMP_NTZ,
+        "array" => ColumnTypeName::ARRAY,
+        "struct" => ColumnTypeName::STRUCT,
+        "map" => ColumnTypeName::MAP,
+        t if t.starts_with("decimal") => ColumnTypeName::DECIMAL,
+        t => ColumnTypeName::Unknown(t.to_owned())
+    }
+}
+
+/// Spark's SQL spelling of a type, e.g. `array<struct<a:bigint>>`.
+fn type_text(data_type: &Value) -> String {
+    match (data_type.as_str(), data_type["type"].as_str()) {
+        (Some("null"), _) => "void".to_owned(),
+        (Some("byte"), _) => "tinyint".to_owned(),
+        (Some("short"), _) => "smallint".to_owned(),
+        (Some("integer"), _) => "int".to_owned(),
+        (Some("long"), _) => "bigint".to_owned(),
+        (Some(name), _) => name.to_owned(),
+        (None, Some("array")) => format!("array<{}>", type_text(&data_type["elementType"])),
+        (None, Some("map")) => format!("map<{},{}>", type_text(&data_type["keyType"]), type_text(&data_type["valueType"])),
+        (None, Some("struct")) => {
+            let fields = data_type["fields"].as_array().into_iter().flatten()
+                .map(|f| format!("{}:{}", f["name"].as_str().unwrap_or_default(), type_text(&f["type"])))
+                .collect::<Vec<_>>();
+            format!("struct<{}>", fields.join(","))
+        },
+        _ => data_type.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::test_utils::temp_dir;
+    use arrow_schema::Schema;
+    use parquet::arrow::ArrowWriter;
+
+    fn write_footer(dir: &std::path::Path, file: &str, fields: Vec<Field>) {
+        let path = dir.join(file);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let schema = Arc::new(Schema::new(fields));
+        ArrowWriter::try_new(std::fs::File::create(path).unwrap(), schema, None).unwrap()
+            .close()
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_infer_parquet_table() -> UCRSResult<()> {
+        let dir = temp_dir();
+        write_footer(dir.path(), "dt=2024-01-01/region=eu/part-0.parquet", vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("price", DataType::Decimal128(10, 2), true),
+            Field::new_list("tags", Field::new_list_field(DataType::Utf8, true), true)
+        ]);
+        write_footer(dir.path(), &format!("dt=2024-01-02/region={}/part-0.parquet", HIVE_DEFAULT_PARTITION), vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new_struct("point", vec![Field::new("x", DataType::Int32, false)], false)
+        ]);
+        std::fs::write(dir.path().join("_SUCCESS"), "").unwrap();
+        std::fs::write(dir.path().join("dt=2024-01-01/region=eu/.part-0.parquet.crc"), "").unwrap();
+
+        let table = infer_local_parquet_table(dir.path(), "unity", "default", "orders").await?;
+        assert_eq!(table.full_name(), "unity.default.orders");
+        assert_eq!(table.storage_location(), Some(Url::from_directory_path(dir.path()).unwrap().as_str()));
+        let columns = table.columns().iter()
+            .map(|c| (c.name().unwrap(), c.type_text().unwrap(), c.nullable().unwrap(), c.partition_index()))
+            .collect::<Vec<_>>();
+        assert_eq!(columns, vec![
+            ("id", "bigint", false, None),
+            ("price", "decimal(10,2)", true, None),
+            ("tags", "array<string>", true, None),
+            ("point", "struct<x:int>", true, None),
+            ("dt", "date", true, Some(0)),
+            ("region", "string", true, Some(1))
+        ]);
+        let price = &table.columns()[1];
+        assert_eq!(price.type_name(), Some(&ColumnTypeName::DECIMAL));
+        assert_eq!((price.type_precision(), price.type_scale()), (Some(10), Some(2)));
+        let point: Value = serde_json::from_str(table.columns()[3].type_json().unwrap()).unwrap();
+        assert_eq!(point["type"]["fields"][0], json!({"name": "x", "type": "integer", "nullable": false, "metadata": {}}));
+
+        write_footer(dir.path(), "dt=2024-01-03/region=us/part-0.parquet", vec![Field::new("id", DataType::Utf8, true)]);
+        let res = infer_local_parquet_table(dir.path(), "unity", "default", "orders").await;
+        assert!(matches!(res, Err(UCRSError::InvalidTableDefinition(_, ref reason)) if reason.contains("conflicting types")));
+        write_footer(dir.path(), "dt=2024-01-03/region=us/part-0.parquet", vec![Field::new("id", DataType::Int64, true)]);
+        write_footer(dir.path(), "dt=2024-01-04/part-0.parquet", vec![Field::new("id", DataType::Int64, true)]);
+        let res = infer_local_parquet_table(dir.path(), "unity", "default", "orders").await;
+        assert!(matches!(res, Err(UCRSError::InvalidTableDefinition(_, ref reason)) if reason.contains("not partitioned by dt, region")));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_hidden_directories_are_skipped() -> UCRSResult<()> {
+        let dir = temp_dir();
+        let id = || vec![Field::new("id", DataType::Int64, false)];
+        write_footer(dir.path(), "dt=2024-01-01/part-0.parquet", id());
+        write_footer(dir.path(), "_temporary/0/part-1.parquet", id());
+        write_footer(dir.path(), ".spark-staging/part-2.parquet", id());
+        write_footer(dir.path(), "_delta_log/checkpoint.parquet", id());
+
+        let table = infer_local_parquet_table(dir.path(), "unity", "default", "events").await?;
+        let columns = table.columns().iter().map(|c| (c.name().unwrap(), c.partition_index())).collect::<Vec<_>>();
+        assert_eq!(columns, vec![("id", None), ("dt", Some(0))]);
+
+        write_footer(dir.path(), "backup/part-3.parquet", id());
+        let res = infer_local_parquet_table(dir.path(), "unity", "default", "events").await;
+        assert!(matches!(res, Err(UCRSError::InvalidTableDefinition(_, ref reason)) if reason == "backup is not a key=value partition directory"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_types() {
+        let merge = |a: Value, b: Value| merge_types(&a, &b);
+        assert_eq!(merge(json!("integer"), json!("long")), Some(json!("long")));
+        assert_eq!(merge(json!("short"), json!("byte")), Some(json!("short")));
+        assert_eq!(merge(json!("double"), json!("float")), Some(json!("double")));
+        assert_eq!(merge(json!("decimal(10,2)"), json!("decimal(6,4)")), Some(json!("decimal(12,4)")));
+        assert_eq!(merge(json!("decimal(38,0)"), json!("decimal(10,5)")), None);
+        assert_eq!(merge(json!("integer"), json!("double")), None);
+        assert_eq!(merge(json!("integer"), json!("string")), None);
+
+        let array = |element: &str| json!({"type": "array", "elementType": element, "containsNull": false});
+        assert_eq!(merge(array("integer"), array("long")), Some(array("long")));
+    }
+}
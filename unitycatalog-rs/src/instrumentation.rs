@@ -0,0 +1,155 @@
+use crate::errors::UCRSError;
+use crate::middleware::{RequestContext, ResponseContext};
+use ::metrics::{counter, histogram};
+use serde::Deserialize;
+
+pub const REQUESTS_TOTAL: &str = "unitycatalog_requests_total";
+pub const ERRORS_TOTAL: &str = "unitycatalog_request_errors_total";
+pub const REQUEST_DURATION_SECONDS: &str = "unitycatalog_request_duration_seconds";
+
+#[derive(Deserialize)]
+struct ErrorBody {
+    error_code: Option<String>
+}
+
+pub(crate) fn record_response(request: &RequestContext, response: &ResponseContext) {
+    let status = response.status.as_u16().to_string();
+    counter!(REQUESTS_TOTAL,
+        "operation" => request.operation.clone(),
+        "method" => request.method.to_string(),
+        "status" => status.clone()
+    ).increment(1);
+    histogram!(REQUEST_DURATION_SECONDS,
+        "operation" => request.operation.clone(),
+        "method" => request.method.to_string(),
+        "status" => status
+    ).record(request.started.elapsed().as_secs_f64());
+}
+
+pub(crate) fn record_error(request: &RequestContext, error: &UCRSError) {
+    if !responded(error) {
+        // No response came back, so `record_response` never saw this call.
+        counter!(REQUESTS_TOTAL,
+            "operation" => request.operation.clone(),
+            "method" => request.method.to_string(),
+            "status" => "none"
+        ).increment(1);
+    }
+    counter!(ERRORS_TOTAL,
+        "operation" => request.operation.clone(),
+        "error_code" => error_code(error)
+    ).increment(1);
+}
+
+/// Whether the call got as far as a response, which `record_response` counted.
+fn responded(error: &UCRSError) -> bool {
+    !matches!(error,
+        UCRSError::RequestError(_)
+        | UCRSError::JSONFormattingError(_)
+        | UCRSError::MiddlewareError(_)
+        | UCRSError::CassetteIOError(_)
+        | UCRSError::CassetteMismatch(_))
+}
+
+fn error_code(error: &UCRSError) -> String {
+    match error {
+        UCRSError::RequestErrorWithResponse(e, body) => serde_json::from_str::<ErrorBody>(body)
+            .ok()
+            .and_then(|b| b.error_code)
+            .or_else(|| e.status().map(|s| s.as_u16().to_string()))
+            .unwrap_or_else(|| "UNKNOWN".to_owned()),
+        UCRSError::RequestError(_) => "REQUEST_FAILED".to_owned(),
+        UCRSError::JSONParsingError(_) => "INVALID_RESPONSE".to_owned(),
+        UCRSError::JSONFormattingError(_) => "INVALID_REQUEST".to_owned(),
+        UCRSError::MiddlewareError(_) => "MIDDLEWARE_REJECTED".to_owned(),
+        UCRSError::CassetteIOError(_) => "CASSETTE_IO".to_owned(),
+        UCRSError::CassetteMismatch(_) => "CASSETTE_MISMATCH".to_owned(),
+        _ => "UNKNOWN".to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::UCRSResult;
+    use crate::middleware::Middleware;
+    use crate::request::RequestClient;
+    use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+    use metrics_util::MetricKind;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    struct Deny;
+
+    impl Middleware for Deny {
+        fn on_request(&self, _request: &mut reqwest::Request) -> UCRSResult<()> {
+            Err(UCRSError::MiddlewareError("denied".to_owned()))
+        }
+    }
+
+    #[test]
+    fn test_records_metrics() -> UCRSResult<()> {
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+
+        ::metrics::with_local_recorder(&recorder, || {
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap()
+                .block_on(async {
+                    let server = MockServer::start().await;
+                    Mock::given(method("GET"))
+                        .and(path("/ok"))
+                        .respond_with(ResponseTemplate::new(200).set_body_string("{}"))
+                        .mount(&server)
+                        .await;
+                    Mock::given(method("GET"))
+                        .and(path("/missing"))
+                        .respond_with(ResponseTemplate::new(404)
+                            .set_body_string(r#"{"error_code":"NOT_FOUND","message":"Catalog not found"}"#))
+                        .mount(&server)
+                        .await;
+
+                    let rc = RequestClient::new(&server.uri(), true)?;
+                    rc.get_op::<_, serde_json::Value>("catalogs.list", rc.base_url.join("/ok").unwrap(), None::<String>).await?;
+                    rc.get_op::<_, serde_json::Value>("catalogs.list", rc.base_url.join("/ok").unwrap(), None::<String>).await?;
+                    let _ = rc.get_op::<_, serde_json::Value>("catalogs.get", rc.base_url.join("/missing").unwrap(), None::<String>).await;
+
+                    let rc = RequestClient::new(&server.uri(), true)?.with_middleware(Deny);
+                    let _ = rc.post_op::<_, serde_json::Value>("catalogs.create", rc.base_url.join("/ok").unwrap(), Some("{}")).await;
+                    Ok::<_, UCRSError>(())
+                })
+        })?;
+
+        let mut observed = snapshotter.snapshot()
+            .into_vec()
+            .into_iter()
+            .map(|(key, _, _, value)| {
+                let (kind, key) = key.into_parts();
+                let mut labels = key.labels()
+                    .map(|l| format!("{}={}", l.key(), l.value()))
+                    .collect::<Vec<_>>();
+                labels.sort();
+                let value = match value {
+                    DebugValue::Counter(c) => c.to_string(),
+                    DebugValue::Histogram(h) => format!("{} samples", h.len()),
+                    DebugValue::Gauge(g) => g.to_string()
+                };
+                (kind == MetricKind::Counter, key.name().to_owned(), labels.join(","), value)
+            })
+            .collect::<Vec<_>>();
+        observed.sort();
+
+        assert_eq!(observed, vec![
+            (false, REQUEST_DURATION_SECONDS.to_owned(), "method=GET,operation=catalogs.get,status=404".to_owned(), "1 samples".to_owned()),
+            (false, REQUEST_DURATION_SECONDS.to_owned(), "method=GET,operation=catalogs.list,status=200".to_owned(), "2 samples".to_owned()),
+            (true, ERRORS_TOTAL.to_owned(), "error_code=MIDDLEWARE_REJECTED,operation=catalogs.create".to_owned(), "1".to_owned()),
+            (true, ERRORS_TOTAL.to_owned(), "error_code=NOT_FOUND,operation=catalogs.get".to_owned(), "1".to_owned()),
+            (true, REQUESTS_TOTAL.to_owned(), "method=GET,operation=catalogs.get,status=404".to_owned(), "1".to_owned()),
+            (true, REQUESTS_TOTAL.to_owned(), "method=GET,operation=catalogs.list,status=200".to_owned(), "2".to_owned()),
+            (true, REQUESTS_TOTAL.to_owned(), "method=POST,operation=catalogs.create,status=none".to_owned(), "1".to_owned()),
+        ]);
+        Ok(())
+    }
+}
@@ -1,6 +1,23 @@
-mod request;
-mod errors;
-mod oss;
+pub mod request;
+pub mod errors;
+pub mod flavor;
+pub mod bulk;
+pub mod cassette;
+pub mod limits;
+pub mod middleware;
+#[cfg(feature = "metrics")]
+pub mod instrumentation;
+pub mod oss;
+#[cfg(feature = "object-store")]
+pub mod store;
+#[cfg(feature = "object-store")]
+pub mod refresh;
+#[cfg(feature = "deltalake")]
+pub mod delta;
+#[cfg(feature = "datafusion")]
+pub mod provider;
+#[cfg(feature = "parquet")]
+pub mod infer;
 mod testing;
 
 pub fn add(left: u64, right: u64) -> u64 {
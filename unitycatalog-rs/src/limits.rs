@@ -0,0 +1,301 @@
+use derive_builder::Builder;
+use reqwest::Method;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::{Semaphore, SemaphorePermit};
+use tokio::time::Instant;
+
+/// Budget applied to a group of requests: a token-bucket rate and/or a cap
+/// on the number of requests in flight at once.
+#[derive(Debug, Clone, Default, Builder)]
+#[builder(setter(strip_option), default, build_fn(validate = "Self::validate"))]
+pub struct Limits {
+    /// Must be a positive, finite, normal number.
+    requests_per_second: Option<f64>,
+    /// Bucket capacity; defaults to one second's worth of requests.
+    burst: Option<u32>,
+    max_in_flight: Option<usize>
+}
+
+impl Limits {
+    pub fn builder() -> LimitsBuilder {
+        LimitsBuilder::default()
+    }
+}
+
+impl LimitsBuilder {
+    fn validate(&self) -> Result<(), String> {
+        match self.requests_per_second {
+            Some(Some(rate)) if !(rate.is_normal() && rate > 0.0) =>
+                Err(format!("requests_per_second must be a positive, finite, normal number, got {}", rate)),
+            _ => Ok(())
+        }
+    }
+}
+
+/// Limits shared by every sub-client of a `RequestClient`. `shared` applies
+/// to all calls, `reads` and `writes` additionally apply per endpoint family.
+#[derive(Debug, Clone, Default, Builder)]
+#[builder(default)]
+pub struct RequestLimits {
+    shared: Limits,
+    reads: Limits,
+    writes: Limits
+}
+
+impl RequestLimits {
+    pub fn builder() -> RequestLimitsBuilder {
+        RequestLimitsBuilder::default()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EndpointFamily {
+    Read,
+    Write
+}
+
+impl From<&Method> for EndpointFamily {
+    fn from(method: &Method) -> Self {
+        match *method {
+            Method::GET | Method::HEAD => EndpointFamily::Read,
+            _ => EndpointFamily::Write
+        }
+    }
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    rate: f64,
+    capacity: f64,
+    state: Mutex<BucketState>
+}
+
+#[derive(Debug)]
+struct BucketState {
+    tokens: f64,
+    last_refill: Instant
+}
+
+impl TokenBucket {
+    fn new(rate: f64, burst: Option<u32>) -> Self {
+        let capacity = burst.map(f64::from).unwrap_or(rate).max(1.0);
+        Self {
+            rate,
+            capacity,
+            state: Mutex::new(BucketState {
+                tokens: capacity,
+                last_refill: Instant::now()
+            })
+        }
+    }
+
+    async fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut state = self.state.lock().unwrap();
+                let now = Instant::now();
+                let elapsed = now.duration_since(state.last_refill).as_secs_f64();
+                state.tokens = (state.tokens + elapsed * self.rate).min(self.capacity);
+                state.last_refill = now;
+                if state.tokens >= 1.0 {
+                    state.tokens -= 1.0;
+                    return;
+                }
+                // A very low rate can put the next token beyond what a
+                // `Duration` holds; sleeping "forever" is the honest answer.
+                Duration::try_from_secs_f64((1.0 - state.tokens) / self.rate)
+                    .unwrap_or(Duration::MAX)
+            };
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Budget {
+    bucket: Option<TokenBucket>,
+    in_flight: Option<Semaphore>
+}
+
+impl Budget {
+    fn new(limits: &Limits) -> Self {
+        Self {
+            bucket: limits.requests_per_second
+                .filter(|rate| *rate > 0.0)
+                .map(|rate| TokenBucket::new(rate, limits.burst)),
+            in_flight: limits.max_in_flight
+                .map(|max| Semaphore::new(max.max(1)))
+        }
+    }
+
+    async fn acquire(&self) -> Option<SemaphorePermit<'_>> {
+        // Take the in-flight slot first so queued requests don't burn tokens
+        // while they wait for a slot.
+        let permit = match &self.in_flight {
+            Some(semaphore) => semaphore.acquire().await.ok(),
+            None => None
+        };
+        if let Some(bucket) = &self.bucket {
+            bucket.acquire().await;
+        }
+        permit
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Limiter {
+    shared: Budget,
+    reads: Budget,
+    writes: Budget
+}
+
+/// Held for the lifetime of a request; releases its in-flight slots on drop.
+pub(crate) struct LimitPermit<'a> {
+    _shared: Option<SemaphorePermit<'a>>,
+    _family: Option<SemaphorePermit<'a>>
+}
+
+impl Limiter {
+    pub(crate) fn new(limits: &RequestLimits) -> Self {
+        Self {
+            shared: Budget::new(&limits.shared),
+            reads: Budget::new(&limits.reads),
+            writes: Budget::new(&limits.writes)
+        }
+    }
+
+    pub(crate) async fn acquire(&self, family: EndpointFamily) -> LimitPermit<'_> {
+        let family_budget = match family {
+            EndpointFamily::Read => &self.reads,
+            EndpointFamily::Write => &self.writes
+        };
+        // Wait for the family budget before touching the shared one, so a
+        // request queued behind a saturated family doesn't sit on a shared
+        // slot (or spend a shared token) and starve the other family.
+        let family = family_budget.acquire().await;
+        let shared = self.shared.acquire().await;
+        LimitPermit {
+            _shared: shared,
+            _family: family
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rate_limit() {
+        let limits = RequestLimits::builder()
+            .shared(Limits::builder().requests_per_second(2.0).burst(2).build().unwrap())
+            .build()
+            .unwrap();
+        let limiter = Limiter::new(&limits);
+
+        let start = Instant::now();
+        for _ in 0..6 {
+            limiter.acquire(EndpointFamily::Read).await;
+        }
+        // Two requests come out of the burst, the other four wait 0.5s each.
+        assert_eq!(start.elapsed().as_secs_f64().round(), 2.0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_family_limits_are_independent() {
+        let limits = RequestLimits::builder()
+            .writes(Limits::builder().requests_per_second(1.0).build().unwrap())
+            .build()
+            .unwrap();
+        let limiter = Limiter::new(&limits);
+
+        let start = Instant::now();
+        for _ in 0..10 {
+            limiter.acquire(EndpointFamily::Read).await;
+        }
+        assert_eq!(start.elapsed(), Duration::ZERO);
+
+        for _ in 0..3 {
+            limiter.acquire(EndpointFamily::Write).await;
+        }
+        assert_eq!(start.elapsed().as_secs_f64().round(), 2.0);
+    }
+
+    #[tokio::test]
+    async fn test_max_in_flight() {
+        let limits = RequestLimits::builder()
+            .shared(Limits::builder().max_in_flight(2).build().unwrap())
+            .build()
+            .unwrap();
+        let limiter = Arc::new(Limiter::new(&limits));
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+
+        let tasks = (0..8).map(|_| {
+            let limiter = limiter.clone();
+            let in_flight = in_flight.clone();
+            let peak = peak.clone();
+            tokio::spawn(async move {
+                let _permit = limiter.acquire(EndpointFamily::Write).await;
+                let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+            })
+        }).collect::<Vec<_>>();
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_saturated_writes_do_not_starve_reads() {
+        let limits = RequestLimits::builder()
+            .shared(Limits::builder().max_in_flight(2).requests_per_second(2.0).burst(2).build().unwrap())
+            .writes(Limits::builder().max_in_flight(1).build().unwrap())
+            .build()
+            .unwrap();
+        let limiter = Limiter::new(&limits);
+        let write = limiter.acquire(EndpointFamily::Write).await;
+
+        // Queue writes behind the saturated write family.
+        let queued = (0..3).map(|_| async {
+            limiter.acquire(EndpointFamily::Write).await;
+        });
+        let queued = futures_util::future::join_all(queued);
+        tokio::pin!(queued);
+        assert!(tokio::time::timeout(Duration::from_millis(10), &mut queued).await.is_err());
+
+        // The queued writes hold neither a shared slot nor a shared token.
+        assert_eq!(limiter.shared.in_flight.as_ref().unwrap().available_permits(), 1);
+        let start = Instant::now();
+        let read = limiter.acquire(EndpointFamily::Read).await;
+        assert_eq!(start.elapsed(), Duration::ZERO);
+
+        drop(read);
+        drop(write);
+        queued.await;
+    }
+
+    #[test]
+    fn test_rejects_unusable_rates() {
+        for rate in [f64::NAN, f64::INFINITY, f64::MIN_POSITIVE / 2.0, -1.0, 0.0] {
+            assert!(Limits::builder().requests_per_second(rate).build().is_err(), "{}", rate);
+        }
+        assert!(Limits::builder().requests_per_second(f64::MIN_POSITIVE).build().is_ok());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_tiny_rate_does_not_panic() {
+        let bucket = TokenBucket::new(f64::MIN_POSITIVE, Some(1));
+        bucket.acquire().await;
+        let next = bucket.acquire();
+        tokio::pin!(next);
+        assert!(tokio::time::timeout(Duration::from_secs(3600), &mut next).await.is_err());
+    }
+}
@@ -0,0 +1,252 @@
+use crate::errors::{UCRSError, UCRSResult};
+use reqwest::header::HeaderMap;
+use reqwest::{Method, Request, StatusCode};
+use std::borrow::Cow;
+use std::sync::Arc;
+use std::time::Instant;
+use url::Url;
+
+/// What the client sent, as seen after every `on_request` hook has run.
+#[derive(Debug, Clone)]
+pub struct RequestContext {
+    /// Name of the API call, e.g. `catalogs.list`. Calls made without one
+    /// are named after their resource and method, e.g. `tables.get`.
+    pub operation: Cow<'static, str>,
+    pub method: Method,
+    pub url: Url,
+    pub headers: HeaderMap,
+    pub body: Option<String>,
+    pub started: Instant
+}
+
+impl RequestContext {
+    pub(crate) fn new(operation: Cow<'static, str>, request: &Request) -> Self {
+        Self {
+            operation,
+            method: request.method().clone(),
+            url: request.url().clone(),
+            headers: request.headers().clone(),
+            body: request.body()
+                .and_then(|b| b.as_bytes())
+                .map(|b| String::from_utf8_lossy(b).into_owned()),
+            started: Instant::now()
+        }
+    }
+
+    /// Context for a call that failed before its request could be built.
+    pub(crate) fn unsent(operation: Cow<'static, str>, method: &Method, url: &Url) -> Self {
+        Self {
+            operation,
+            method: method.clone(),
+            url: url.clone(),
+            headers: HeaderMap::new(),
+            body: None,
+            started: Instant::now()
+        }
+    }
+}
+
+/// The buffered response, before it is checked for an error status or parsed.
+#[derive(Debug, Clone)]
+pub struct ResponseContext {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: String
+}
+
+impl From<ResponseContext> for reqwest::Response {
+    fn from(context: ResponseContext) -> Self {
+        let mut response = http::Response::new(context.body);
+        *response.status_mut() = context.status;
+        *response.headers_mut() = context.headers;
+        response.into()
+    }
+}
+
+/// Hooks run around every call made by a `RequestClient`.
+///
+/// `on_request` runs in registration order and may modify the outgoing
+/// request; returning an error aborts the call. `respond` may answer the
+/// request itself instead of it being sent; the first middleware to do so
+/// wins. `on_response` runs for every response received, whatever its status,
+/// and `on_error` runs with the error the call is about to return. Both run in
+/// reverse registration order.
+pub trait Middleware: Send + Sync {
+    fn on_request(&self, _request: &mut Request) -> UCRSResult<()> {
+        Ok(())
+    }
+
+    fn respond(&self, _request: &RequestContext) -> Option<UCRSResult<ResponseContext>> {
+        None
+    }
+
+    fn on_response(&self, _request: &RequestContext, _response: &ResponseContext) {}
+
+    fn on_error(&self, _request: &RequestContext, _error: &UCRSError) {}
+}
+
+impl<M: Middleware + ?Sized> Middleware for Arc<M> {
+    fn on_request(&self, request: &mut Request) -> UCRSResult<()> {
+        (**self).on_request(request)
+    }
+
+    fn respond(&self, request: &RequestContext) -> Option<UCRSResult<ResponseContext>> {
+        (**self).respond(request)
+    }
+
+    fn on_response(&self, request: &RequestContext, response: &ResponseContext) {
+        (**self).on_response(request, response)
+    }
+
+    fn on_error(&self, request: &RequestContext, error: &UCRSError) {
+        (**self).on_error(request, error)
+    }
+}
+
+#[derive(Default, Clone)]
+pub(crate) struct MiddlewareChain {
+    middleware: Vec<Arc<dyn Middleware>>
+}
+
+impl MiddlewareChain {
+    pub(crate) fn push(&mut self, middleware: Arc<dyn Middleware>) {
+        self.middleware.push(middleware);
+    }
+
+    pub(crate) fn on_request(&self, request: &mut Request) -> UCRSResult<()> {
+        self.middleware.iter().try_for_each(|m| m.on_request(request))
+    }
+
+    pub(crate) fn respond(&self, request: &RequestContext) -> Option<UCRSResult<ResponseContext>> {
+        self.middleware.iter().find_map(|m| m.respond(request))
+    }
+
+    pub(crate) fn on_response(&self, request: &RequestContext, response: &ResponseContext) {
+        self.middleware.iter().rev().for_each(|m| m.on_response(request, response));
+    }
+
+    pub(crate) fn on_error(&self, request: &RequestContext, error: &UCRSError) {
+        self.middleware.iter().rev().for_each(|m| m.on_error(request, error));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::request::RequestClient;
+    use reqwest::header::HeaderValue;
+    use std::sync::Mutex;
+    use std::time::Duration;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    struct AddHeader;
+
+    impl Middleware for AddHeader {
+        fn on_request(&self, request: &mut Request) -> UCRSResult<()> {
+            request.headers_mut().insert("x-request-source", HeaderValue::from_static("tests"));
+            Ok(())
+        }
+    }
+
+    #[derive(Default)]
+    struct Audit {
+        events: Mutex<Vec<String>>
+    }
+
+    impl Middleware for Audit {
+        fn on_response(&self, request: &RequestContext, response: &ResponseContext) {
+            self.events.lock().unwrap().push(format!("{} {} {}", request.method, request.url.path(), response.status));
+        }
+
+        fn on_error(&self, request: &RequestContext, _error: &UCRSError) {
+            self.events.lock().unwrap().push(format!("error {} {}", request.method, request.url.path()));
+        }
+    }
+
+    struct Latency {
+        elapsed: Mutex<Vec<Duration>>
+    }
+
+    impl Middleware for Latency {
+        fn on_response(&self, request: &RequestContext, _response: &ResponseContext) {
+            self.elapsed.lock().unwrap().push(request.started.elapsed());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_middleware_chain() -> UCRSResult<()> {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/ok"))
+            .and(header("x-request-source", "tests"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("{}"))
+            .mount(&server)
+            .await;
+        Mock::given(method("DELETE"))
+            .and(path("/missing"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+
+        let audit = Arc::new(Audit::default());
+        let latency = Arc::new(Latency { elapsed: Mutex::new(vec![]) });
+        let rc = RequestClient::new(&server.uri(), true)?
+            .with_middleware(AddHeader)
+            .with_middleware(audit.clone())
+            .with_middleware(latency.clone());
+
+        let ok: serde_json::Value = rc.get(rc.base_url.join("/ok").unwrap(), None::<String>).await?;
+        assert_eq!(ok, serde_json::json!({}));
+        let missing = rc.delete(rc.base_url.join("/missing").unwrap(), None::<String>).await;
+        assert!(matches!(missing, Err(UCRSError::RequestErrorWithResponse(_, _))));
+
+        assert_eq!(*audit.events.lock().unwrap(), vec![
+            "GET /ok 200 OK".to_string(),
+            "DELETE /missing 404 Not Found".to_string(),
+            "error DELETE /missing".to_string()
+        ]);
+        assert_eq!(latency.elapsed.lock().unwrap().len(), 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_on_request_error_aborts() -> UCRSResult<()> {
+        struct Deny;
+        impl Middleware for Deny {
+            fn on_request(&self, request: &mut Request) -> UCRSResult<()> {
+                Err(UCRSError::MiddlewareError(format!("{} denied", request.method())))
+            }
+        }
+
+        let server = MockServer::start().await;
+        let rc = RequestClient::new(&server.uri(), true)?
+            .with_middleware(Deny);
+        let res = rc.post::<_, serde_json::Value>(rc.base_url.join("/anything").unwrap(), Some("{}")).await;
+
+        assert!(matches!(res, Err(UCRSError::MiddlewareError(ref m)) if m == "POST denied"));
+        assert!(server.received_requests().await.unwrap().is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_on_request_error_reaches_on_error() -> UCRSResult<()> {
+        struct Deny;
+        impl Middleware for Deny {
+            fn on_request(&self, _request: &mut Request) -> UCRSResult<()> {
+                Err(UCRSError::MiddlewareError("denied".to_owned()))
+            }
+        }
+
+        let server = MockServer::start().await;
+        let audit = Arc::new(Audit::default());
+        let rc = RequestClient::new(&server.uri(), true)?
+            .with_middleware(audit.clone())
+            .with_middleware(Deny);
+        let res = rc.get::<_, serde_json::Value>(rc.base_url.join("/anything").unwrap(), None::<String>).await;
+
+        assert!(res.is_err());
+        assert_eq!(*audit.events.lock().unwrap(), vec!["error GET /anything".to_string()]);
+        Ok(())
+    }
+}
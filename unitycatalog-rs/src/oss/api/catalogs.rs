@@ -3,6 +3,7 @@ use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use crate::errors::UCRSResult;
+use crate::oss::api::tags::{find_tag, Tag};
 use derive_builder::Builder;
 
 pub struct CatalogsClient<'a> {
@@ -17,22 +18,31 @@ impl<'a> CatalogsClient<'a> {
     pub async fn list(&self, page_token:Option<String>, max_results: Option<i32>) -> UCRSResult<ListCatalogResponse> {
         let mut url = self.client.base_url.clone().join("/api/2.1/unity-catalog/catalogs")
             .map_err(UCRSError::MalformedURL)?;
-        if let Some(token) = page_token {
-            url.query_pairs_mut().append_pair("page_token", &token);
-        }
-        if let Some(max_results) = max_results {
-            url.query_pairs_mut().append_pair("max_results", &max_results.to_string());
+        self.client.flavor().page(&mut url, page_token, max_results);
+        self.client.get_op("catalogs.list", url, None::<String>).await
+    }
+
+    /// Follows `next_page_token` until every catalog has been listed.
+    pub async fn list_all(&self) -> UCRSResult<Vec<CatalogInfo>> {
+        let mut catalogs = vec![];
+        let mut page_token = None;
+        loop {
+            let page = self.list(page_token, None).await?;
+            catalogs.extend(page.catalogs);
+            match page.next_page_token {
+                Some(token) if !token.is_empty() => page_token = Some(token),
+                _ => return Ok(catalogs)
+            }
         }
-        self.client.get(url, None::<String>).await
     }
 
     pub async fn create(&self, props: CreateCatalog) -> UCRSResult<CatalogInfo> {
         let route = self.client.base_url.join("/api/2.1/unity-catalog/catalogs")
             .map_err(UCRSError::MalformedURL)?;
 
-        let res = self.client.post(route, Some(&props)).await;
-        if let Err(UCRSError::RequestError(ref res_inner)) = res {
-            match res_inner.status() {
+        let res = self.client.post_op("catalogs.create", route, Some(&props)).await;
+        if let Err(ref e) = res {
+            match e.status() {
                 Some(StatusCode::CONFLICT) => Err(UCRSError::DuplicateCatalogName(props.name.to_owned())),
                 _ => res
             }
@@ -44,9 +54,9 @@ impl<'a> CatalogsClient<'a> {
     pub async fn get(&self, name: &str) -> UCRSResult<CatalogInfo> {
         let path = self.client.base_url.join(&format!("/api/2.1/unity-catalog/catalogs/{}", name))
             .map_err(UCRSError::MalformedURL)?;
-        let res = self.client.get(path, None::<String>).await;
-        if let Err(UCRSError::RequestError(ref res_inner)) = res {
-            match res_inner.status() {
+        let res = self.client.get_op("catalogs.get", path, None::<String>).await;
+        if let Err(ref e) = res {
+            match e.status() {
                 Some(StatusCode::NOT_FOUND) => 
                     Err(UCRSError::CatalogNotFound(name.to_owned())),
                 _ => res
@@ -60,30 +70,29 @@ impl<'a> CatalogsClient<'a> {
         let mut path = self.client.base_url.join(&format!("/api/2.1/unity-catalog/catalogs/{}", name))
             .map_err(UCRSError::MalformedURL)?;
         path.query_pairs_mut().append_pair("force", &force.to_string());
-        let res = self.client.delete(path, None::<String>).await;
-        if let Err(UCRSError::RequestError(ref res_inner)) = res {
-            match res_inner.status() {
+        let res = self.client.delete_op("catalogs.delete", path, None::<String>).await;
+        if let Err(ref e) = res {
+            match e.status() {
                 Some(StatusCode::NOT_FOUND) => 
                     Err(UCRSError::CatalogNotFound(name.to_owned())),
                 _ => res
             }
-        } else if let Err(UCRSError::JSONParsingError(_)) = res {
-            // This is because DELETE returns "200 OK" as a response body :/
-            Ok(())
-        } 
-        else {
+        } else {
             res
         }
     }
 
-    pub async fn update(&self, name: &str, update_props: UpdateCatalog)
+    pub async fn update(&self, name: &str, mut update_props: UpdateCatalog)
         -> UCRSResult<CatalogInfo> {
         let path = self.client.base_url.join(&format!("/api/2.1/unity-catalog/catalogs/{}", name))
             .map_err(UCRSError::MalformedURL)?;
         
-        let res = self.client.patch(path, Some(&update_props)).await;
-        if let Err(UCRSError::RequestError(ref res_inner)) = res {
-            match res_inner.status() {
+        if !self.client.flavor().sends_extra_fields() {
+            update_props.extra.clear();
+        }
+        let res = self.client.patch_op("catalogs.update", path, Some(&update_props)).await;
+        if let Err(ref e) = res {
+            match e.status() {
                 Some(StatusCode::NOT_FOUND) => 
                     Err(UCRSError::CatalogNotFound(name.to_owned())),
                 _ => res
@@ -94,11 +103,48 @@ impl<'a> CatalogsClient<'a> {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Default, Builder)]
+#[derive(Serialize, Deserialize, Debug, Default, Builder, Clone, PartialEq, Eq)]
 pub struct CreateCatalog {
     name: String,
     comment: Option<String>,
-    properties: Option<HashMap<String, String>>
+    properties: Option<HashMap<String, String>>,
+    #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    catalog_type: Option<CatalogType>,
+    /// For a `FOREIGN_CATALOG`, the connection to the external database.
+    #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    connection_name: Option<String>,
+    /// For a `FOREIGN_CATALOG`, e.g. the `database` to expose.
+    #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    options: Option<HashMap<String, String>>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum CatalogType {
+    #[allow(non_camel_case_types)]
+    MANAGED_CATALOG,
+    #[allow(non_camel_case_types)]
+    FOREIGN_CATALOG,
+    #[allow(non_camel_case_types)]
+    DELTASHARING_CATALOG,
+    #[allow(non_camel_case_types)]
+    SYSTEM_CATALOG,
+    /// A value this crate doesn't know yet, kept as sent by the server.
+    #[serde(untagged)]
+    Unknown(String)
+}
+
+impl CreateCatalog {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn rename(mut self, name: &str) -> Self {
+        self.name = name.to_owned();
+        self
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Default)]
@@ -107,6 +153,16 @@ pub struct ListCatalogResponse {
     next_page_token: Option<String>
 }
 
+impl ListCatalogResponse {
+    pub fn catalogs(&self) -> &[CatalogInfo] {
+        &self.catalogs
+    }
+
+    pub fn next_page_token(&self) -> Option<&str> {
+        self.next_page_token.as_deref()
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Default)]
 pub struct CatalogInfo {
     name: Option<String>,
@@ -114,27 +170,157 @@ pub struct CatalogInfo {
     properties: Option<HashMap<String, String>>,
     created_at: Option<i64>,
     updated_at: Option<i64>,
-    id: Option<String>
+    id: Option<String>,
+    owner: Option<String>,
+    created_by: Option<String>,
+    updated_by: Option<String>,
+    storage_root: Option<String>,
+    storage_location: Option<String>,
+    browse_only: Option<bool>,
+    metastore_id: Option<String>,
+    catalog_type: Option<CatalogType>,
+    connection_name: Option<String>,
+    options: Option<HashMap<String, String>>,
+    tags: Option<Vec<Tag>>,
+    #[serde(flatten)]
+    extra: HashMap<String, serde_json::Value>
+}
+
+impl CatalogInfo {
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn comment(&self) -> Option<&str> {
+        self.comment.as_deref()
+    }
+
+    pub fn properties(&self) -> Option<&HashMap<String, String>> {
+        self.properties.as_ref()
+    }
+
+    pub fn created_at(&self) -> Option<i64> {
+        self.created_at
+    }
+
+    pub fn updated_at(&self) -> Option<i64> {
+        self.updated_at
+    }
+
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    pub fn owner(&self) -> Option<&str> {
+        self.owner.as_deref()
+    }
+
+    pub fn created_by(&self) -> Option<&str> {
+        self.created_by.as_deref()
+    }
+
+    pub fn updated_by(&self) -> Option<&str> {
+        self.updated_by.as_deref()
+    }
+
+    pub fn storage_root(&self) -> Option<&str> {
+        self.storage_root.as_deref()
+    }
+
+    pub fn storage_location(&self) -> Option<&str> {
+        self.storage_location.as_deref()
+    }
+
+    pub fn browse_only(&self) -> Option<bool> {
+        self.browse_only
+    }
+
+    pub fn metastore_id(&self) -> Option<&str> {
+        self.metastore_id.as_deref()
+    }
+
+    pub fn catalog_type(&self) -> Option<&CatalogType> {
+        self.catalog_type.as_ref()
+    }
+
+    pub fn connection_name(&self) -> Option<&str> {
+        self.connection_name.as_deref()
+    }
+
+    pub fn options(&self) -> Option<&HashMap<String, String>> {
+        self.options.as_ref()
+    }
+
+    pub fn tags(&self) -> &[Tag] {
+        self.tags.as_deref().unwrap_or_default()
+    }
+
+    /// `None` if `key` isn't set, `Some(None)` if it is set without a value.
+    pub fn tag(&self, key: &str) -> Option<Option<&str>> {
+        find_tag(self.tags(), key)
+    }
+
+    /// Attaches tags fetched separately, e.g. with `TagsClient::list_all`.
+    pub fn with_tags(mut self, tags: Vec<Tag>) -> Self {
+        self.tags = Some(tags);
+        self
+    }
+
+    /// Fields returned by the server that this crate doesn't model yet.
+    pub fn extra(&self) -> &HashMap<String, serde_json::Value> {
+        &self.extra
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Default, Builder)]
 pub struct UpdateCatalog {
     new_name: Option<String>,
     properties: Option<HashMap<String, String>>,
-    comment: Option<String>
+    comment: Option<String>,
+    /// Sent as is to servers that accept them (see `Flavor`), so fields this
+    /// crate doesn't model survive an update.
+    #[builder(default)]
+    #[serde(flatten)]
+    extra: HashMap<String, serde_json::Value>
+}
+
+impl From<&CatalogInfo> for CreateCatalog {
+    fn from(info: &CatalogInfo) -> Self {
+        Self {
+            name: info.name.clone().unwrap_or_default(),
+            comment: info.comment.clone(),
+            properties: info.properties.clone(),
+            catalog_type: info.catalog_type.clone(),
+            connection_name: info.connection_name.clone(),
+            options: info.options.clone()
+        }
+    }
+}
+
+impl From<&CatalogInfo> for UpdateCatalog {
+    fn from(info: &CatalogInfo) -> Self {
+        Self {
+            new_name: None,
+            properties: info.properties.clone(),
+            comment: info.comment.clone(),
+            extra: info.extra.clone()
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use insta::with_settings;
-    use crate::testing::test_utils::{cleanup_user_model, test_with_uc};
+    use crate::testing::test_utils::{cleanup_user_model, test_with_cassette};
+    use crate::flavor::Flavor;
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
 
     #[tokio::test]
     async fn test_list() -> UCRSResult<()> {
 
-        test_with_uc(|port| async move {
-            let rc = RequestClient::new(&format!("http://localhost:{}", port), true)?;
+        test_with_cassette("catalogs_list", |rc| async move {
             let client = CatalogsClient::new(&rc);
             let list = client.list(None, None).await;
 
@@ -153,8 +339,7 @@ mod tests {
     #[tokio::test]
     async fn test_round_trip() -> UCRSResult<()> {
 
-        test_with_uc(|port| async move {
-            let rc = RequestClient::new(&format!("http://localhost:{}", port), true)?;
+        test_with_cassette("catalogs_round_trip", |rc| async move {
             let catalog_client = CatalogsClient::new(&rc);
 
             let initial_list = catalog_client.list(None, None).await?;
@@ -195,8 +380,7 @@ mod tests {
     #[tokio::test]
     async fn test_not_found() -> UCRSResult<()> {
 
-        test_with_uc(|port| async move {
-            let rc = RequestClient::new(&format!("http://localhost:{}", port), true)?;
+        test_with_cassette("catalogs_not_found", |rc| async move {
             let catalog_client = CatalogsClient::new(&rc);
 
             let res = catalog_client.delete("mycatalog", false).await;
@@ -212,4 +396,63 @@ mod tests {
         .await
         
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_update_from_info_keeps_unknown_fields() -> UCRSResult<()> {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/catalogs"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "catalogs": [{"name": "unity", "comment": "Main", "isolation_mode": "ISOLATED"}],
+                "next_page_token": "2"
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("PATCH"))
+            .and(path("/api/2.1/unity-catalog/catalogs/unity"))
+            .and(body_partial_json(serde_json::json!({"comment": "Main", "isolation_mode": "ISOLATED"})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"name": "unity"})))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("PATCH"))
+            .and(path("/api/2.1/unity-catalog/catalogs/unity"))
+            .and(|req: &wiremock::Request| !String::from_utf8_lossy(&req.body).contains("isolation_mode"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"name": "unity"})))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let rc = RequestClient::new(&server.uri(), true)?.with_flavor(Flavor::Databricks);
+        let client = CatalogsClient::new(&rc);
+        let list = client.list(None, None).await?;
+        assert_eq!(list.next_page_token(), Some("2"));
+        client.update("unity", UpdateCatalog::from(&list.catalogs()[0])).await?;
+
+        let oss = RequestClient::new(&server.uri(), true)?;
+        CatalogsClient::new(&oss).update("unity", UpdateCatalog::from(&list.catalogs()[0])).await?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_unknown_fields_round_trip() {
+        let json = serde_json::json!({
+            "name": "unity",
+            "owner": "admins",
+            "created_by": "alice",
+            "browse_only": false,
+            "isolation_mode": "OPEN",
+            "effective_predictive_optimization_flag": {"value": "ENABLE"}
+        });
+        let info: CatalogInfo = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(info.owner(), Some("admins"));
+        assert_eq!(info.created_by(), Some("alice"));
+        assert_eq!(info.browse_only(), Some(false));
+        assert_eq!(info.extra().len(), 2);
+        assert_eq!(info.extra()["isolation_mode"], "OPEN");
+
+        let round_tripped = serde_json::to_value(&info).unwrap();
+        assert_eq!(round_tripped["isolation_mode"], json["isolation_mode"]);
+        assert_eq!(round_tripped["effective_predictive_optimization_flag"], json["effective_predictive_optimization_flag"]);
+    }
+}
@@ -0,0 +1,400 @@
+use crate::{errors::UCRSError, request::RequestClient};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use crate::errors::UCRSResult;
+use crate::cassette::is_secret_key;
+use derive_builder::Builder;
+
+pub struct ConnectionsClient<'a> {
+    client: &'a RequestClient
+}
+
+impl<'a> ConnectionsClient<'a> {
+    pub fn new(client: &'a RequestClient) -> Self {
+        Self { client }
+    }
+
+    pub async fn list(&self, page_token: Option<String>, max_results: Option<i32>) -> UCRSResult<ListConnectionsResponse> {
+        let mut url = self.client.base_url.join("/api/2.1/unity-catalog/connections")
+            .map_err(UCRSError::MalformedURL)?;
+        self.client.flavor().page(&mut url, page_token, max_results);
+        self.client.get_op("connections.list", url, None::<String>).await
+    }
+
+    /// Follows `next_page_token` until every connection has been listed.
+    pub async fn list_all(&self) -> UCRSResult<Vec<ConnectionInfo>> {
+        let mut connections = vec![];
+        let mut page_token = None;
+        loop {
+            let page = self.list(page_token, None).await?;
+            connections.extend(page.connections);
+            match page.next_page_token {
+                Some(token) if !token.is_empty() => page_token = Some(token),
+                _ => return Ok(connections)
+            }
+        }
+    }
+
+    pub async fn create(&self, props: CreateConnection) -> UCRSResult<ConnectionInfo> {
+        let route = self.client.base_url.join("/api/2.1/unity-catalog/connections")
+            .map_err(UCRSError::MalformedURL)?;
+
+        let res = self.client.post_op("connections.create", route, Some(&props)).await;
+        if let Err(ref e) = res {
+            match e.status() {
+                Some(StatusCode::CONFLICT) => Err(UCRSError::DuplicateConnectionName(props.name)),
+                _ => res
+            }
+        } else {
+            res
+        }
+    }
+
+    pub async fn get(&self, name: &str) -> UCRSResult<ConnectionInfo> {
+        let path = self.client.base_url.join(&format!("/api/2.1/unity-catalog/connections/{}", name))
+            .map_err(UCRSError::MalformedURL)?;
+        let res = self.client.get_op("connections.get", path, None::<String>).await;
+        if let Err(ref e) = res {
+            match e.status() {
+                Some(StatusCode::NOT_FOUND) =>
+                    Err(UCRSError::ConnectionNotFound(name.to_owned())),
+                _ => res
+            }
+        } else {
+            res
+        }
+    }
+
+    pub async fn update(&self, name: &str, update_props: UpdateConnection) -> UCRSResult<ConnectionInfo> {
+        let path = self.client.base_url.join(&format!("/api/2.1/unity-catalog/connections/{}", name))
+            .map_err(UCRSError::MalformedURL)?;
+        let res = self.client.patch_op("connections.update", path, Some(&update_props)).await;
+        if let Err(ref e) = res {
+            match e.status() {
+                Some(StatusCode::NOT_FOUND) =>
+                    Err(UCRSError::ConnectionNotFound(name.to_owned())),
+                _ => res
+            }
+        } else {
+            res
+        }
+    }
+
+    pub async fn delete(&self, name: &str) -> UCRSResult<()> {
+        let path = self.client.base_url.join(&format!("/api/2.1/unity-catalog/connections/{}", name))
+            .map_err(UCRSError::MalformedURL)?;
+        let res = self.client.delete_op("connections.delete", path, None::<String>).await;
+        if let Err(ref e) = res {
+            match e.status() {
+                Some(StatusCode::NOT_FOUND) =>
+                    Err(UCRSError::ConnectionNotFound(name.to_owned())),
+                _ => res
+            }
+        } else {
+            res
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum ConnectionType {
+    MYSQL,
+    POSTGRESQL,
+    SNOWFLAKE,
+    REDSHIFT,
+    SQLDW,
+    SQLSERVER,
+    DATABRICKS,
+    BIGQUERY,
+    GLUE,
+    #[allow(non_camel_case_types)]
+    HIVE_METASTORE,
+    /// A value this crate doesn't know yet, kept as sent by the server.
+    #[serde(untagged)]
+    Unknown(String)
+}
+
+/// Connection options such as `host`, `port`, `user` and `password`.
+/// `Debug` output hides the values of options that look like secrets.
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[serde(transparent)]
+pub struct ConnectionOptions(HashMap<String, String>);
+
+impl ConnectionOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, key: &str, value: &str) -> Self {
+        self.0.insert(key.to_owned(), value.to_owned());
+        self
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).map(String::as_str)
+    }
+
+    pub fn is_secret(key: &str) -> bool {
+        is_secret_key(key)
+    }
+}
+
+impl From<HashMap<String, String>> for ConnectionOptions {
+    fn from(options: HashMap<String, String>) -> Self {
+        Self(options)
+    }
+}
+
+impl fmt::Debug for ConnectionOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut keys = self.0.keys().collect::<Vec<_>>();
+        keys.sort();
+        f.debug_map()
+            .entries(keys.into_iter().map(|k| {
+                let value = if Self::is_secret(k) { "REDACTED" } else { self.0[k].as_str() };
+                (k, value)
+            }))
+            .finish()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Builder, Clone, PartialEq, Eq)]
+pub struct CreateConnection {
+    name: String,
+    connection_type: ConnectionType,
+    options: ConnectionOptions,
+    #[builder(setter(strip_option), default)]
+    comment: Option<String>,
+    #[builder(setter(strip_option), default)]
+    properties: Option<HashMap<String, String>>,
+    #[builder(default)]
+    read_only: bool
+}
+
+impl CreateConnection {
+    pub fn builder() -> CreateConnectionBuilder {
+        CreateConnectionBuilder::create_empty()
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// `options` replaces the whole options map, so secrets must be sent again.
+#[derive(Serialize, Deserialize, Debug, Builder, Clone)]
+pub struct UpdateConnection {
+    options: ConnectionOptions,
+    #[builder(setter(strip_option), default)]
+    new_name: Option<String>,
+    #[builder(setter(strip_option), default)]
+    owner: Option<String>
+}
+
+impl UpdateConnection {
+    pub fn builder() -> UpdateConnectionBuilder {
+        UpdateConnectionBuilder::create_empty()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+pub struct ListConnectionsResponse {
+    connections: Vec<ConnectionInfo>,
+    next_page_token: Option<String>
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+pub struct ConnectionInfo {
+    name: Option<String>,
+    connection_id: Option<String>,
+    connection_type: Option<ConnectionType>,
+    options: Option<ConnectionOptions>,
+    comment: Option<String>,
+    properties: Option<HashMap<String, String>>,
+    owner: Option<String>,
+    read_only: Option<bool>,
+    url: Option<String>,
+    full_name: Option<String>,
+    metastore_id: Option<String>,
+    created_at: Option<i64>,
+    created_by: Option<String>,
+    updated_at: Option<i64>,
+    updated_by: Option<String>,
+    #[serde(flatten)]
+    extra: HashMap<String, serde_json::Value>
+}
+
+impl ConnectionInfo {
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn connection_id(&self) -> Option<&str> {
+        self.connection_id.as_deref()
+    }
+
+    pub fn connection_type(&self) -> Option<&ConnectionType> {
+        self.connection_type.as_ref()
+    }
+
+    pub fn options(&self) -> Option<&ConnectionOptions> {
+        self.options.as_ref()
+    }
+
+    pub fn comment(&self) -> Option<&str> {
+        self.comment.as_deref()
+    }
+
+    pub fn properties(&self) -> Option<&HashMap<String, String>> {
+        self.properties.as_ref()
+    }
+
+    pub fn owner(&self) -> Option<&str> {
+        self.owner.as_deref()
+    }
+
+    pub fn read_only(&self) -> Option<bool> {
+        self.read_only
+    }
+
+    pub fn url(&self) -> Option<&str> {
+        self.url.as_deref()
+    }
+
+    pub fn full_name(&self) -> Option<&str> {
+        self.full_name.as_deref()
+    }
+
+    pub fn metastore_id(&self) -> Option<&str> {
+        self.metastore_id.as_deref()
+    }
+
+    pub fn created_at(&self) -> Option<i64> {
+        self.created_at
+    }
+
+    pub fn created_by(&self) -> Option<&str> {
+        self.created_by.as_deref()
+    }
+
+    pub fn updated_at(&self) -> Option<i64> {
+        self.updated_at
+    }
+
+    pub fn updated_by(&self) -> Option<&str> {
+        self.updated_by.as_deref()
+    }
+
+    /// Fields returned by the server that this crate doesn't model yet.
+    pub fn extra(&self) -> &HashMap<String, serde_json::Value> {
+        &self.extra
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::oss::api::catalogs::{CatalogType, CatalogsClient, CreateCatalogBuilder};
+    use serde_json::json;
+    use wiremock::matchers::{body_json, body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_secret_options_are_redacted() {
+        let options = ConnectionOptions::new()
+            .with("host", "db.internal")
+            .with("user", "reader")
+            .with("password", "hunter2")
+            .with("pem_private_key", "-----BEGIN")
+            .with("gcp_key_json", "{}");
+        let debug = format!("{:?}", options);
+        assert_eq!(debug, r#"{"gcp_key_json": "REDACTED", "host": "db.internal", "password": "REDACTED", "pem_private_key": "REDACTED", "user": "reader"}"#);
+
+        let create = CreateConnection::builder()
+            .name("pg".to_owned())
+            .connection_type(ConnectionType::POSTGRESQL)
+            .options(options)
+            .build()
+            .unwrap();
+        assert!(!format!("{:?}", create).contains("hunter2"));
+        assert_eq!(serde_json::to_value(&create).unwrap()["options"]["password"], "hunter2");
+    }
+
+    #[tokio::test]
+    async fn test_foreign_catalog() -> UCRSResult<()> {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/2.1/unity-catalog/connections"))
+            .and(body_partial_json(json!({
+                "name": "pg",
+                "connection_type": "POSTGRESQL",
+                "options": {"host": "db.internal", "port": "5432", "user": "reader", "password": "hunter2"}
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "name": "pg",
+                "connection_type": "POSTGRESQL",
+                "options": {"host": "db.internal", "port": "5432"},
+                "url": "jdbc://db.internal:5432/"
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/connections/teradata"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"name": "teradata", "connection_type": "TERADATA"})))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/2.1/unity-catalog/catalogs"))
+            .and(body_json(json!({
+                "name": "orders_pg",
+                "comment": null,
+                "properties": null,
+                "catalog_type": "FOREIGN_CATALOG",
+                "connection_name": "pg",
+                "options": {"database": "orders"}
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "name": "orders_pg",
+                "catalog_type": "FOREIGN_CATALOG",
+                "connection_name": "pg",
+                "options": {"database": "orders"}
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let rc = RequestClient::new(&server.uri(), true)?;
+        let connections = ConnectionsClient::new(&rc);
+        let created = connections.create(CreateConnection::builder()
+            .name("pg".to_owned())
+            .connection_type(ConnectionType::POSTGRESQL)
+            .options(ConnectionOptions::new()
+                .with("host", "db.internal")
+                .with("port", "5432")
+                .with("user", "reader")
+                .with("password", "hunter2"))
+            .build()
+            .unwrap()).await?;
+        assert_eq!(created.options().and_then(|o| o.get("port")), Some("5432"));
+        assert_eq!(created.url(), Some("jdbc://db.internal:5432/"));
+
+        let other = connections.get("teradata").await?;
+        assert_eq!(other.connection_type(), Some(&ConnectionType::Unknown("TERADATA".to_owned())));
+
+        let catalog = CatalogsClient::new(&rc).create(CreateCatalogBuilder::default()
+            .name("orders_pg".to_owned())
+            .comment(None)
+            .properties(None)
+            .catalog_type(Some(CatalogType::FOREIGN_CATALOG))
+            .connection_name(Some("pg".to_owned()))
+            .options(Some(HashMap::from([("database".to_owned(), "orders".to_owned())])))
+            .build()
+            .unwrap()).await?;
+        assert_eq!(catalog.catalog_type(), Some(&CatalogType::FOREIGN_CATALOG));
+        assert_eq!(catalog.connection_name(), Some("pg"));
+        Ok(())
+    }
+}
@@ -0,0 +1,314 @@
+use crate::{errors::UCRSError, request::RequestClient};
+use crate::errors::UCRSResult;
+use crate::oss::api::tables::{ColumnInfo, CreateChecks, TablesClient};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+
+pub struct ConstraintsClient<'a> {
+    client: &'a RequestClient
+}
+
+impl<'a> ConstraintsClient<'a> {
+    pub fn new(client: &'a RequestClient) -> Self {
+        Self { client }
+    }
+
+    /// Adds `constraint` to the table `full_name` after checking that its
+    /// columns, and for a foreign key the parent's columns, exist.
+    pub async fn create(&self, full_name: &str, constraint: TableConstraint) -> UCRSResult<TableConstraint> {
+        let tables = TablesClient::new(self.client);
+        let table = tables.get(full_name).await?;
+        check_constraint(&tables, &CreateChecks::default(), full_name, table.columns(), &constraint).await?;
+
+        let route = self.client.base_url.join("/api/2.1/unity-catalog/constraints")
+            .map_err(UCRSError::MalformedURL)?;
+        let body = CreateTableConstraint {
+            full_name_arg: full_name.to_owned(),
+            constraint
+        };
+        let res = self.client.post_op("constraints.create", route, Some(&body)).await;
+        if let Err(ref e) = res {
+            match e.status() {
+                Some(StatusCode::NOT_FOUND) => Err(UCRSError::TableNotFound(full_name.to_owned())),
+                _ => res
+            }
+        } else {
+            res
+        }
+    }
+
+    /// Drops the named constraint. With `cascade`, foreign keys referencing a
+    /// dropped primary key are dropped too.
+    pub async fn delete(&self, full_name: &str, constraint_name: &str, cascade: bool) -> UCRSResult<()> {
+        let mut path = self.client.base_url.join(&format!("/api/2.1/unity-catalog/constraints/{}", full_name))
+            .map_err(UCRSError::MalformedURL)?;
+        path.query_pairs_mut()
+            .append_pair("constraint_name", constraint_name)
+            .append_pair("cascade", &cascade.to_string());
+        let res = self.client.delete_op("constraints.delete", path, None::<String>).await;
+        if let Err(ref e) = res {
+            match e.status() {
+                Some(StatusCode::NOT_FOUND) => Err(UCRSError::TableNotFound(full_name.to_owned())),
+                _ => res
+            }
+        } else {
+            res
+        }
+    }
+}
+
+/// Checks that the columns `constraint` names exist in `columns`, those of
+/// `full_name`, and for a foreign key in the parent table, looked up through
+/// `checks`. Constraints this crate doesn't model are left to the server.
+pub(crate) async fn check_constraint(tables: &TablesClient<'_>, checks: &CreateChecks, full_name: &str, columns: &[ColumnInfo], constraint: &TableConstraint) -> UCRSResult<()> {
+    check_columns(constraint, full_name, constraint.child_columns(), columns)?;
+    if let TableConstraint::ForeignKey(fk) = constraint {
+        if fk.parent_table == full_name {
+            check_columns(constraint, full_name, &fk.parent_columns, columns)?;
+        } else {
+            let parent_columns = checks.parent_columns(tables, &fk.parent_table).await?;
+            check_columns(constraint, &fk.parent_table, &fk.parent_columns, &parent_columns)?;
+        }
+    }
+    Ok(())
+}
+
+fn check_columns(constraint: &TableConstraint, table: &str, wanted: &[String], columns: &[ColumnInfo]) -> UCRSResult<()> {
+    if wanted.is_empty() && !matches!(constraint, TableConstraint::Named(_) | TableConstraint::Unknown(_)) {
+        return Err(UCRSError::InvalidConstraint(constraint.name().to_owned(), "no columns given".to_owned()));
+    }
+    match wanted.iter().find(|w| !columns.iter().any(|c| c.name() == Some(w.as_str()))) {
+        Some(missing) => Err(UCRSError::InvalidConstraint(
+            constraint.name().to_owned(),
+            format!("column {} does not exist in {}", missing, table)
+        )),
+        None => Ok(())
+    }
+}
+
+#[derive(Serialize, Debug)]
+struct CreateTableConstraint {
+    full_name_arg: String,
+    constraint: TableConstraint
+}
+
+/// An informational constraint. The server doesn't enforce these.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum TableConstraint {
+    #[serde(rename = "primary_key_constraint")]
+    PrimaryKey(PrimaryKeyConstraint),
+    #[serde(rename = "foreign_key_constraint")]
+    ForeignKey(ForeignKeyConstraint),
+    #[serde(rename = "named_table_constraint")]
+    Named(NamedTableConstraint),
+    /// A constraint kind this crate doesn't know yet, kept as sent by the server.
+    #[serde(untagged)]
+    Unknown(serde_json::Value)
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PrimaryKeyConstraint {
+    name: String,
+    child_columns: Vec<String>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ForeignKeyConstraint {
+    name: String,
+    child_columns: Vec<String>,
+    parent_table: String,
+    parent_columns: Vec<String>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct NamedTableConstraint {
+    name: String
+}
+
+fn owned(columns: &[&str]) -> Vec<String> {
+    columns.iter().map(|c| c.to_string()).collect()
+}
+
+impl TableConstraint {
+    pub fn primary_key(name: &str, columns: &[&str]) -> Self {
+        TableConstraint::PrimaryKey(PrimaryKeyConstraint {
+            name: name.to_owned(),
+            child_columns: owned(columns)
+        })
+    }
+
+    /// `columns` in this table reference `parent_columns` of `parent_table`, in order.
+    pub fn foreign_key(name: &str, columns: &[&str], parent_table: &str, parent_columns: &[&str]) -> Self {
+        TableConstraint::ForeignKey(ForeignKeyConstraint {
+            name: name.to_owned(),
+            child_columns: owned(columns),
+            parent_table: parent_table.to_owned(),
+            parent_columns: owned(parent_columns)
+        })
+    }
+
+    pub fn named(name: &str) -> Self {
+        TableConstraint::Named(NamedTableConstraint { name: name.to_owned() })
+    }
+
+    /// For an unknown constraint, the `name` inside it if there is one.
+    pub fn name(&self) -> &str {
+        match self {
+            TableConstraint::PrimaryKey(pk) => &pk.name,
+            TableConstraint::ForeignKey(fk) => &fk.name,
+            TableConstraint::Named(n) => &n.name,
+            TableConstraint::Unknown(value) => value.as_object()
+                .and_then(|o| o.values().next())
+                .and_then(|c| c.get("name"))
+                .and_then(serde_json::Value::as_str)
+                .unwrap_or_default()
+        }
+    }
+
+    pub fn child_columns(&self) -> &[String] {
+        match self {
+            TableConstraint::PrimaryKey(pk) => &pk.child_columns,
+            TableConstraint::ForeignKey(fk) => &fk.child_columns,
+            TableConstraint::Named(_) | TableConstraint::Unknown(_) => &[]
+        }
+    }
+}
+
+impl ForeignKeyConstraint {
+    pub fn parent_table(&self) -> &str {
+        &self.parent_table
+    }
+
+    pub fn parent_columns(&self) -> &[String] {
+        &self.parent_columns
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::oss::api::tables::{CreateTable, DataSourceFormat, TableType};
+    use serde_json::json;
+    use wiremock::matchers::{body_json, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    async fn mock_tables() -> MockServer {
+        let server = MockServer::start().await;
+        for (name, columns) in [("orders", vec!["id", "customer_id"]), ("customers", vec!["id"])] {
+            Mock::given(method("GET"))
+                .and(path(format!("/api/2.1/unity-catalog/tables/unity.sales.{}", name)))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                    "name": name,
+                    "columns": columns.iter().map(|c| json!({"name": c, "type_name": "LONG"})).collect::<Vec<_>>()
+                })))
+                .mount(&server)
+                .await;
+        }
+        server
+    }
+
+    #[tokio::test]
+    async fn test_create_and_delete() -> UCRSResult<()> {
+        let server = mock_tables().await;
+        let fk = json!({"foreign_key_constraint": {
+            "name": "orders_customer_fk",
+            "child_columns": ["customer_id"],
+            "parent_table": "unity.sales.customers",
+            "parent_columns": ["id"]
+        }});
+        Mock::given(method("POST"))
+            .and(path("/api/2.1/unity-catalog/constraints"))
+            .and(body_json(json!({"full_name_arg": "unity.sales.orders", "constraint": fk})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(&fk))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("DELETE"))
+            .and(path("/api/2.1/unity-catalog/constraints/unity.sales.orders"))
+            .and(query_param("constraint_name", "orders_customer_fk"))
+            .and(query_param("cascade", "false"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let rc = RequestClient::new(&server.uri(), true)?;
+        let client = ConstraintsClient::new(&rc);
+        let constraint = TableConstraint::foreign_key("orders_customer_fk", &["customer_id"], "unity.sales.customers", &["id"]);
+        let created = client.create("unity.sales.orders", constraint.clone()).await?;
+        assert_eq!(created, constraint);
+        client.delete("unity.sales.orders", "orders_customer_fk", false).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_missing_columns_are_rejected() -> UCRSResult<()> {
+        let server = mock_tables().await;
+        let rc = RequestClient::new(&server.uri(), true)?;
+        let client = ConstraintsClient::new(&rc);
+
+        let res = client.create("unity.sales.orders", TableConstraint::primary_key("orders_pk", &["order_id"])).await;
+        assert!(matches!(res, Err(UCRSError::InvalidConstraint(ref n, ref reason))
+            if n == "orders_pk" && reason == "column order_id does not exist in unity.sales.orders"));
+
+        let fk = TableConstraint::foreign_key("fk", &["customer_id"], "unity.sales.customers", &["customer_id"]);
+        let res = client.create("unity.sales.orders", fk).await;
+        assert!(matches!(res, Err(UCRSError::InvalidConstraint(_, ref reason)) if reason.ends_with("unity.sales.customers")));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_table_checks_constraints() -> UCRSResult<()> {
+        let server = mock_tables().await;
+        Mock::given(method("POST"))
+            .and(path("/api/2.1/unity-catalog/tables"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"name": "payments"})))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let rc = RequestClient::new(&server.uri(), true)?;
+        let tables = TablesClient::new(&rc);
+
+        let payments = |constraints: Vec<TableConstraint>| CreateTable::builder()
+            .name("payments".to_owned())
+            .catalog_name("unity".to_owned())
+            .schema_name("sales".to_owned())
+            .table_type(TableType::EXTERNAL)
+            .data_source_format(DataSourceFormat::DELTA)
+            .columns(vec![
+                ColumnInfo::builder().name("id".to_owned()).build().unwrap(),
+                ColumnInfo::builder().name("order_id".to_owned()).build().unwrap()
+            ])
+            .storage_location(Some("s3://bucket/payments".to_owned()))
+            .table_constraints(Some(constraints))
+            .build()
+            .unwrap();
+
+        let res = tables.create(payments(vec![TableConstraint::primary_key("payments_pk", &["payment_id"])])).await;
+        assert!(matches!(res, Err(UCRSError::InvalidConstraint(ref n, ref reason))
+            if n == "payments_pk" && reason == "column payment_id does not exist in unity.sales.payments"));
+        let fk = TableConstraint::foreign_key("payments_order_fk", &["order_id"], "unity.sales.orders", &["order_id"]);
+        let res = tables.create(payments(vec![fk])).await;
+        assert!(matches!(res, Err(UCRSError::InvalidConstraint(_, ref reason)) if reason.ends_with("unity.sales.orders")));
+
+        tables.create(payments(vec![
+            TableConstraint::primary_key("payments_pk", &["id"]),
+            TableConstraint::foreign_key("payments_order_fk", &["order_id"], "unity.sales.orders", &["id"]),
+            TableConstraint::foreign_key("payments_self_fk", &["order_id"], "unity.sales.payments", &["id"])
+        ])).await?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_unknown_constraint_round_trip() {
+        let json = json!({"check_constraint": {"name": "positive_amount", "condition": "amount > 0"}});
+        let constraint: TableConstraint = serde_json::from_value(json.clone()).unwrap();
+        assert!(matches!(constraint, TableConstraint::Unknown(_)));
+        assert_eq!(constraint.name(), "positive_amount");
+        assert!(constraint.child_columns().is_empty());
+        assert_eq!(serde_json::to_value(&constraint).unwrap(), json);
+
+        let pk: TableConstraint = serde_json::from_value(json!({"primary_key_constraint": {"name": "pk", "child_columns": ["id"]}})).unwrap();
+        assert_eq!(pk, TableConstraint::primary_key("pk", &["id"]));
+    }
+}
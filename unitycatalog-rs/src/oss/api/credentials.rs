@@ -0,0 +1,512 @@
+use crate::{errors::UCRSError, request::RequestClient};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use crate::errors::UCRSResult;
+use derive_builder::Builder;
+
+pub struct StorageCredentialsClient<'a> {
+    client: &'a RequestClient
+}
+
+impl<'a> StorageCredentialsClient<'a> {
+    pub fn new(client: &'a RequestClient) -> Self {
+        Self { client }
+    }
+
+    pub async fn list(&self, page_token: Option<String>, max_results: Option<i32>) -> UCRSResult<ListStorageCredentialsResponse> {
+        let mut url = self.client.base_url.join("/api/2.1/unity-catalog/storage-credentials")
+            .map_err(UCRSError::MalformedURL)?;
+        self.client.flavor().page(&mut url, page_token, max_results);
+        self.client.get_op("storage_credentials.list", url, None::<String>).await
+    }
+
+    /// Follows `next_page_token` until every credential has been listed.
+    pub async fn list_all(&self) -> UCRSResult<Vec<StorageCredentialInfo>> {
+        let mut credentials = vec![];
+        let mut page_token = None;
+        loop {
+            let page = self.list(page_token, None).await?;
+            credentials.extend(page.storage_credentials);
+            match page.next_page_token {
+                Some(token) if !token.is_empty() => page_token = Some(token),
+                _ => return Ok(credentials)
+            }
+        }
+    }
+
+    pub async fn create(&self, props: CreateStorageCredential) -> UCRSResult<StorageCredentialInfo> {
+        let route = self.client.base_url.join("/api/2.1/unity-catalog/storage-credentials")
+            .map_err(UCRSError::MalformedURL)?;
+
+        let res = self.client.post_op("storage_credentials.create", route, Some(&props)).await;
+        if let Err(ref e) = res {
+            match e.status() {
+                Some(StatusCode::CONFLICT) => Err(UCRSError::DuplicateStorageCredentialName(props.name)),
+                _ => res
+            }
+        } else {
+            res
+        }
+    }
+
+    pub async fn get(&self, name: &str) -> UCRSResult<StorageCredentialInfo> {
+        let path = self.client.base_url.join(&format!("/api/2.1/unity-catalog/storage-credentials/{}", name))
+            .map_err(UCRSError::MalformedURL)?;
+        let res = self.client.get_op("storage_credentials.get", path, None::<String>).await;
+        if let Err(ref e) = res {
+            match e.status() {
+                Some(StatusCode::NOT_FOUND) =>
+                    Err(UCRSError::StorageCredentialNotFound(name.to_owned())),
+                _ => res
+            }
+        } else {
+            res
+        }
+    }
+
+    pub async fn update(&self, name: &str, update_props: UpdateStorageCredential) -> UCRSResult<StorageCredentialInfo> {
+        let path = self.client.base_url.join(&format!("/api/2.1/unity-catalog/storage-credentials/{}", name))
+            .map_err(UCRSError::MalformedURL)?;
+        let res = self.client.patch_op("storage_credentials.update", path, Some(&update_props)).await;
+        if let Err(ref e) = res {
+            match e.status() {
+                Some(StatusCode::NOT_FOUND) =>
+                    Err(UCRSError::StorageCredentialNotFound(name.to_owned())),
+                _ => res
+            }
+        } else {
+            res
+        }
+    }
+
+    /// With `force`, deletes the credential even if external locations still use it.
+    pub async fn delete(&self, name: &str, force: bool) -> UCRSResult<()> {
+        let mut path = self.client.base_url.join(&format!("/api/2.1/unity-catalog/storage-credentials/{}", name))
+            .map_err(UCRSError::MalformedURL)?;
+        path.query_pairs_mut().append_pair("force", &force.to_string());
+        let res = self.client.delete_op("storage_credentials.delete", path, None::<String>).await;
+        if let Err(ref e) = res {
+            match e.status() {
+                Some(StatusCode::NOT_FOUND) =>
+                    Err(UCRSError::StorageCredentialNotFound(name.to_owned())),
+                _ => res
+            }
+        } else {
+            res
+        }
+    }
+
+    /// Asks the server to check what the credential can do at `url`.
+    pub async fn validate(&self, name: &str, url: &str, read_only: bool) -> UCRSResult<ValidationReport> {
+        let route = self.client.base_url.join("/api/2.1/unity-catalog/validate-storage-credentials")
+            .map_err(UCRSError::MalformedURL)?;
+        let body = ValidateStorageCredential {
+            storage_credential_name: name.to_owned(),
+            url: url.to_owned(),
+            read_only
+        };
+        let res = self.client.post_op("storage_credentials.validate", route, Some(&body)).await;
+        if let Err(ref e) = res {
+            match e.status() {
+                Some(StatusCode::NOT_FOUND) =>
+                    Err(UCRSError::StorageCredentialNotFound(name.to_owned())),
+                _ => res
+            }
+        } else {
+            res
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct AwsIamRole {
+    role_arn: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    external_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    unity_catalog_iam_arn: Option<String>
+}
+
+impl AwsIamRole {
+    pub fn new(role_arn: &str) -> Self {
+        Self {
+            role_arn: role_arn.to_owned(),
+            external_id: None,
+            unity_catalog_iam_arn: None
+        }
+    }
+
+    pub fn role_arn(&self) -> &str {
+        &self.role_arn
+    }
+
+    /// Set by the server; the role's trust policy must require it.
+    pub fn external_id(&self) -> Option<&str> {
+        self.external_id.as_deref()
+    }
+
+    pub fn unity_catalog_iam_arn(&self) -> Option<&str> {
+        self.unity_catalog_iam_arn.as_deref()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct AzureServicePrincipal {
+    directory_id: String,
+    application_id: String,
+    /// Write-only: the server never returns it.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    client_secret: String
+}
+
+impl AzureServicePrincipal {
+    pub fn new(directory_id: &str, application_id: &str, client_secret: &str) -> Self {
+        Self {
+            directory_id: directory_id.to_owned(),
+            application_id: application_id.to_owned(),
+            client_secret: client_secret.to_owned()
+        }
+    }
+
+    pub fn directory_id(&self) -> &str {
+        &self.directory_id
+    }
+
+    pub fn application_id(&self) -> &str {
+        &self.application_id
+    }
+}
+
+impl fmt::Debug for AzureServicePrincipal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AzureServicePrincipal")
+            .field("directory_id", &self.directory_id)
+            .field("application_id", &self.application_id)
+            .field("client_secret", &"REDACTED")
+            .finish()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct AzureManagedIdentity {
+    access_connector_id: String,
+    managed_identity_id: Option<String>
+}
+
+impl AzureManagedIdentity {
+    pub fn new(access_connector_id: &str, managed_identity_id: Option<&str>) -> Self {
+        Self {
+            access_connector_id: access_connector_id.to_owned(),
+            managed_identity_id: managed_identity_id.map(str::to_owned)
+        }
+    }
+
+    pub fn access_connector_id(&self) -> &str {
+        &self.access_connector_id
+    }
+
+    pub fn managed_identity_id(&self) -> Option<&str> {
+        self.managed_identity_id.as_deref()
+    }
+}
+
+/// Exactly one of the cloud-specific identities should be set.
+#[derive(Serialize, Deserialize, Debug, Builder, Clone, PartialEq, Eq)]
+pub struct CreateStorageCredential {
+    name: String,
+    #[builder(setter(strip_option), default)]
+    comment: Option<String>,
+    #[builder(default)]
+    read_only: bool,
+    #[builder(setter(strip_option), default)]
+    aws_iam_role: Option<AwsIamRole>,
+    #[builder(setter(strip_option), default)]
+    azure_service_principal: Option<AzureServicePrincipal>,
+    #[builder(setter(strip_option), default)]
+    azure_managed_identity: Option<AzureManagedIdentity>,
+    #[builder(default)]
+    skip_validation: bool
+}
+
+impl CreateStorageCredential {
+    pub fn builder() -> CreateStorageCredentialBuilder {
+        CreateStorageCredentialBuilder::create_empty()
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Builder, Clone)]
+#[builder(setter(strip_option), default)]
+pub struct UpdateStorageCredential {
+    new_name: Option<String>,
+    comment: Option<String>,
+    owner: Option<String>,
+    read_only: Option<bool>,
+    aws_iam_role: Option<AwsIamRole>,
+    azure_service_principal: Option<AzureServicePrincipal>,
+    azure_managed_identity: Option<AzureManagedIdentity>,
+    skip_validation: Option<bool>,
+    force: Option<bool>
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+pub struct ListStorageCredentialsResponse {
+    storage_credentials: Vec<StorageCredentialInfo>,
+    next_page_token: Option<String>
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+pub struct StorageCredentialInfo {
+    name: Option<String>,
+    id: Option<String>,
+    comment: Option<String>,
+    owner: Option<String>,
+    read_only: Option<bool>,
+    aws_iam_role: Option<AwsIamRole>,
+    azure_service_principal: Option<AzureServicePrincipal>,
+    azure_managed_identity: Option<AzureManagedIdentity>,
+    used_for_managed_storage: Option<bool>,
+    full_name: Option<String>,
+    metastore_id: Option<String>,
+    created_at: Option<i64>,
+    created_by: Option<String>,
+    updated_at: Option<i64>,
+    updated_by: Option<String>,
+    #[serde(flatten)]
+    extra: HashMap<String, serde_json::Value>
+}
+
+impl StorageCredentialInfo {
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    pub fn comment(&self) -> Option<&str> {
+        self.comment.as_deref()
+    }
+
+    pub fn owner(&self) -> Option<&str> {
+        self.owner.as_deref()
+    }
+
+    pub fn read_only(&self) -> Option<bool> {
+        self.read_only
+    }
+
+    pub fn aws_iam_role(&self) -> Option<&AwsIamRole> {
+        self.aws_iam_role.as_ref()
+    }
+
+    pub fn azure_service_principal(&self) -> Option<&AzureServicePrincipal> {
+        self.azure_service_principal.as_ref()
+    }
+
+    pub fn azure_managed_identity(&self) -> Option<&AzureManagedIdentity> {
+        self.azure_managed_identity.as_ref()
+    }
+
+    pub fn used_for_managed_storage(&self) -> Option<bool> {
+        self.used_for_managed_storage
+    }
+
+    pub fn full_name(&self) -> Option<&str> {
+        self.full_name.as_deref()
+    }
+
+    pub fn metastore_id(&self) -> Option<&str> {
+        self.metastore_id.as_deref()
+    }
+
+    pub fn created_at(&self) -> Option<i64> {
+        self.created_at
+    }
+
+    pub fn created_by(&self) -> Option<&str> {
+        self.created_by.as_deref()
+    }
+
+    pub fn updated_at(&self) -> Option<i64> {
+        self.updated_at
+    }
+
+    pub fn updated_by(&self) -> Option<&str> {
+        self.updated_by.as_deref()
+    }
+
+    /// Fields returned by the server that this crate doesn't model yet.
+    pub fn extra(&self) -> &HashMap<String, serde_json::Value> {
+        &self.extra
+    }
+}
+
+#[derive(Serialize, Debug)]
+struct ValidateStorageCredential {
+    storage_credential_name: String,
+    url: String,
+    read_only: bool
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum ValidationOutcome {
+    PASS,
+    FAIL,
+    SKIP,
+    #[serde(untagged)]
+    Unknown(String)
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ValidationResult {
+    operation: Option<String>,
+    result: Option<ValidationOutcome>,
+    message: Option<String>
+}
+
+impl ValidationResult {
+    /// e.g. `READ`, `WRITE`, `DELETE` or `LIST`.
+    pub fn operation(&self) -> Option<&str> {
+        self.operation.as_deref()
+    }
+
+    pub fn result(&self) -> Option<&ValidationOutcome> {
+        self.result.as_ref()
+    }
+
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationReport {
+    #[serde(rename = "isDir")]
+    is_dir: Option<bool>,
+    #[serde(default)]
+    results: Vec<ValidationResult>
+}
+
+impl ValidationReport {
+    pub fn is_dir(&self) -> Option<bool> {
+        self.is_dir
+    }
+
+    pub fn results(&self) -> &[ValidationResult] {
+        &self.results
+    }
+
+    /// Whether no operation failed. Skipped operations don't count as failures.
+    pub fn passed(&self) -> bool {
+        !self.results.iter().any(|r| r.result == Some(ValidationOutcome::FAIL))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wiremock::matchers::{body_json, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_crud_and_validate() -> UCRSResult<()> {
+        let server = MockServer::start().await;
+        let info = json!({
+            "name": "lake",
+            "aws_iam_role": {
+                "role_arn": "arn:aws:iam::123:role/lake",
+                "external_id": "ext-1",
+                "unity_catalog_iam_arn": "arn:aws:iam::456:role/uc"
+            },
+            "read_only": false,
+            "owner": "admins"
+        });
+        Mock::given(method("POST"))
+            .and(path("/api/2.1/unity-catalog/storage-credentials"))
+            .and(body_json(json!({
+                "name": "lake",
+                "comment": "Data lake",
+                "read_only": false,
+                "aws_iam_role": {"role_arn": "arn:aws:iam::123:role/lake"},
+                "azure_service_principal": null,
+                "azure_managed_identity": null,
+                "skip_validation": false
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(&info))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/2.1/unity-catalog/storage-credentials"))
+            .respond_with(ResponseTemplate::new(409))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/storage-credentials/missing"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/2.1/unity-catalog/validate-storage-credentials"))
+            .and(body_json(json!({"storage_credential_name": "lake", "url": "s3://lake/raw", "read_only": true})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "isDir": true,
+                "results": [
+                    {"operation": "READ", "result": "PASS"},
+                    {"operation": "LIST", "result": "FAIL", "message": "Access denied"},
+                    {"operation": "WRITE", "result": "SKIP"}
+                ]
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("DELETE"))
+            .and(path("/api/2.1/unity-catalog/storage-credentials/lake"))
+            .and(query_param("force", "true"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let rc = RequestClient::new(&server.uri(), true)?;
+        let client = StorageCredentialsClient::new(&rc);
+        let create = CreateStorageCredential::builder()
+            .name("lake".to_owned())
+            .comment("Data lake".to_owned())
+            .aws_iam_role(AwsIamRole::new("arn:aws:iam::123:role/lake"))
+            .build()
+            .unwrap();
+        let created = client.create(create.clone()).await?;
+        assert_eq!(created.aws_iam_role().and_then(|r| r.external_id()), Some("ext-1"));
+        assert!(matches!(client.create(create).await, Err(UCRSError::DuplicateStorageCredentialName(ref n)) if n == "lake"));
+        assert!(matches!(client.get("missing").await, Err(UCRSError::StorageCredentialNotFound(ref n)) if n == "missing"));
+
+        let report = client.validate("lake", "s3://lake/raw", true).await?;
+        assert_eq!(report.is_dir(), Some(true));
+        assert!(!report.passed());
+        assert_eq!(report.results()[1].message(), Some("Access denied"));
+
+        client.delete("lake", true).await?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_client_secret_is_redacted() {
+        let create = CreateStorageCredential::builder()
+            .name("lake".to_owned())
+            .azure_service_principal(AzureServicePrincipal::new("tenant-1", "app-1", "hunter2"))
+            .build()
+            .unwrap();
+        let debug = format!("{:?}", create);
+        assert!(debug.contains("app-1") && debug.contains("REDACTED") && !debug.contains("hunter2"));
+        assert_eq!(serde_json::to_value(&create).unwrap()["azure_service_principal"]["client_secret"], "hunter2");
+    }
+}
@@ -0,0 +1,300 @@
+use crate::{errors::UCRSError, request::RequestClient};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use url::Url;
+use crate::errors::UCRSResult;
+use derive_builder::Builder;
+
+pub struct ExternalLocationsClient<'a> {
+    client: &'a RequestClient
+}
+
+impl<'a> ExternalLocationsClient<'a> {
+    pub fn new(client: &'a RequestClient) -> Self {
+        Self { client }
+    }
+
+    pub async fn list(&self, page_token: Option<String>, max_results: Option<i32>) -> UCRSResult<ListExternalLocationsResponse> {
+        let mut url = self.client.base_url.join("/api/2.1/unity-catalog/external-locations")
+            .map_err(UCRSError::MalformedURL)?;
+        self.client.flavor().page(&mut url, page_token, max_results);
+        self.client.get_op("external_locations.list", url, None::<String>).await
+    }
+
+    /// Follows `next_page_token` until every external location has been listed.
+    pub async fn list_all(&self) -> UCRSResult<Vec<ExternalLocationInfo>> {
+        let mut locations = vec![];
+        let mut page_token = None;
+        loop {
+            let page = self.list(page_token, None).await?;
+            locations.extend(page.external_locations);
+            match page.next_page_token {
+                Some(token) if !token.is_empty() => page_token = Some(token),
+                _ => return Ok(locations)
+            }
+        }
+    }
+
+    pub async fn create(&self, props: CreateExternalLocation) -> UCRSResult<ExternalLocationInfo> {
+        let route = self.client.base_url.join("/api/2.1/unity-catalog/external-locations")
+            .map_err(UCRSError::MalformedURL)?;
+
+        let res = self.client.post_op("external_locations.create", route, Some(&props)).await;
+        if let Err(ref e) = res {
+            match e.status() {
+                Some(StatusCode::CONFLICT) => Err(UCRSError::DuplicateExternalLocationName(props.name)),
+                _ => res
+            }
+        } else {
+            res
+        }
+    }
+
+    pub async fn get(&self, name: &str) -> UCRSResult<ExternalLocationInfo> {
+        let path = self.client.base_url.join(&format!("/api/2.1/unity-catalog/external-locations/{}", name))
+            .map_err(UCRSError::MalformedURL)?;
+        let res = self.client.get_op("external_locations.get", path, None::<String>).await;
+        if let Err(ref e) = res {
+            match e.status() {
+                Some(StatusCode::NOT_FOUND) =>
+                    Err(UCRSError::ExternalLocationNotFound(name.to_owned())),
+                _ => res
+            }
+        } else {
+            res
+        }
+    }
+
+    pub async fn update(&self, name: &str, update_props: UpdateExternalLocation) -> UCRSResult<ExternalLocationInfo> {
+        let path = self.client.base_url.join(&format!("/api/2.1/unity-catalog/external-locations/{}", name))
+            .map_err(UCRSError::MalformedURL)?;
+        let res = self.client.patch_op("external_locations.update", path, Some(&update_props)).await;
+        if let Err(ref e) = res {
+            match e.status() {
+                Some(StatusCode::NOT_FOUND) =>
+                    Err(UCRSError::ExternalLocationNotFound(name.to_owned())),
+                _ => res
+            }
+        } else {
+            res
+        }
+    }
+
+    /// With `force`, deletes the location even if tables still use it.
+    pub async fn delete(&self, name: &str, force: bool) -> UCRSResult<()> {
+        let mut path = self.client.base_url.join(&format!("/api/2.1/unity-catalog/external-locations/{}", name))
+            .map_err(UCRSError::MalformedURL)?;
+        path.query_pairs_mut().append_pair("force", &force.to_string());
+        let res = self.client.delete_op("external_locations.delete", path, None::<String>).await;
+        if let Err(ref e) = res {
+            match e.status() {
+                Some(StatusCode::NOT_FOUND) =>
+                    Err(UCRSError::ExternalLocationNotFound(name.to_owned())),
+                _ => res
+            }
+        } else {
+            res
+        }
+    }
+
+    /// The external location whose URL most specifically contains
+    /// `storage_location`, if any.
+    pub async fn covering(&self, storage_location: &str) -> UCRSResult<Option<ExternalLocationInfo>> {
+        let target = Url::parse(storage_location).map_err(UCRSError::MalformedURL)?;
+        let covering = self.list_all().await?
+            .into_iter()
+            .filter_map(|l| {
+                let depth = l.url.as_deref()
+                    .and_then(|u| Url::parse(u).ok())
+                    .and_then(|u| covering_depth(&u, &target))?;
+                Some((depth, l))
+            })
+            .max_by_key(|(depth, _)| *depth)
+            .map(|(_, l)| l);
+        Ok(covering)
+    }
+}
+
+fn segments(url: &Url) -> Vec<&str> {
+    url.path().split('/').filter(|s| !s.is_empty()).collect()
+}
+
+/// Number of path segments in `location` if it contains `target`. Matching is
+/// by whole segment, so `s3://b/data` doesn't cover `s3://b/database`.
+fn covering_depth(location: &Url, target: &Url) -> Option<usize> {
+    let same_root = location.scheme() == target.scheme()
+        && location.username() == target.username()
+        && location.host_str() == target.host_str()
+        && location.port() == target.port();
+    let location_segments = segments(location);
+    let target_segments = segments(target);
+    (same_root && target_segments.starts_with(&location_segments)).then_some(location_segments.len())
+}
+
+#[derive(Serialize, Deserialize, Debug, Builder, Clone, PartialEq, Eq)]
+pub struct CreateExternalLocation {
+    name: String,
+    url: String,
+    credential_name: String,
+    #[builder(setter(strip_option), default)]
+    comment: Option<String>,
+    #[builder(default)]
+    read_only: bool,
+    #[builder(default)]
+    skip_validation: bool
+}
+
+impl CreateExternalLocation {
+    pub fn builder() -> CreateExternalLocationBuilder {
+        CreateExternalLocationBuilder::create_empty()
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Builder, Clone)]
+#[builder(setter(strip_option), default)]
+pub struct UpdateExternalLocation {
+    new_name: Option<String>,
+    url: Option<String>,
+    credential_name: Option<String>,
+    comment: Option<String>,
+    owner: Option<String>,
+    read_only: Option<bool>,
+    skip_validation: Option<bool>,
+    force: Option<bool>
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+pub struct ListExternalLocationsResponse {
+    external_locations: Vec<ExternalLocationInfo>,
+    next_page_token: Option<String>
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+pub struct ExternalLocationInfo {
+    name: Option<String>,
+    url: Option<String>,
+    credential_name: Option<String>,
+    credential_id: Option<String>,
+    comment: Option<String>,
+    owner: Option<String>,
+    read_only: Option<bool>,
+    metastore_id: Option<String>,
+    created_at: Option<i64>,
+    created_by: Option<String>,
+    updated_at: Option<i64>,
+    updated_by: Option<String>,
+    #[serde(flatten)]
+    extra: HashMap<String, serde_json::Value>
+}
+
+impl ExternalLocationInfo {
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn url(&self) -> Option<&str> {
+        self.url.as_deref()
+    }
+
+    pub fn credential_name(&self) -> Option<&str> {
+        self.credential_name.as_deref()
+    }
+
+    pub fn credential_id(&self) -> Option<&str> {
+        self.credential_id.as_deref()
+    }
+
+    pub fn comment(&self) -> Option<&str> {
+        self.comment.as_deref()
+    }
+
+    pub fn owner(&self) -> Option<&str> {
+        self.owner.as_deref()
+    }
+
+    pub fn read_only(&self) -> Option<bool> {
+        self.read_only
+    }
+
+    pub fn metastore_id(&self) -> Option<&str> {
+        self.metastore_id.as_deref()
+    }
+
+    pub fn created_at(&self) -> Option<i64> {
+        self.created_at
+    }
+
+    pub fn created_by(&self) -> Option<&str> {
+        self.created_by.as_deref()
+    }
+
+    pub fn updated_at(&self) -> Option<i64> {
+        self.updated_at
+    }
+
+    pub fn updated_by(&self) -> Option<&str> {
+        self.updated_by.as_deref()
+    }
+
+    /// Fields returned by the server that this crate doesn't model yet.
+    pub fn extra(&self) -> &HashMap<String, serde_json::Value> {
+        &self.extra
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_create_and_covering() -> UCRSResult<()> {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/2.1/unity-catalog/external-locations"))
+            .and(body_partial_json(json!({"name": "raw", "url": "s3://lake/raw", "credential_name": "lake"})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"name": "raw", "url": "s3://lake/raw"})))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/external-locations"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"external_locations": [
+                {"name": "lake", "url": "s3://lake/"},
+                {"name": "raw", "url": "s3://lake/raw"},
+                {"name": "other", "url": "s3://other-bucket/raw"},
+                {"name": "landing", "url": "abfss://landing@acct.dfs.core.windows.net/in"}
+            ]})))
+            .mount(&server)
+            .await;
+
+        let rc = RequestClient::new(&server.uri(), true)?;
+        let client = ExternalLocationsClient::new(&rc);
+        let create = CreateExternalLocation::builder()
+            .name("raw".to_owned())
+            .url("s3://lake/raw".to_owned())
+            .credential_name("lake".to_owned())
+            .build()
+            .unwrap();
+        client.create(create).await?;
+
+        let name_covering = |location: &'static str| {
+            let client = &client;
+            async move { client.covering(location).await.map(|l| l.and_then(|l| l.name.clone())) }
+        };
+        assert_eq!(name_covering("s3://lake/raw/events/day=1").await?.as_deref(), Some("raw"));
+        assert_eq!(name_covering("s3://lake/raw_v2").await?.as_deref(), Some("lake"));
+        assert_eq!(name_covering("s3://nobody/raw").await?, None);
+        assert_eq!(name_covering("abfss://landing@acct.dfs.core.windows.net/in/x").await?.as_deref(), Some("landing"));
+        assert_eq!(name_covering("abfss://other@acct.dfs.core.windows.net/in/x").await?, None);
+        assert!(matches!(client.covering("not a url").await, Err(UCRSError::MalformedURL(_))));
+        Ok(())
+    }
+}
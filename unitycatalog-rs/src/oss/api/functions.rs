@@ -0,0 +1,156 @@
+use crate::{errors::UCRSError, request::RequestClient};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use crate::errors::UCRSResult;
+
+pub struct FunctionsClient<'a> {
+    client: &'a RequestClient
+}
+
+impl<'a> FunctionsClient<'a> {
+    pub fn new(client: &'a RequestClient) -> Self {
+        Self { client }
+    }
+
+    pub fn full_name(catalog_name: &str, schema_name: &str, name: &str) -> String {
+        format!("{}.{}.{}", catalog_name, schema_name, name)
+    }
+
+    pub async fn get(&self, full_name: &str) -> UCRSResult<FunctionInfo> {
+        let path = self.client.base_url.join(&format!("/api/2.1/unity-catalog/functions/{}", full_name))
+            .map_err(UCRSError::MalformedURL)?;
+        let res = self.client.get_op("functions.get", path, None::<String>).await;
+        if let Err(ref e) = res {
+            match e.status() {
+                Some(StatusCode::NOT_FOUND) =>
+                    Err(UCRSError::FunctionNotFound(full_name.to_owned())),
+                _ => res
+            }
+        } else {
+            res
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+pub struct FunctionInfo {
+    name: Option<String>,
+    catalog_name: Option<String>,
+    schema_name: Option<String>,
+    full_name: Option<String>,
+    comment: Option<String>,
+    data_type: Option<String>,
+    full_data_type: Option<String>,
+    routine_body: Option<String>,
+    routine_definition: Option<String>,
+    owner: Option<String>,
+    created_at: Option<i64>,
+    updated_at: Option<i64>,
+    function_id: Option<String>,
+    #[serde(flatten)]
+    extra: HashMap<String, serde_json::Value>
+}
+
+impl FunctionInfo {
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn catalog_name(&self) -> Option<&str> {
+        self.catalog_name.as_deref()
+    }
+
+    pub fn schema_name(&self) -> Option<&str> {
+        self.schema_name.as_deref()
+    }
+
+    pub fn full_name(&self) -> Option<&str> {
+        self.full_name.as_deref()
+    }
+
+    pub fn comment(&self) -> Option<&str> {
+        self.comment.as_deref()
+    }
+
+    pub fn data_type(&self) -> Option<&str> {
+        self.data_type.as_deref()
+    }
+
+    pub fn full_data_type(&self) -> Option<&str> {
+        self.full_data_type.as_deref()
+    }
+
+    pub fn routine_body(&self) -> Option<&str> {
+        self.routine_body.as_deref()
+    }
+
+    pub fn routine_definition(&self) -> Option<&str> {
+        self.routine_definition.as_deref()
+    }
+
+    pub fn owner(&self) -> Option<&str> {
+        self.owner.as_deref()
+    }
+
+    pub fn created_at(&self) -> Option<i64> {
+        self.created_at
+    }
+
+    pub fn updated_at(&self) -> Option<i64> {
+        self.updated_at
+    }
+
+    pub fn function_id(&self) -> Option<&str> {
+        self.function_id.as_deref()
+    }
+
+    /// Fields returned by the server that this crate doesn't model yet.
+    pub fn extra(&self) -> &HashMap<String, serde_json::Value> {
+        &self.extra
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_get() -> UCRSResult<()> {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/functions/unity.default.redact"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "name": "redact",
+                "catalog_name": "unity",
+                "schema_name": "default",
+                "full_name": "unity.default.redact",
+                "data_type": "STRING",
+                "routine_body": "SQL",
+                "routine_definition": "'***'",
+                "security_type": "DEFINER"
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/functions/unity.default.missing"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+
+        let rc = RequestClient::new(&server.uri(), true)?;
+        let client = FunctionsClient::new(&rc);
+        let full_name = FunctionsClient::full_name("unity", "default", "redact");
+        let function = client.get(&full_name).await?;
+        assert_eq!(function.full_name(), Some("unity.default.redact"));
+        assert_eq!(function.data_type(), Some("STRING"));
+        assert_eq!(function.routine_definition(), Some("'***'"));
+        assert_eq!(function.extra()["security_type"], "DEFINER");
+
+        let res = client.get("unity.default.missing").await;
+        assert!(matches!(res, Err(UCRSError::FunctionNotFound(ref n)) if n == "unity.default.missing"));
+        Ok(())
+    }
+}
@@ -1,3 +1,11 @@
-mod catalogs;
-mod schemas;
-mod tables;
\ No newline at end of file
+pub mod catalogs;
+pub mod connections;
+pub mod constraints;
+pub mod credentials;
+pub mod external_locations;
+pub mod functions;
+pub mod schemas;
+pub mod tables;
+pub mod tags;
+pub mod temporary_credentials;
+pub mod volumes;
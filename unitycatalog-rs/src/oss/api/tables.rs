@@ -1,9 +1,16 @@
-use crate::{errors::UCRSError, request::RequestClient};
+use crate::{bulk::{self, BulkReport}, errors::UCRSError, request::RequestClient};
+use crate::oss::api::constraints::{check_constraint, TableConstraint};
+use crate::flavor::Flavor;
+use crate::oss::api::functions::FunctionsClient;
+use crate::oss::api::schemas::SchemasClient;
+use crate::oss::api::tags::{find_tag, Tag};
 use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Mutex;
 use crate::errors::UCRSResult;
 use derive_builder::{self, Builder};
+use regex::Regex;
 
 pub struct TablesClient<'a> {
     client: &'a RequestClient
@@ -19,27 +26,122 @@ impl <'a> TablesClient<'a> {
     }
 
     pub async fn list(&self, catalog_name: &str, schema_name: &str, page_token:Option<String>, max_results: Option<i32>) -> UCRSResult<ListTablesResponse> {
+        let options = ListTablesOptions { max_results, ..Default::default() };
+        self.list_with_options(catalog_name, schema_name, page_token, &options).await
+    }
+
+    pub async fn list_with_options(&self, catalog_name: &str, schema_name: &str, page_token: Option<String>, options: &ListTablesOptions) -> UCRSResult<ListTablesResponse> {
         let mut url = self.client.base_url.clone().join("/api/2.1/unity-catalog/tables")
             .map_err(UCRSError::MalformedURL)?;
         url.query_pairs_mut()
             .append_pair("catalog_name", catalog_name)
             .append_pair("schema_name", schema_name);
-        if let Some(token) = page_token {
-            url.query_pairs_mut().append_pair("page_token", &token);
+        options.append_flags(&mut url);
+        self.client.flavor().page(&mut url, page_token, options.max_results);
+        self.client.get_op("tables.list", url, None::<String>).await
+    }
+
+    /// The name and type of every table in `catalog_name` whose schema matches
+    /// the SQL `LIKE` pattern `schema_name_pattern` (all schemas if `None`).
+    /// Databricks serves this from `/table-summaries`; against OSS the schemas
+    /// are listed and their tables fetched without columns or properties.
+    pub async fn list_summaries(&self, catalog_name: &str, schema_name_pattern: Option<&str>, options: &ListTablesOptions) -> UCRSResult<Vec<TableSummary>> {
+        match self.client.flavor() {
+            Flavor::Databricks => self.list_summaries_route(catalog_name, schema_name_pattern, options).await,
+            Flavor::Oss => self.list_summaries_fallback(catalog_name, schema_name_pattern, options).await
         }
-        if let Some(max_results) = max_results {
-            url.query_pairs_mut().append_pair("max_results", &max_results.to_string());
+    }
+
+    async fn list_summaries_route(&self, catalog_name: &str, schema_name_pattern: Option<&str>, options: &ListTablesOptions) -> UCRSResult<Vec<TableSummary>> {
+        let mut summaries = vec![];
+        let mut page_token = None;
+        loop {
+            let mut url = self.client.base_url.join("/api/2.1/unity-catalog/table-summaries")
+                .map_err(UCRSError::MalformedURL)?;
+            url.query_pairs_mut().append_pair("catalog_name", catalog_name);
+            if let Some(pattern) = schema_name_pattern {
+                url.query_pairs_mut().append_pair("schema_name_pattern", pattern);
+            }
+            self.client.flavor().page(&mut url, page_token, options.max_results);
+            let page: ListTableSummariesResponse = self.client.get_op("tables.list_summaries", url, None::<String>).await?;
+            summaries.extend(page.tables);
+            match page.next_page_token {
+                Some(token) if !token.is_empty() => page_token = Some(token),
+                _ => return Ok(summaries)
+            }
         }
-        self.client.get(url, None::<String>).await
     }
 
+    async fn list_summaries_fallback(&self, catalog_name: &str, schema_name_pattern: Option<&str>, options: &ListTablesOptions) -> UCRSResult<Vec<TableSummary>> {
+        let pattern = schema_name_pattern.map(like_pattern).transpose()?;
+        let options = ListTablesOptions {
+            omit_columns: true,
+            omit_properties: true,
+            ..options.clone()
+        };
+        let mut summaries = vec![];
+        for schema in SchemasClient::new(self.client).list_all(catalog_name).await? {
+            let Some(schema_name) = schema.name() else { continue };
+            if pattern.as_ref().is_some_and(|p| !p.is_match(schema_name)) {
+                continue;
+            }
+            let mut page_token = None;
+            loop {
+                let page = self.list_with_options(catalog_name, schema_name, page_token, &options).await?;
+                summaries.extend(page.tables.into_iter().map(|t| TableSummary {
+                    full_name: t.full_name.clone().unwrap_or_else(||
+                        Self::full_name(catalog_name, schema_name, t.name().unwrap_or_default())),
+                    table_type: t.table_type
+                }));
+                match page.next_page_token {
+                    Some(token) if !token.is_empty() => page_token = Some(token),
+                    _ => break
+                }
+            }
+        }
+        Ok(summaries)
+    }
+
+    /// Follows `next_page_token` until every table in the schema has been listed.
+    pub async fn list_all(&self, catalog_name: &str, schema_name: &str) -> UCRSResult<Vec<TableInfo>> {
+        let mut tables = vec![];
+        let mut page_token = None;
+        loop {
+            let page = self.list(catalog_name, schema_name, page_token, None).await?;
+            tables.extend(page.tables);
+            match page.next_page_token {
+                Some(token) if !token.is_empty() => page_token = Some(token),
+                _ => return Ok(tables)
+            }
+        }
+    }
+
+    /// Fails with `InvalidConstraint` if a table constraint names a column
+    /// that doesn't exist, as `ConstraintsClient::create` does, and with
+    /// `FunctionNotFound` if a row filter or column mask names a function
+    /// that doesn't exist. These checks cost a GET per row filter and column
+    /// mask function and per foreign key parent table before the table is
+    /// created; `create_many` makes them once per batch.
     pub async fn create(&self, props: CreateTable) -> UCRSResult<TableInfo> {
+        self.create_checked(props, &CreateChecks::default()).await
+    }
+
+    /// `create`, reusing the lookups already made in `checks`.
+    pub(crate) async fn create_checked(&self, props: CreateTable, checks: &CreateChecks) -> UCRSResult<TableInfo> {
+        let full_name = props.full_name();
+        for constraint in props.table_constraints.iter().flatten() {
+            check_constraint(self, checks, &full_name, &props.columns, constraint).await?;
+        }
+        for function_name in props.policy_functions() {
+            checks.function(self.client, function_name).await?;
+        }
+
         let route = self.client.base_url.join("/api/2.1/unity-catalog/tables")
             .map_err(UCRSError::MalformedURL)?;
 
-        let res = self.client.post(route, Some(&props)).await;
-        if let Err(UCRSError::RequestError(ref res_inner)) = res {
-            match res_inner.status() {
+        let res = self.client.post_op("tables.create", route, Some(&props)).await;
+        if let Err(ref e) = res {
+            match e.status() {
                 Some(StatusCode::CONFLICT) => Err(UCRSError::DuplicateTableName(props.name)),
                 _ => res
             }
@@ -48,12 +150,38 @@ impl <'a> TablesClient<'a> {
         }
     }
 
+    pub async fn create_view(&self, props: CreateView) -> UCRSResult<TableInfo> {
+        let route = self.client.base_url.join("/api/2.1/unity-catalog/tables")
+            .map_err(UCRSError::MalformedURL)?;
+
+        let res = self.client.post_op("tables.create_view", route, Some(&props)).await;
+        if let Err(ref e) = res {
+            match e.status() {
+                Some(StatusCode::CONFLICT) => Err(UCRSError::DuplicateTableName(props.name)),
+                _ => res
+            }
+        } else {
+            res
+        }
+    }
+
+    pub async fn create_definition(&self, definition: TableDefinition) -> UCRSResult<TableInfo> {
+        self.create_definition_checked(definition, &CreateChecks::default()).await
+    }
+
+    pub(crate) async fn create_definition_checked(&self, definition: TableDefinition, checks: &CreateChecks) -> UCRSResult<TableInfo> {
+        match definition {
+            TableDefinition::Table(props) => self.create_checked(props, checks).await,
+            TableDefinition::View(props) => self.create_view(props).await
+        }
+    }
+
     pub async fn get(&self, full_name: &str) -> UCRSResult<TableInfo> {
         let path = self.client.base_url.join(&format!("/api/2.1/unity-catalog/tables/{}", full_name))
             .map_err(UCRSError::MalformedURL)?;
-        let res = self.client.get(path, None::<String>).await;
-        if let Err(UCRSError::RequestError(ref res_inner)) = res {
-            match res_inner.status() {
+        let res = self.client.get_op("tables.get", path, None::<String>).await;
+        if let Err(ref e) = res {
+            match e.status() {
                 Some(StatusCode::NOT_FOUND) => 
                     Err(UCRSError::TableNotFound(full_name.to_owned())),
                 _ => res
@@ -66,21 +194,97 @@ impl <'a> TablesClient<'a> {
     pub async fn delete(&self, full_name: &str) -> UCRSResult<()> {
         let path = self.client.base_url.join(&format!("/api/2.1/unity-catalog/tables/{}", full_name))
             .map_err(UCRSError::MalformedURL)?;
-        let res = self.client.delete(path, None::<String>).await;
-        if let Err(UCRSError::RequestError(ref res_inner)) = res {
-            match res_inner.status() {
+        let res = self.client.delete_op("tables.delete", path, None::<String>).await;
+        if let Err(ref e) = res {
+            match e.status() {
                 Some(StatusCode::NOT_FOUND) => 
                     Err(UCRSError::TableNotFound(full_name.to_owned())),
                 _ => res
             }
-        } else if let Err(UCRSError::JSONParsingError(_)) = res {
-            // This is because DELETE returns "200 OK" as a response body :/
-            Ok(())
-        } 
-        else {
+        } else {
             res
         }
     }
+
+    /// Creates each table with at most `concurrency` requests in flight,
+    /// carrying on past failures. The functions and foreign key parents the
+    /// tables reference are looked up once, before any table is created.
+    pub async fn create_many(&self, tables: Vec<CreateTable>, concurrency: usize) -> BulkReport<TableInfo> {
+        let checks = CreateChecks::default();
+        let functions = tables.iter()
+            .flat_map(|t| t.policy_functions())
+            .map(str::to_owned)
+            .collect::<BTreeSet<_>>();
+        let parents = tables.iter()
+            .flat_map(|t| t.table_constraints.iter().flatten().filter_map(move |c| match c {
+                TableConstraint::ForeignKey(fk) if fk.parent_table() != t.full_name() => Some(fk.parent_table().to_owned()),
+                _ => None
+            }))
+            .collect::<BTreeSet<_>>();
+        // Failures are left for each table's own check to report.
+        let checks = &checks;
+        bulk::run(functions, concurrency, |name| (name.clone(), async move { checks.function(self.client, &name).await })).await;
+        bulk::run(parents, concurrency, |name| (name.clone(), async move { checks.parent_columns(self, &name).await })).await;
+
+        bulk::run(tables, concurrency, |props| {
+            let key = props.full_name();
+            (key, self.create_checked(props, checks))
+        }).await
+    }
+
+    pub async fn get_many<S: AsRef<str>>(&self, full_names: &[S], concurrency: usize) -> BulkReport<TableInfo> {
+        bulk::run(full_names, concurrency, |full_name| {
+            (full_name.as_ref().to_owned(), self.get(full_name.as_ref()))
+        }).await
+    }
+
+    pub async fn delete_many<S: AsRef<str>>(&self, full_names: &[S], concurrency: usize) -> BulkReport<()> {
+        bulk::run(full_names, concurrency, |full_name| {
+            (full_name.as_ref().to_owned(), self.delete(full_name.as_ref()))
+        }).await
+    }
+}
+
+/// Functions and foreign key parents already looked up by earlier creates,
+/// so a batch fetches each once. Missing parents aren't remembered, since
+/// the batch may create them.
+#[derive(Default)]
+pub(crate) struct CreateChecks {
+    functions: Mutex<HashMap<String, bool>>,
+    parents: Mutex<HashMap<String, Vec<ColumnInfo>>>
+}
+
+impl CreateChecks {
+    pub(crate) async fn function(&self, client: &RequestClient, full_name: &str) -> UCRSResult<()> {
+        let known = self.functions.lock().unwrap().get(full_name).copied();
+        let exists = match known {
+            Some(exists) => exists,
+            None => {
+                let exists = match FunctionsClient::new(client).get(full_name).await {
+                    Ok(_) => true,
+                    Err(UCRSError::FunctionNotFound(_)) => false,
+                    Err(e) => return Err(e)
+                };
+                self.functions.lock().unwrap().insert(full_name.to_owned(), exists);
+                exists
+            }
+        };
+        if exists {
+            Ok(())
+        } else {
+            Err(UCRSError::FunctionNotFound(full_name.to_owned()))
+        }
+    }
+
+    pub(crate) async fn parent_columns(&self, tables: &TablesClient<'_>, full_name: &str) -> UCRSResult<Vec<ColumnInfo>> {
+        let known = self.parents.lock().unwrap().get(full_name).cloned();
+        if let Some(columns) = known {
+            return Ok(columns);
+        }
+        let columns = tables.get(full_name).await?.columns().to_vec();
+        self.parents.lock().unwrap().insert(full_name.to_owned(), columns.clone());
+        Ok(columns)
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Default)]
@@ -89,6 +293,86 @@ pub struct ListTablesResponse {
     next_page_token: Option<String>
 }
 
+impl ListTablesResponse {
+    pub fn tables(&self) -> &[TableInfo] {
+        &self.tables
+    }
+
+    pub fn next_page_token(&self) -> Option<&str> {
+        self.next_page_token.as_deref()
+    }
+}
+
+/// Query options for listing tables. Omitting columns and properties keeps
+/// responses small for schemas with many wide tables.
+#[derive(Debug, Default, Builder, Clone, PartialEq, Eq)]
+#[builder(default)]
+pub struct ListTablesOptions {
+    omit_columns: bool,
+    omit_properties: bool,
+    /// Also list tables the caller can only see metadata of.
+    include_browse: bool,
+    #[builder(setter(strip_option))]
+    max_results: Option<i32>
+}
+
+impl ListTablesOptions {
+    pub fn builder() -> ListTablesOptionsBuilder {
+        ListTablesOptionsBuilder::default()
+    }
+
+    fn append_flags(&self, url: &mut url::Url) {
+        for (name, set) in [
+            ("omit_columns", self.omit_columns),
+            ("omit_properties", self.omit_properties),
+            ("include_browse", self.include_browse)
+        ] {
+            if set {
+                url.query_pairs_mut().append_pair(name, "true");
+            }
+        }
+    }
+}
+
+/// Turns a SQL `LIKE` pattern, where `%` matches any run of characters, `_`
+/// any single one and `\` escapes the next, into an anchored regex.
+fn like_pattern(pattern: &str) -> UCRSResult<Regex> {
+    let mut re = String::from("^");
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '%' => re.push_str(".*"),
+            '_' => re.push('.'),
+            '\\' => re.push_str(&regex::escape(&chars.next().unwrap_or('\\').to_string())),
+            c => re.push_str(&regex::escape(&c.to_string()))
+        }
+    }
+    re.push('$');
+    Regex::new(&re).map_err(|e| UCRSError::InvalidPattern(pattern.to_owned(), e.to_string()))
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct ListTableSummariesResponse {
+    tables: Vec<TableSummary>,
+    next_page_token: Option<String>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TableSummary {
+    full_name: String,
+    table_type: Option<TableType>
+}
+
+impl TableSummary {
+    pub fn full_name(&self) -> &str {
+        &self.full_name
+    }
+
+    pub fn table_type(&self) -> Option<&TableType> {
+        self.table_type.as_ref()
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Default)]
 pub struct TableInfo {
     name: Option<String>,
@@ -102,16 +386,251 @@ pub struct TableInfo {
     properties: Option<HashMap<String, String>>,
     created_at: Option<i64>,
     updated_at: Option<i64>,
-    table_id: Option<String>
+    table_id: Option<String>,
+    owner: Option<String>,
+    created_by: Option<String>,
+    updated_by: Option<String>,
+    browse_only: Option<bool>,
+    metastore_id: Option<String>,
+    full_name: Option<String>,
+    view_definition: Option<String>,
+    view_dependencies: Option<DependencyList>,
+    table_constraints: Option<Vec<TableConstraint>>,
+    row_filter: Option<RowFilter>,
+    tags: Option<Vec<Tag>>,
+    #[serde(flatten)]
+    extra: HashMap<String, serde_json::Value>
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+impl TableInfo {
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn catalog_name(&self) -> Option<&str> {
+        self.catalog_name.as_deref()
+    }
+
+    pub fn schema_name(&self) -> Option<&str> {
+        self.schema_name.as_deref()
+    }
+
+    pub fn table_type(&self) -> Option<&TableType> {
+        self.table_type.as_ref()
+    }
+
+    pub fn data_source_format(&self) -> Option<&DataSourceFormat> {
+        self.data_source_format.as_ref()
+    }
+
+    pub fn columns(&self) -> &[ColumnInfo] {
+        self.columns.as_deref().unwrap_or_default()
+    }
+
+    pub fn storage_location(&self) -> Option<&str> {
+        self.storage_location.as_deref()
+    }
+
+    pub fn comment(&self) -> Option<&str> {
+        self.comment.as_deref()
+    }
+
+    pub fn properties(&self) -> Option<&HashMap<String, String>> {
+        self.properties.as_ref()
+    }
+
+    pub fn created_at(&self) -> Option<i64> {
+        self.created_at
+    }
+
+    pub fn updated_at(&self) -> Option<i64> {
+        self.updated_at
+    }
+
+    pub fn table_id(&self) -> Option<&str> {
+        self.table_id.as_deref()
+    }
+
+    pub fn owner(&self) -> Option<&str> {
+        self.owner.as_deref()
+    }
+
+    pub fn created_by(&self) -> Option<&str> {
+        self.created_by.as_deref()
+    }
+
+    pub fn updated_by(&self) -> Option<&str> {
+        self.updated_by.as_deref()
+    }
+
+    pub fn browse_only(&self) -> Option<bool> {
+        self.browse_only
+    }
+
+    pub fn metastore_id(&self) -> Option<&str> {
+        self.metastore_id.as_deref()
+    }
+
+    pub fn full_name(&self) -> Option<&str> {
+        self.full_name.as_deref()
+    }
+
+    pub fn view_definition(&self) -> Option<&str> {
+        self.view_definition.as_deref()
+    }
+
+    pub fn view_dependencies(&self) -> Option<&DependencyList> {
+        self.view_dependencies.as_ref()
+    }
+
+    pub fn table_constraints(&self) -> &[TableConstraint] {
+        self.table_constraints.as_deref().unwrap_or_default()
+    }
+
+    pub fn row_filter(&self) -> Option<&RowFilter> {
+        self.row_filter.as_ref()
+    }
+
+    pub fn is_view(&self) -> bool {
+        matches!(self.table_type, Some(TableType::VIEW) | Some(TableType::MATERIALIZED_VIEW))
+    }
+
+    pub fn tags(&self) -> &[Tag] {
+        self.tags.as_deref().unwrap_or_default()
+    }
+
+    /// `None` if `key` isn't set, `Some(None)` if it is set without a value.
+    pub fn tag(&self, key: &str) -> Option<Option<&str>> {
+        find_tag(self.tags(), key)
+    }
+
+    /// Attaches tags fetched separately, e.g. with `TagsClient::list_all`.
+    pub fn with_tags(mut self, tags: Vec<Tag>) -> Self {
+        self.tags = Some(tags);
+        self
+    }
+
+    /// Fields returned by the server that this crate doesn't model yet.
+    pub fn extra(&self) -> &HashMap<String, serde_json::Value> {
+        &self.extra
+    }
+}
+
+/// The tables and functions a view reads from.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct DependencyList {
+    dependencies: Vec<Dependency>
+}
+
+impl DependencyList {
+    pub fn new(dependencies: Vec<Dependency>) -> Self {
+        Self { dependencies }
+    }
+
+    pub fn dependencies(&self) -> &[Dependency] {
+        &self.dependencies
+    }
+}
+
+/// Exactly one of `table` or `function` is set.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Dependency {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    table: Option<TableDependency>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    function: Option<FunctionDependency>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+struct TableDependency {
+    table_full_name: String
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+struct FunctionDependency {
+    function_full_name: String
+}
+
+impl Dependency {
+    pub fn on_table(full_name: &str) -> Self {
+        Self {
+            table: Some(TableDependency { table_full_name: full_name.to_owned() }),
+            function: None
+        }
+    }
+
+    pub fn on_function(full_name: &str) -> Self {
+        Self {
+            table: None,
+            function: Some(FunctionDependency { function_full_name: full_name.to_owned() })
+        }
+    }
+
+    pub fn table_full_name(&self) -> Option<&str> {
+        self.table.as_ref().map(|t| t.table_full_name.as_str())
+    }
+
+    pub fn function_full_name(&self) -> Option<&str> {
+        self.function.as_ref().map(|f| f.function_full_name.as_str())
+    }
+}
+
+/// Rows are only returned where `function_name`, called with the input
+/// columns, returns true.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RowFilter {
+    function_name: String,
+    input_column_names: Vec<String>
+}
+
+impl RowFilter {
+    pub fn function_name(&self) -> &str {
+        &self.function_name
+    }
+
+    pub fn input_column_names(&self) -> &[String] {
+        &self.input_column_names
+    }
+}
+
+/// The column's value is replaced by `function_name` applied to it and the
+/// `using` columns.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ColumnMask {
+    function_name: String,
+    using_column_names: Vec<String>
+}
+
+impl ColumnMask {
+    pub fn function_name(&self) -> &str {
+        &self.function_name
+    }
+
+    pub fn using_column_names(&self) -> &[String] {
+        &self.using_column_names
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub enum TableType {
     MANAGED,
-    EXTERNAL
+    EXTERNAL,
+    VIEW,
+    #[allow(non_camel_case_types)]
+    MATERIALIZED_VIEW,
+    #[allow(non_camel_case_types)]
+    STREAMING_TABLE,
+    #[allow(non_camel_case_types)]
+    MANAGED_SHALLOW_CLONE,
+    #[allow(non_camel_case_types)]
+    EXTERNAL_SHALLOW_CLONE,
+    FOREIGN,
+    /// A value this crate doesn't know yet, kept as sent by the server.
+    #[serde(untagged)]
+    Unknown(String)
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub enum DataSourceFormat {
     DELTA,
     CSV,
@@ -119,10 +638,37 @@ pub enum DataSourceFormat {
     AVRO,
     PARQUET,
     ORC,
-    TEXT
+    TEXT,
+    #[allow(non_camel_case_types)]
+    UNITY_CATALOG,
+    DELTASHARING,
+    ICEBERG,
+    #[allow(non_camel_case_types)]
+    HIVE_SERDE,
+    #[allow(non_camel_case_types)]
+    HIVE_CUSTOM,
+    #[allow(non_camel_case_types)]
+    DATABRICKS_FORMAT,
+    #[allow(non_camel_case_types)]
+    MYSQL_FORMAT,
+    #[allow(non_camel_case_types)]
+    POSTGRESQL_FORMAT,
+    #[allow(non_camel_case_types)]
+    REDSHIFT_FORMAT,
+    #[allow(non_camel_case_types)]
+    SNOWFLAKE_FORMAT,
+    #[allow(non_camel_case_types)]
+    SQLDW_FORMAT,
+    #[allow(non_camel_case_types)]
+    SQLSERVER_FORMAT,
+    #[allow(non_camel_case_types)]
+    BIGQUERY_FORMAT,
+    /// A value this crate doesn't know yet, kept as sent by the server.
+    #[serde(untagged)]
+    Unknown(String)
 }
 
-#[derive(Serialize, Deserialize, Debug, Default, Builder, Clone)]
+#[derive(Serialize, Deserialize, Debug, Default, Builder, Clone, PartialEq, Eq)]
 #[builder(setter(strip_option), default)]
 pub struct ColumnInfo {
     name: Option<String>,
@@ -135,26 +681,104 @@ pub struct ColumnInfo {
     position: Option<u32>,
     comment: Option<String>,
     nullable: Option<bool>,
-    partition_index: Option<i32>
+    partition_index: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mask: Option<ColumnMask>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tags: Option<Vec<Tag>>
 }
 
 impl ColumnInfo {
     pub fn builder() -> ColumnInfoBuilder {
         ColumnInfoBuilder::create_empty()
     }
+
+    /// The column as a client declares it, without the fields the server
+    /// fills in.
+    fn declared(&self) -> Self {
+        Self {
+            type_json: None,
+            position: None,
+            nullable: Some(self.nullable.unwrap_or(true)),
+            ..self.clone()
+        }
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn type_text(&self) -> Option<&str> {
+        self.type_text.as_deref()
+    }
+
+    pub fn type_json(&self) -> Option<&str> {
+        self.type_json.as_deref()
+    }
+
+    pub fn type_name(&self) -> Option<&ColumnTypeName> {
+        self.type_name.as_ref()
+    }
+
+    pub fn type_precision(&self) -> Option<i32> {
+        self.type_precision
+    }
+
+    pub fn type_scale(&self) -> Option<i32> {
+        self.type_scale
+    }
+
+    pub fn type_interval_type(&self) -> Option<&str> {
+        self.type_interval_type.as_deref()
+    }
+
+    pub fn position(&self) -> Option<u32> {
+        self.position
+    }
+
+    pub fn comment(&self) -> Option<&str> {
+        self.comment.as_deref()
+    }
+
+    pub fn nullable(&self) -> Option<bool> {
+        self.nullable
+    }
+
+    pub fn partition_index(&self) -> Option<i32> {
+        self.partition_index
+    }
+
+    pub fn mask(&self) -> Option<&ColumnMask> {
+        self.mask.as_ref()
+    }
+
+    pub fn tags(&self) -> &[Tag] {
+        self.tags.as_deref().unwrap_or_default()
+    }
+
+    /// `None` if `key` isn't set, `Some(None)` if it is set without a value.
+    pub fn tag(&self, key: &str) -> Option<Option<&str>> {
+        find_tag(self.tags(), key)
+    }
+
+    /// Attaches tags fetched separately, e.g. with `TagsClient::list_all`.
+    pub fn with_tags(mut self, tags: Vec<Tag>) -> Self {
+        self.tags = Some(tags);
+        self
+    }
 }
 
 impl ColumnInfoBuilder {
     pub fn generate_type_json(&mut self) -> &mut Self {
         //TODO: Better failure handling here, but the builder pattern makes it awkward
         let name = self.name.as_ref().unwrap().as_ref().unwrap();
-        let r#type:&'static str = self.type_name.as_ref().unwrap().as_ref().unwrap().into();
+        let r#type = self.type_name.as_ref().unwrap().as_ref().unwrap().as_str();
         let nullable = self.nullable.unwrap().unwrap();
         let md = HashMap::new();
         let tj = TypeJSON {
             name: name.to_string(),
-            r#type: r#type.to_owned(),
-            nullable: nullable,
+            r#type: r#type.to_ascii_lowercase(),
+            nullable,
             metadata: md
         };
         self.type_json(serde_json::to_string(&tj).unwrap())
@@ -169,7 +793,7 @@ pub struct TypeJSON {
     metadata: HashMap<String, String>
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, strum::IntoStaticStr)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, strum::IntoStaticStr)]
 pub enum ColumnTypeName {
     #[strum(serialize = "boolean")]
     BOOLEAN,
@@ -215,10 +839,27 @@ pub enum ColumnTypeName {
     USER_DEFINED_TYPE,
     #[allow(non_camel_case_types)]
     #[strum(serialize = "table_type")]
-    TABLE_TYPE
+    TABLE_TYPE,
+    #[strum(serialize = "variant")]
+    VARIANT,
+    /// A value this crate doesn't know yet, kept as sent by the server.
+    #[serde(untagged)]
+    #[strum(serialize = "unknown")]
+    Unknown(String)
 }
 
-#[derive(Serialize, Deserialize, Debug, Builder)]
+impl ColumnTypeName {
+    /// The type's name. Unlike the `&'static str` conversion, which gives
+    /// "unknown", `Unknown` gives the name the server sent.
+    pub fn as_str(&self) -> &str {
+        match self {
+            ColumnTypeName::Unknown(name) => name,
+            known => known.into()
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Builder, Clone, PartialEq, Eq)]
 //#[builder(setter(strip_option), private)]
 
 pub struct CreateTable {
@@ -230,7 +871,11 @@ pub struct CreateTable {
     columns: Vec<ColumnInfo>,
     storage_location: Option<String>,
     comment: Option<String>,
-    properties: Option<HashMap<String, String>>
+    properties: Option<HashMap<String, String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    table_constraints: Option<Vec<TableConstraint>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    row_filter: Option<RowFilter>
 }
 
 impl CreateTable {
@@ -239,22 +884,319 @@ impl CreateTable {
             .storage_location(None)
             .comment(None)
             .properties(None)
+            .table_constraints(None)
+            .row_filter(None)
             .to_owned()
     }
+
+    pub fn full_name(&self) -> String {
+        TablesClient::full_name(&self.catalog_name, &self.schema_name, &self.name)
+    }
+
+    pub fn in_catalog(mut self, catalog_name: &str) -> Self {
+        self.catalog_name = catalog_name.to_owned();
+        self
+    }
+
+    pub fn table_type(&self) -> &TableType {
+        &self.table_type
+    }
+
+    pub fn columns(&self) -> &[ColumnInfo] {
+        &self.columns
+    }
+
+    /// Whether `other` declares the same table. Column fields the server
+    /// fills in, such as `type_json` and `position`, are ignored, and no
+    /// properties counts the same as empty ones.
+    pub fn same_definition(&self, other: &CreateTable) -> bool {
+        let declared = |t: &CreateTable| CreateTable {
+            columns: t.columns.iter().map(ColumnInfo::declared).collect(),
+            properties: t.properties.clone().filter(|p| !p.is_empty()),
+            ..t.clone()
+        };
+        declared(self) == declared(other)
+    }
+
+    pub fn storage_location(&self) -> Option<&str> {
+        self.storage_location.as_deref()
+    }
+
+    pub fn with_storage_location(mut self, storage_location: Option<String>) -> Self {
+        self.storage_location = storage_location;
+        self
+    }
+
+    pub fn row_filter(&self) -> Option<&RowFilter> {
+        self.row_filter.as_ref()
+    }
+
+    /// Filters rows with `function_name` (a full three-part name), called
+    /// with `input_columns`.
+    pub fn with_row_filter(mut self, function_name: &str, input_columns: &[&str]) -> UCRSResult<Self> {
+        self.check_columns(input_columns)?;
+        self.row_filter = Some(RowFilter {
+            function_name: function_name.to_owned(),
+            input_column_names: input_columns.iter().map(|c| c.to_string()).collect()
+        });
+        Ok(self)
+    }
+
+    pub fn without_row_filter(mut self) -> Self {
+        self.row_filter = None;
+        self
+    }
+
+    /// Masks `column` with `function_name`, called with the column's value
+    /// followed by `using_columns`.
+    pub fn with_column_mask(mut self, column: &str, function_name: &str, using_columns: &[&str]) -> UCRSResult<Self> {
+        self.check_columns(using_columns)?;
+        let mask = ColumnMask {
+            function_name: function_name.to_owned(),
+            using_column_names: using_columns.iter().map(|c| c.to_string()).collect()
+        };
+        self.column_mut(column)?.mask = Some(mask);
+        Ok(self)
+    }
+
+    pub fn without_column_mask(mut self, column: &str) -> UCRSResult<Self> {
+        self.column_mut(column)?.mask = None;
+        Ok(self)
+    }
+
+    /// Functions referenced by the row filter and column masks.
+    fn policy_functions(&self) -> BTreeSet<&str> {
+        self.row_filter.iter()
+            .map(|f| f.function_name.as_str())
+            .chain(self.columns.iter().filter_map(|c| c.mask.as_ref()).map(|m| m.function_name.as_str()))
+            .collect()
+    }
+
+    fn missing_column(&self, column: &str) -> UCRSError {
+        UCRSError::InvalidTableDefinition(self.full_name(), format!("column {} does not exist", column))
+    }
+
+    fn check_columns(&self, columns: &[&str]) -> UCRSResult<()> {
+        match columns.iter().find(|name| !self.columns.iter().any(|c| c.name() == Some(**name))) {
+            Some(missing) => Err(self.missing_column(missing)),
+            None => Ok(())
+        }
+    }
+
+    fn column_mut(&mut self, column: &str) -> UCRSResult<&mut ColumnInfo> {
+        match self.columns.iter().position(|c| c.name() == Some(column)) {
+            Some(i) => Ok(&mut self.columns[i]),
+            None => Err(self.missing_column(column))
+        }
+    }
+}
+
+/// A view or materialized view. Views are registered through the tables
+/// endpoint but never own storage, so `build` rejects a storage location.
+#[derive(Serialize, Deserialize, Debug, Builder, Clone, PartialEq, Eq)]
+#[builder(build_fn(validate = "Self::validate"))]
+pub struct CreateView {
+    name: String,
+    catalog_name: String,
+    schema_name: String,
+    #[builder(default = "TableType::VIEW")]
+    table_type: TableType,
+    view_definition: String,
+    #[builder(default)]
+    view_dependencies: Option<DependencyList>,
+    #[builder(default)]
+    columns: Vec<ColumnInfo>,
+    #[builder(default)]
+    storage_location: Option<String>,
+    #[builder(default)]
+    comment: Option<String>,
+    #[builder(default)]
+    properties: Option<HashMap<String, String>>
+}
+
+impl CreateView {
+    pub fn builder() -> CreateViewBuilder {
+        CreateViewBuilder::create_empty()
+    }
+
+    pub fn full_name(&self) -> String {
+        TablesClient::full_name(&self.catalog_name, &self.schema_name, &self.name)
+    }
+
+    pub fn in_catalog(mut self, catalog_name: &str) -> Self {
+        self.catalog_name = catalog_name.to_owned();
+        self
+    }
+
+    /// Whether `other` declares the same view, ignoring the same server-filled
+    /// fields as [`CreateTable::same_definition`].
+    pub fn same_definition(&self, other: &CreateView) -> bool {
+        let declared = |v: &CreateView| CreateView {
+            columns: v.columns.iter().map(ColumnInfo::declared).collect(),
+            properties: v.properties.clone().filter(|p| !p.is_empty()),
+            ..v.clone()
+        };
+        declared(self) == declared(other)
+    }
+}
+
+impl CreateViewBuilder {
+    fn validate(&self) -> Result<(), String> {
+        if let Some(Some(location)) = &self.storage_location {
+            return Err(format!("views cannot have a storage location, got {}", location));
+        }
+        match &self.table_type {
+            None | Some(TableType::VIEW) | Some(TableType::MATERIALIZED_VIEW) => {},
+            Some(other) => return Err(format!("{:?} is not a view table type", other))
+        }
+        if self.view_definition.as_ref().is_some_and(|d| d.trim().is_empty()) {
+            return Err("view_definition is empty".to_owned());
+        }
+        Ok(())
+    }
+}
+
+/// What it takes to recreate a table read from the server: views are
+/// created from their SQL, everything else from its format and location.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TableDefinition {
+    Table(CreateTable),
+    View(CreateView)
+}
+
+impl TableDefinition {
+    pub fn full_name(&self) -> String {
+        match self {
+            TableDefinition::Table(t) => t.full_name(),
+            TableDefinition::View(v) => v.full_name()
+        }
+    }
+
+    pub fn in_catalog(self, catalog_name: &str) -> Self {
+        match self {
+            TableDefinition::Table(t) => TableDefinition::Table(t.in_catalog(catalog_name)),
+            TableDefinition::View(v) => TableDefinition::View(v.in_catalog(catalog_name))
+        }
+    }
+
+    /// Always `None` for views.
+    pub fn storage_location(&self) -> Option<&str> {
+        match self {
+            TableDefinition::Table(t) => t.storage_location(),
+            TableDefinition::View(_) => None
+        }
+    }
+
+    /// Views are returned unchanged.
+    pub fn with_storage_location(self, storage_location: Option<String>) -> Self {
+        match self {
+            TableDefinition::Table(t) => TableDefinition::Table(t.with_storage_location(storage_location)),
+            view => view
+        }
+    }
+
+    /// Whether dropping and recreating keeps the data, which holds for views
+    /// and external tables but not managed ones.
+    pub fn recreatable(&self) -> bool {
+        match self {
+            TableDefinition::Table(t) => t.table_type() == &TableType::EXTERNAL,
+            TableDefinition::View(_) => true
+        }
+    }
+
+    pub fn same_definition(&self, other: &TableDefinition) -> bool {
+        match (self, other) {
+            (TableDefinition::Table(a), TableDefinition::Table(b)) => a.same_definition(b),
+            (TableDefinition::View(a), TableDefinition::View(b)) => a.same_definition(b),
+            _ => false
+        }
+    }
+}
+
+impl TryFrom<&TableInfo> for TableDefinition {
+    type Error = UCRSError;
+
+    fn try_from(info: &TableInfo) -> UCRSResult<Self> {
+        if info.is_view() {
+            CreateView::try_from(info).map(TableDefinition::View)
+        } else {
+            CreateTable::try_from(info).map(TableDefinition::Table)
+        }
+    }
+}
+
+fn invalid_definition(info: &TableInfo, reason: String) -> UCRSError {
+    let full_name = TablesClient::full_name(
+        info.catalog_name.as_deref().unwrap_or_default(),
+        info.schema_name.as_deref().unwrap_or_default(),
+        info.name.as_deref().unwrap_or_default()
+    );
+    UCRSError::InvalidTableDefinition(full_name, reason)
+}
+
+impl TryFrom<&TableInfo> for CreateView {
+    type Error = UCRSError;
+
+    fn try_from(info: &TableInfo) -> UCRSResult<Self> {
+        let missing = |field: &str| invalid_definition(info, format!("missing {}", field));
+        CreateView::builder()
+            .name(info.name.clone().ok_or_else(|| missing("name"))?)
+            .catalog_name(info.catalog_name.clone().ok_or_else(|| missing("catalog_name"))?)
+            .schema_name(info.schema_name.clone().ok_or_else(|| missing("schema_name"))?)
+            .table_type(info.table_type.clone().ok_or_else(|| missing("table_type"))?)
+            .view_definition(info.view_definition.clone().ok_or_else(|| missing("view_definition"))?)
+            .view_dependencies(info.view_dependencies.clone())
+            .columns(info.columns.clone().unwrap_or_default())
+            .comment(info.comment.clone())
+            .properties(info.properties.clone())
+            .build()
+            .map_err(|e| invalid_definition(info, e.to_string()))
+    }
+}
+
+/// Fails for views, which need a [`CreateView`]; see [`TableDefinition`].
+impl TryFrom<&TableInfo> for CreateTable {
+    type Error = UCRSError;
+
+    fn try_from(info: &TableInfo) -> UCRSResult<Self> {
+        if info.is_view() {
+            return Err(invalid_definition(info, "views are created with CreateView".to_owned()));
+        }
+        let missing = |field: &str| invalid_definition(info, format!("missing {}", field));
+        let table_type = info.table_type.clone().ok_or_else(|| missing("table_type"))?;
+        // A managed table's location belongs to the metastore it was read
+        // from; the server picks a fresh one when the table is created.
+        let storage_location = match table_type {
+            TableType::MANAGED => None,
+            _ => info.storage_location.clone()
+        };
+        Ok(Self {
+            name: info.name.clone().ok_or_else(|| missing("name"))?,
+            catalog_name: info.catalog_name.clone().ok_or_else(|| missing("catalog_name"))?,
+            schema_name: info.schema_name.clone().ok_or_else(|| missing("schema_name"))?,
+            table_type,
+            data_source_format: info.data_source_format.clone().ok_or_else(|| missing("data_source_format"))?,
+            columns: info.columns.clone().unwrap_or_default(),
+            storage_location,
+            comment: info.comment.clone(),
+            properties: info.properties.clone(),
+            table_constraints: info.table_constraints.clone(),
+            row_filter: info.row_filter.clone()
+        })
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use insta::with_settings;
-    use crate::testing::test_utils::{cleanup_user_model, test_with_uc};
+    use crate::testing::test_utils::{cleanup_user_model, test_with_cassette};
 
 
     #[tokio::test]
     async fn test_round_trip() -> UCRSResult<()> {
 
-        test_with_uc(|port| async move {
-            let rc = RequestClient::new(&format!("http://localhost:{}", port), true)?;
+        test_with_cassette("tables_round_trip", |rc| async move {
             let client = TablesClient::new(&rc);
 
             let catalog_name = "unity";
@@ -262,11 +1204,11 @@ mod tests {
             let table_name = "mytable";
             let full_name = TablesClient::full_name(catalog_name, schema_name, table_name);
 
-            let initial_list = client.list(&catalog_name, &schema_name, None, None).await?;
+            let initial_list = client.list(catalog_name, schema_name, None, None).await?;
             let create_columns = vec![
                 ColumnInfoBuilder::default()
                     .name("my_column".to_owned())
-                    .type_text(Into::<&'static str>::into(ColumnTypeName::INT).to_owned())
+                    .type_text(ColumnTypeName::INT.as_str().to_owned())
                     .type_name(ColumnTypeName::INT)
                     .position(0)
                     .type_precision(0)
@@ -289,11 +1231,11 @@ mod tests {
                 .unwrap();
 
             let table_info = client.create(table_create_props).await?;
-            let updated_list = client.list(&catalog_name, &schema_name, None, None).await?;
+            let updated_list = client.list(catalog_name, schema_name, None, None).await?;
 
             client.delete(&full_name).await?;
 
-            let final_list = client.list(&catalog_name, &schema_name, None, None).await?;
+            let final_list = client.list(catalog_name, schema_name, None, None).await?;
 
             with_settings!({
                 filters => cleanup_user_model()
@@ -310,5 +1252,391 @@ mod tests {
         })
         .await
     }
-}
 
+    #[tokio::test]
+    async fn test_bulk() -> UCRSResult<()> {
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        for name in ["a", "c"] {
+            Mock::given(method("POST"))
+                .and(path("/api/2.1/unity-catalog/tables"))
+                .and(body_partial_json(serde_json::json!({"name": name})))
+                .respond_with(ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({"name": name, "catalog_name": "unity", "schema_name": "default"})))
+                .mount(&server)
+                .await;
+        }
+        Mock::given(method("POST"))
+            .and(path("/api/2.1/unity-catalog/tables"))
+            .and(body_partial_json(serde_json::json!({"name": "b"})))
+            .respond_with(ResponseTemplate::new(409))
+            .mount(&server)
+            .await;
+        Mock::given(method("DELETE"))
+            .and(path("/api/2.1/unity-catalog/tables/unity.default.a"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("200 OK"))
+            .mount(&server)
+            .await;
+
+        let rc = RequestClient::new(&server.uri(), true)?;
+        let client = TablesClient::new(&rc);
+        let tables = ["a", "b", "c"].iter().map(|name| CreateTable::builder()
+            .catalog_name("unity".to_owned())
+            .schema_name("default".to_owned())
+            .name(name.to_string())
+            .table_type(TableType::EXTERNAL)
+            .data_source_format(DataSourceFormat::PARQUET)
+            .columns(vec![])
+            .storage_location(Some(format!("s3://bucket/{}", name)))
+            .build()
+            .unwrap()
+        ).collect();
+
+        let created = client.create_many(tables, 2).await;
+        assert_eq!(created.successes().map(|(k, _)| k).collect::<Vec<_>>(), vec!["unity.default.a", "unity.default.c"]);
+        assert_eq!(created.failures().map(|(k, _)| k).collect::<Vec<_>>(), vec!["unity.default.b"]);
+        assert!(matches!(created.failures().next(), Some((_, UCRSError::DuplicateTableName(_)))));
+
+        let deleted = client.delete_many(&["unity.default.a", "unity.default.missing"], 2).await;
+        assert_eq!(deleted.successes().map(|(k, _)| k).collect::<Vec<_>>(), vec!["unity.default.a"]);
+        assert_eq!(deleted.failures().map(|(k, _)| k).collect::<Vec<_>>(), vec!["unity.default.missing"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_list_summaries() -> UCRSResult<()> {
+        use wiremock::matchers::{method, path, query_param, query_param_is_missing};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+        use serde_json::json;
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/schemas"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"schemas": [
+                {"name": "sales_eu"}, {"name": "sales_us"}, {"name": "salesforce"}
+            ]})))
+            .mount(&server)
+            .await;
+        for schema in ["sales_eu", "sales_us"] {
+            Mock::given(method("GET"))
+                .and(path("/api/2.1/unity-catalog/tables"))
+                .and(query_param("schema_name", schema))
+                .and(query_param("omit_columns", "true"))
+                .and(query_param("omit_properties", "true"))
+                .and(query_param("max_results", "100"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({"tables": [
+                    {"name": "orders", "table_type": "MANAGED"}
+                ]})))
+                .expect(1)
+                .mount(&server)
+                .await;
+        }
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/table-summaries"))
+            .and(query_param("schema_name_pattern", "sales\\_%"))
+            .and(query_param_is_missing("page_token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "tables": [{"full_name": "unity.sales_eu.orders", "table_type": "MANAGED"}],
+                "next_page_token": "2"
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/table-summaries"))
+            .and(query_param("page_token", "2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "tables": [{"full_name": "unity.sales_us.orders_v", "table_type": "VIEW"}]
+            })))
+            .mount(&server)
+            .await;
+
+        let options = ListTablesOptions::builder().max_results(100).build().unwrap();
+        let rc = RequestClient::new(&server.uri(), true)?;
+        let summaries = TablesClient::new(&rc).list_summaries("unity", Some("sales\\_%"), &options).await?;
+        assert_eq!(summaries.iter().map(TableSummary::full_name).collect::<Vec<_>>(),
+            vec!["unity.sales_eu.orders", "unity.sales_us.orders"]);
+        assert_eq!(summaries[0].table_type(), Some(&TableType::MANAGED));
+
+        let rc = RequestClient::new(&server.uri(), true)?.with_flavor(Flavor::Databricks);
+        let summaries = TablesClient::new(&rc).list_summaries("unity", Some("sales\\_%"), &options).await?;
+        assert_eq!(summaries.iter().map(TableSummary::full_name).collect::<Vec<_>>(),
+            vec!["unity.sales_eu.orders", "unity.sales_us.orders_v"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_views() -> UCRSResult<()> {
+        use wiremock::matchers::{body_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let definition = "SELECT id, name FROM unity.default.marksheet WHERE unity.default.passed(marks)";
+        let dependencies = DependencyList::new(vec![
+            Dependency::on_table("unity.default.marksheet"),
+            Dependency::on_function("unity.default.passed")
+        ]);
+        let view_info = serde_json::json!({
+            "name": "passed",
+            "catalog_name": "unity",
+            "schema_name": "default",
+            "table_type": "VIEW",
+            "view_definition": definition,
+            "view_dependencies": {"dependencies": [
+                {"table": {"table_full_name": "unity.default.marksheet"}},
+                {"function": {"function_full_name": "unity.default.passed"}}
+            ]}
+        });
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/2.1/unity-catalog/tables"))
+            .and(body_json(serde_json::json!({
+                "name": "passed",
+                "catalog_name": "unity",
+                "schema_name": "default",
+                "table_type": "VIEW",
+                "view_definition": definition,
+                "view_dependencies": view_info["view_dependencies"],
+                "columns": [],
+                "storage_location": null,
+                "comment": null,
+                "properties": null
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(view_info))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let rc = RequestClient::new(&server.uri(), true)?;
+        let view = CreateView::builder()
+            .name("passed".to_owned())
+            .catalog_name("unity".to_owned())
+            .schema_name("default".to_owned())
+            .view_definition(definition.to_owned())
+            .view_dependencies(Some(dependencies.clone()))
+            .build()
+            .unwrap();
+        let info = TablesClient::new(&rc).create_view(view).await?;
+
+        assert!(info.is_view());
+        assert_eq!(info.view_definition(), Some(definition));
+        assert_eq!(info.view_dependencies(), Some(&dependencies));
+        let deps = info.view_dependencies().unwrap().dependencies();
+        assert_eq!(deps[0].table_full_name(), Some("unity.default.marksheet"));
+        assert_eq!(deps[1].function_full_name(), Some("unity.default.passed"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_view_validation() {
+        let builder = || {
+            let mut builder = CreateView::builder();
+            builder
+                .name("v".to_owned())
+                .catalog_name("unity".to_owned())
+                .schema_name("default".to_owned())
+                .view_definition("SELECT 1".to_owned());
+            builder
+        };
+
+        assert!(builder().build().is_ok());
+        assert!(builder().table_type(TableType::MATERIALIZED_VIEW).build().is_ok());
+        let err = builder().storage_location(Some("s3://bucket/v".to_owned())).build().unwrap_err();
+        assert!(err.to_string().contains("storage location"));
+        assert!(builder().table_type(TableType::EXTERNAL).build().is_err());
+        assert!(builder().view_definition(" ".to_owned()).build().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_row_filters_and_masks() -> UCRSResult<()> {
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        for name in ["unity.default.same_region", "unity.default.redact"] {
+            Mock::given(method("GET"))
+                .and(path(format!("/api/2.1/unity-catalog/functions/{}", name)))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"full_name": name})))
+                .mount(&server)
+                .await;
+        }
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/functions/unity.default.missing"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/2.1/unity-catalog/tables"))
+            .and(body_partial_json(serde_json::json!({
+                "row_filter": {"function_name": "unity.default.same_region", "input_column_names": ["region"]},
+                "columns": [
+                    {"name": "email", "mask": {"function_name": "unity.default.redact", "using_column_names": ["region"]}},
+                    {"name": "region"}
+                ]
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"name": "customers"})))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let column = |name: &str| ColumnInfo::builder().name(name.to_owned()).build().unwrap();
+        let table = CreateTable::builder()
+            .name("customers".to_owned())
+            .catalog_name("unity".to_owned())
+            .schema_name("default".to_owned())
+            .table_type(TableType::EXTERNAL)
+            .data_source_format(DataSourceFormat::DELTA)
+            .columns(vec![column("email"), column("region")])
+            .storage_location(Some("s3://bucket/customers".to_owned()))
+            .build()
+            .unwrap()
+            .with_row_filter("unity.default.same_region", &["region"])?
+            .with_column_mask("email", "unity.default.redact", &["region"])?;
+        assert_eq!(table.row_filter().map(|f| f.function_name()), Some("unity.default.same_region"));
+
+        let rc = RequestClient::new(&server.uri(), true)?;
+        let client = TablesClient::new(&rc);
+        client.create(table.clone()).await?;
+
+        let res = client.create(table.clone().with_row_filter("unity.default.missing", &[])?).await;
+        assert!(matches!(res, Err(UCRSError::FunctionNotFound(ref n)) if n == "unity.default.missing"));
+
+        let unmasked = table.clone().without_row_filter().without_column_mask("email")?;
+        assert!(unmasked.row_filter().is_none());
+        assert!(unmasked.policy_functions().is_empty());
+        assert!(matches!(table.clone().with_row_filter("f", &["nope"]), Err(UCRSError::InvalidTableDefinition(_, _))));
+        assert!(matches!(table.with_column_mask("nope", "f", &[]), Err(UCRSError::InvalidTableDefinition(_, _))));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_many_checks_once() -> UCRSResult<()> {
+        use crate::oss::api::constraints::TableConstraint;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/functions/unity.default.redact"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"full_name": "unity.default.redact"})))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/functions/unity.default.missing"))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/tables/unity.default.customers"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "name": "customers", "catalog_name": "unity", "schema_name": "default", "columns": [{"name": "id"}]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/2.1/unity-catalog/tables"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"name": "orders"})))
+            .expect(3)
+            .mount(&server)
+            .await;
+
+        let column = |name: &str| ColumnInfo::builder().name(name.to_owned()).build().unwrap();
+        let table = |name: &str, mask: &str| CreateTable::builder()
+            .name(name.to_owned())
+            .catalog_name("unity".to_owned())
+            .schema_name("default".to_owned())
+            .table_type(TableType::EXTERNAL)
+            .data_source_format(DataSourceFormat::DELTA)
+            .columns(vec![column("customer_id"), column("email")])
+            .storage_location(Some(format!("s3://bucket/{}", name)))
+            .table_constraints(Some(vec![TableConstraint::foreign_key(
+                &format!("{}_customer", name), &["customer_id"], "unity.default.customers", &["id"])]))
+            .build()
+            .unwrap()
+            .with_column_mask("email", mask, &[]);
+        let tables = vec![
+            table("orders_1", "unity.default.redact")?,
+            table("orders_2", "unity.default.redact")?,
+            table("orders_3", "unity.default.redact")?,
+            table("orders_4", "unity.default.missing")?,
+            table("orders_5", "unity.default.missing")?
+        ];
+
+        let rc = RequestClient::new(&server.uri(), true)?;
+        let created = TablesClient::new(&rc).create_many(tables, 3).await;
+        assert_eq!(created.successes().count(), 3);
+        assert_eq!(created.failures().map(|(k, _)| k).collect::<Vec<_>>(), vec!["unity.default.orders_4", "unity.default.orders_5"]);
+        assert!(created.failures().all(|(_, e)| matches!(e, UCRSError::FunctionNotFound(_))));
+        Ok(())
+    }
+
+    #[test]
+    fn test_managed_definition_has_no_location() -> UCRSResult<()> {
+        let info = |table_type: &str| serde_json::from_value::<TableInfo>(serde_json::json!({
+            "name": "scores",
+            "catalog_name": "unity",
+            "schema_name": "default",
+            "table_type": table_type,
+            "data_source_format": "DELTA",
+            "storage_location": "s3://source-metastore/tables/1234"
+        })).unwrap();
+
+        let managed = CreateTable::try_from(&info("MANAGED"))?;
+        assert_eq!(managed.storage_location(), None);
+        assert_eq!(serde_json::to_value(&managed).unwrap()["storage_location"], serde_json::Value::Null);
+        let external = CreateTable::try_from(&info("EXTERNAL"))?;
+        assert_eq!(external.storage_location(), Some("s3://source-metastore/tables/1234"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_unknown_enum_values() {
+        let list: ListTablesResponse = serde_json::from_value(serde_json::json!({
+            "tables": [
+                {"name": "v", "table_type": "VIEW", "data_source_format": "DELTASHARING"},
+                {"name": "s", "table_type": "STREAMING_TABLE", "data_source_format": "ICEBERG"},
+                {
+                    "name": "future",
+                    "table_type": "HOLOGRAPHIC_TABLE",
+                    "data_source_format": "CRYSTAL",
+                    "columns": [
+                        {"name": "a", "type_name": "VARIANT"},
+                        {"name": "b", "type_name": "GEOGRAPHY"}
+                    ]
+                }
+            ]
+        })).unwrap();
+
+        let tables = &list.tables;
+        assert_eq!(tables[0].table_type(), Some(&TableType::VIEW));
+        assert_eq!(tables[0].data_source_format(), Some(&DataSourceFormat::DELTASHARING));
+        assert_eq!(tables[1].table_type(), Some(&TableType::STREAMING_TABLE));
+        assert_eq!(tables[1].data_source_format(), Some(&DataSourceFormat::ICEBERG));
+        assert_eq!(tables[2].table_type(), Some(&TableType::Unknown("HOLOGRAPHIC_TABLE".to_owned())));
+        assert_eq!(tables[2].data_source_format(), Some(&DataSourceFormat::Unknown("CRYSTAL".to_owned())));
+        assert_eq!(tables[2].columns()[0].type_name(), Some(&ColumnTypeName::VARIANT));
+        assert_eq!(tables[2].columns()[1].type_name(), Some(&ColumnTypeName::Unknown("GEOGRAPHY".to_owned())));
+
+        let json = serde_json::to_value(&tables[2]).unwrap();
+        assert_eq!(json["table_type"], "HOLOGRAPHIC_TABLE");
+        assert_eq!(json["data_source_format"], "CRYSTAL");
+        assert_eq!(json["columns"][1]["type_name"], "GEOGRAPHY");
+        assert_eq!(serde_json::to_value(TableType::MATERIALIZED_VIEW).unwrap(), "MATERIALIZED_VIEW");
+
+        assert_eq!(ColumnTypeName::TIMESTAMP_NTZ.as_str(), "timestamp_ntz");
+        assert_eq!(tables[2].columns()[1].type_name().map(ColumnTypeName::as_str), Some("GEOGRAPHY"));
+        let column = ColumnInfoBuilder::default()
+            .name("b".to_owned())
+            .type_name(ColumnTypeName::Unknown("GEOGRAPHY".to_owned()))
+            .nullable(true)
+            .generate_type_json()
+            .build()
+            .unwrap();
+        let type_json: serde_json::Value = serde_json::from_str(column.type_json().unwrap()).unwrap();
+        assert_eq!(type_json["type"], "geography");
+    }
+}
@@ -0,0 +1,298 @@
+use crate::{errors::UCRSError, request::RequestClient};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use crate::errors::UCRSResult;
+
+/// Only Databricks serves tag assignments. With any other `Flavor`, including
+/// the default `Flavor::Oss`, every call fails with `UnsupportedByFlavor`, so
+/// there are no tags to attach with `with_tags` and `Search::tag` predicates
+/// match nothing.
+pub struct TagsClient<'a> {
+    client: &'a RequestClient
+}
+
+/// Something that can carry tags, named by its full name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TagEntity {
+    Catalog(String),
+    Schema(String),
+    Table(String),
+    Volume(String),
+    /// A column of the table with the given full name.
+    Column(String, String)
+}
+
+impl TagEntity {
+    pub fn entity_type(&self) -> &'static str {
+        match self {
+            TagEntity::Catalog(_) => "catalogs",
+            TagEntity::Schema(_) => "schemas",
+            TagEntity::Table(_) => "tables",
+            TagEntity::Volume(_) => "volumes",
+            TagEntity::Column(_, _) => "columns"
+        }
+    }
+
+    pub fn entity_name(&self) -> String {
+        match self {
+            TagEntity::Catalog(name) | TagEntity::Schema(name) | TagEntity::Table(name) | TagEntity::Volume(name) => name.clone(),
+            TagEntity::Column(table, column) => format!("{}.{}", table, column)
+        }
+    }
+
+    fn not_found(&self) -> UCRSError {
+        match self {
+            TagEntity::Catalog(name) => UCRSError::CatalogNotFound(name.clone()),
+            TagEntity::Schema(name) => UCRSError::SchemaNotFound(name.clone()),
+            TagEntity::Table(name) | TagEntity::Column(name, _) => UCRSError::TableNotFound(name.clone()),
+            TagEntity::Volume(name) => UCRSError::VolumeNotFound(name.clone())
+        }
+    }
+}
+
+impl<'a> TagsClient<'a> {
+    pub fn new(client: &'a RequestClient) -> Self {
+        Self { client }
+    }
+
+    /// The entity's tags route, or the route of its tag `key`. Names and keys
+    /// are pushed as single path segments, so `/`, `?` and `#` are escaped.
+    fn route(&self, entity: &TagEntity, key: Option<&str>) -> UCRSResult<url::Url> {
+        let mut route = self.client.base_url.join(self.client.flavor().tags_route()?)
+            .map_err(UCRSError::MalformedURL)?;
+        {
+            // Joining an absolute path succeeded, so the URL can be a base.
+            let mut segments = route.path_segments_mut().expect("joined URLs can be a base");
+            segments.push(entity.entity_type()).push(&entity.entity_name());
+            if let Some(key) = key {
+                segments.push("tags").push(key);
+            }
+        }
+        Ok(route)
+    }
+
+    fn map_not_found<T>(entity: &TagEntity, res: UCRSResult<T>) -> UCRSResult<T> {
+        if let Err(ref e) = res {
+            match e.status() {
+                Some(StatusCode::NOT_FOUND) => Err(entity.not_found()),
+                _ => res
+            }
+        } else {
+            res
+        }
+    }
+
+    /// Sets `key` on `entity`, replacing any existing value.
+    pub async fn set(&self, entity: &TagEntity, key: &str, value: Option<&str>) -> UCRSResult<TagAssignment> {
+        let route = self.client.base_url.join(self.client.flavor().tags_route()?)
+            .map_err(UCRSError::MalformedURL)?;
+        let body = TagAssignment {
+            entity_type: entity.entity_type().to_owned(),
+            entity_name: entity.entity_name(),
+            tag_key: key.to_owned(),
+            tag_value: value.map(str::to_owned)
+        };
+        let res = self.client.post_op("tags.set", route, Some(&body)).await;
+        Self::map_not_found(entity, res)
+    }
+
+    pub async fn unset(&self, entity: &TagEntity, key: &str) -> UCRSResult<()> {
+        let route = self.route(entity, Some(key))?;
+        let res = self.client.delete_op("tags.unset", route, None::<String>).await;
+        Self::map_not_found(entity, res)
+    }
+
+    pub async fn list(&self, entity: &TagEntity, page_token: Option<String>, max_results: Option<i32>) -> UCRSResult<ListTagsResponse> {
+        let mut route = self.route(entity, None)?;
+        self.client.flavor().page(&mut route, page_token, max_results);
+        let res = self.client.get_op("tags.list", route, None::<String>).await;
+        Self::map_not_found(entity, res)
+    }
+
+    /// Follows `next_page_token` until every tag on the entity has been listed.
+    pub async fn list_all(&self, entity: &TagEntity) -> UCRSResult<Vec<Tag>> {
+        let mut tags = vec![];
+        let mut page_token = None;
+        loop {
+            let page = self.list(entity, page_token, None).await?;
+            tags.extend(page.tag_assignments.into_iter().map(Tag::from));
+            match page.next_page_token {
+                Some(token) if !token.is_empty() => page_token = Some(token),
+                _ => return Ok(tags)
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+pub struct ListTagsResponse {
+    tag_assignments: Vec<TagAssignment>,
+    next_page_token: Option<String>
+}
+
+impl ListTagsResponse {
+    pub fn tag_assignments(&self) -> &[TagAssignment] {
+        &self.tag_assignments
+    }
+
+    pub fn next_page_token(&self) -> Option<&str> {
+        self.next_page_token.as_deref()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TagAssignment {
+    entity_type: String,
+    entity_name: String,
+    tag_key: String,
+    tag_value: Option<String>
+}
+
+impl TagAssignment {
+    pub fn entity_type(&self) -> &str {
+        &self.entity_type
+    }
+
+    pub fn entity_name(&self) -> &str {
+        &self.entity_name
+    }
+
+    pub fn tag_key(&self) -> &str {
+        &self.tag_key
+    }
+
+    pub fn tag_value(&self) -> Option<&str> {
+        self.tag_value.as_deref()
+    }
+}
+
+/// A tag as carried on `CatalogInfo`, `SchemaInfo`, `TableInfo` and `ColumnInfo`.
+/// Tags without a value are flags such as `pii`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Tag {
+    key: String,
+    value: Option<String>
+}
+
+impl Tag {
+    pub fn new(key: &str, value: Option<&str>) -> Self {
+        Self {
+            key: key.to_owned(),
+            value: value.map(str::to_owned)
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn value(&self) -> Option<&str> {
+        self.value.as_deref()
+    }
+}
+
+impl From<TagAssignment> for Tag {
+    fn from(assignment: TagAssignment) -> Self {
+        Self {
+            key: assignment.tag_key,
+            value: assignment.tag_value
+        }
+    }
+}
+
+/// Looks up `key` in `tags`: `None` if the tag isn't set, `Some(None)` if it
+/// is set without a value.
+pub(crate) fn find_tag<'t>(tags: &'t [Tag], key: &str) -> Option<Option<&'t str>> {
+    tags.iter().find(|t| t.key == key).map(|t| t.value())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flavor::Flavor;
+    use serde_json::json;
+    use wiremock::matchers::{body_json, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_set_list_unset() -> UCRSResult<()> {
+        let server = MockServer::start().await;
+        let assignment = json!({
+            "entity_type": "columns",
+            "entity_name": "unity.default.customers.email",
+            "tag_key": "pii",
+            "tag_value": "true"
+        });
+        Mock::given(method("POST"))
+            .and(path("/api/2.1/unity-catalog/entity-tag-assignments"))
+            .and(body_json(&assignment))
+            .respond_with(ResponseTemplate::new(200).set_body_json(&assignment))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/entity-tag-assignments/tables/unity.default.customers"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "tag_assignments": [{"entity_type": "tables", "entity_name": "unity.default.customers", "tag_key": "domain", "tag_value": "finance"}],
+                "next_page_token": "2"
+            })))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/entity-tag-assignments/tables/unity.default.customers"))
+            .and(query_param("page_token", "2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "tag_assignments": [{"entity_type": "tables", "entity_name": "unity.default.customers", "tag_key": "gold"}]
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("DELETE"))
+            .and(path("/api/2.1/unity-catalog/entity-tag-assignments/schemas/unity.default/tags/domain"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("DELETE"))
+            .and(path("/api/2.1/unity-catalog/entity-tag-assignments/catalogs/nope/tags/domain"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+
+        let oss = RequestClient::new(&server.uri(), true)?;
+        let res = TagsClient::new(&oss).list_all(&TagEntity::Catalog("unity".to_owned())).await;
+        assert!(matches!(res, Err(UCRSError::UnsupportedByFlavor(_))));
+
+        let rc = RequestClient::new(&server.uri(), true)?.with_flavor(Flavor::Databricks);
+        let client = TagsClient::new(&rc);
+
+        let column = TagEntity::Column("unity.default.customers".to_owned(), "email".to_owned());
+        let set = client.set(&column, "pii", Some("true")).await?;
+        assert_eq!(set.entity_name(), "unity.default.customers.email");
+
+        let tags = client.list_all(&TagEntity::Table("unity.default.customers".to_owned())).await?;
+        assert_eq!(tags, vec![Tag::new("domain", Some("finance")), Tag::new("gold", None)]);
+        assert_eq!(find_tag(&tags, "gold"), Some(None));
+        assert_eq!(find_tag(&tags, "pii"), None);
+
+        client.unset(&TagEntity::Schema("unity.default".to_owned()), "domain").await?;
+        let res = client.unset(&TagEntity::Catalog("nope".to_owned()), "domain").await;
+        assert!(matches!(res, Err(UCRSError::CatalogNotFound(ref n)) if n == "nope"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_names_and_keys_are_escaped() -> UCRSResult<()> {
+        let server = MockServer::start().await;
+        Mock::given(method("DELETE"))
+            .and(path("/api/2.1/unity-catalog/entity-tag-assignments/tables/unity.default.odd%20name/tags/a%2Fb%3Fc%23d%20e"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let rc = RequestClient::new(&server.uri(), true)?.with_flavor(Flavor::Databricks);
+        TagsClient::new(&rc).unset(&TagEntity::Table("unity.default.odd name".to_owned()), "a/b?c#d e").await?;
+        Ok(())
+    }
+}
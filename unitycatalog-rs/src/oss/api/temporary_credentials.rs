@@ -0,0 +1,229 @@
+use crate::{errors::UCRSError, request::RequestClient};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use crate::errors::UCRSResult;
+
+/// Vends short-lived cloud credentials scoped to a single table or volume.
+pub struct TemporaryCredentialsClient<'a> {
+    client: &'a RequestClient
+}
+
+impl<'a> TemporaryCredentialsClient<'a> {
+    pub fn new(client: &'a RequestClient) -> Self {
+        Self { client }
+    }
+
+    pub async fn table(&self, table_id: &str, operation: TableOperation) -> UCRSResult<TemporaryCredentials> {
+        let route = self.client.base_url.join("/api/2.1/unity-catalog/temporary-table-credentials")
+            .map_err(UCRSError::MalformedURL)?;
+        let body = GenerateTableCredential {
+            table_id: table_id.to_owned(),
+            operation
+        };
+        let res = self.client.post_op("temporary_credentials.table", route, Some(&body)).await;
+        if let Err(ref e) = res {
+            match e.status() {
+                Some(StatusCode::NOT_FOUND) => Err(UCRSError::TableNotFound(table_id.to_owned())),
+                _ => res
+            }
+        } else {
+            res
+        }
+    }
+
+    pub async fn volume(&self, volume_id: &str, operation: VolumeOperation) -> UCRSResult<TemporaryCredentials> {
+        let route = self.client.base_url.join("/api/2.1/unity-catalog/temporary-volume-credentials")
+            .map_err(UCRSError::MalformedURL)?;
+        let body = GenerateVolumeCredential {
+            volume_id: volume_id.to_owned(),
+            operation
+        };
+        let res = self.client.post_op("temporary_credentials.volume", route, Some(&body)).await;
+        if let Err(ref e) = res {
+            match e.status() {
+                Some(StatusCode::NOT_FOUND) => Err(UCRSError::VolumeNotFound(volume_id.to_owned())),
+                _ => res
+            }
+        } else {
+            res
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+struct GenerateTableCredential {
+    table_id: String,
+    operation: TableOperation
+}
+
+#[derive(Serialize, Debug)]
+struct GenerateVolumeCredential {
+    volume_id: String,
+    operation: VolumeOperation
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TableOperation {
+    READ,
+    #[allow(non_camel_case_types)]
+    READ_WRITE
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VolumeOperation {
+    #[allow(non_camel_case_types)]
+    READ_VOLUME,
+    #[allow(non_camel_case_types)]
+    WRITE_VOLUME
+}
+
+/// Holds the credentials for whichever cloud the securable lives in; for
+/// local `file://` storage none of them are set.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct TemporaryCredentials {
+    aws_temp_credentials: Option<AwsTemporaryCredentials>,
+    azure_user_delegation_sas: Option<AzureUserDelegationSas>,
+    gcp_oauth_token: Option<GcpOauthToken>,
+    /// Milliseconds since the epoch.
+    expiration_time: Option<i64>,
+    url: Option<String>,
+    #[serde(flatten)]
+    extra: HashMap<String, serde_json::Value>
+}
+
+impl TemporaryCredentials {
+    pub fn aws_temp_credentials(&self) -> Option<&AwsTemporaryCredentials> {
+        self.aws_temp_credentials.as_ref()
+    }
+
+    pub fn azure_user_delegation_sas(&self) -> Option<&AzureUserDelegationSas> {
+        self.azure_user_delegation_sas.as_ref()
+    }
+
+    pub fn gcp_oauth_token(&self) -> Option<&GcpOauthToken> {
+        self.gcp_oauth_token.as_ref()
+    }
+
+    pub fn expiration_time(&self) -> Option<i64> {
+        self.expiration_time
+    }
+
+    pub fn url(&self) -> Option<&str> {
+        self.url.as_deref()
+    }
+
+    /// Fields returned by the server that this crate doesn't model yet.
+    pub fn extra(&self) -> &HashMap<String, serde_json::Value> {
+        &self.extra
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct AwsTemporaryCredentials {
+    access_key_id: String,
+    secret_access_key: String,
+    session_token: Option<String>
+}
+
+impl AwsTemporaryCredentials {
+    pub fn access_key_id(&self) -> &str {
+        &self.access_key_id
+    }
+
+    pub fn secret_access_key(&self) -> &str {
+        &self.secret_access_key
+    }
+
+    pub fn session_token(&self) -> Option<&str> {
+        self.session_token.as_deref()
+    }
+}
+
+impl fmt::Debug for AwsTemporaryCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AwsTemporaryCredentials")
+            .field("access_key_id", &self.access_key_id)
+            .field("secret_access_key", &"REDACTED")
+            .field("session_token", &self.session_token.as_ref().map(|_| "REDACTED"))
+            .finish()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct AzureUserDelegationSas {
+    sas_token: String
+}
+
+impl AzureUserDelegationSas {
+    pub fn sas_token(&self) -> &str {
+        &self.sas_token
+    }
+}
+
+impl fmt::Debug for AzureUserDelegationSas {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AzureUserDelegationSas")
+            .field("sas_token", &"REDACTED")
+            .finish()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct GcpOauthToken {
+    oauth_token: String
+}
+
+impl GcpOauthToken {
+    pub fn oauth_token(&self) -> &str {
+        &self.oauth_token
+    }
+}
+
+impl fmt::Debug for GcpOauthToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GcpOauthToken")
+            .field("oauth_token", &"REDACTED")
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wiremock::matchers::{body_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_table_and_volume_credentials() -> UCRSResult<()> {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/2.1/unity-catalog/temporary-table-credentials"))
+            .and(body_json(json!({"table_id": "t-1", "operation": "READ"})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "aws_temp_credentials": {"access_key_id": "AKIA", "secret_access_key": "shh", "session_token": "sesame"},
+                "expiration_time": 1700000000000_i64
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/2.1/unity-catalog/temporary-volume-credentials"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+
+        let rc = RequestClient::new(&server.uri(), true)?;
+        let client = TemporaryCredentialsClient::new(&rc);
+        let creds = client.table("t-1", TableOperation::READ).await?;
+        assert_eq!(creds.expiration_time(), Some(1700000000000));
+        assert_eq!(creds.aws_temp_credentials().map(|c| c.secret_access_key()), Some("shh"));
+        let debug = format!("{:?}", creds);
+        assert!(debug.contains("AKIA") && !debug.contains("shh") && !debug.contains("sesame"));
+
+        let res = client.volume("v-1", VolumeOperation::READ_VOLUME).await;
+        assert!(matches!(res, Err(UCRSError::VolumeNotFound(ref id)) if id == "v-1"));
+        Ok(())
+    }
+}
@@ -0,0 +1,142 @@
+use crate::{errors::UCRSError, request::RequestClient};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use crate::errors::UCRSResult;
+
+pub struct VolumesClient<'a> {
+    client: &'a RequestClient
+}
+
+impl<'a> VolumesClient<'a> {
+    pub fn new(client: &'a RequestClient) -> Self {
+        Self { client }
+    }
+
+    pub fn full_name(catalog_name: &str, schema_name: &str, name: &str) -> String {
+        format!("{}.{}.{}", catalog_name, schema_name, name)
+    }
+
+    pub async fn get(&self, full_name: &str) -> UCRSResult<VolumeInfo> {
+        let path = self.client.base_url.join(&format!("/api/2.1/unity-catalog/volumes/{}", full_name))
+            .map_err(UCRSError::MalformedURL)?;
+        let res = self.client.get_op("volumes.get", path, None::<String>).await;
+        if let Err(ref e) = res {
+            match e.status() {
+                Some(StatusCode::NOT_FOUND) =>
+                    Err(UCRSError::VolumeNotFound(full_name.to_owned())),
+                _ => res
+            }
+        } else {
+            res
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum VolumeType {
+    MANAGED,
+    EXTERNAL,
+    /// A value this crate doesn't know yet, kept as sent by the server.
+    #[serde(untagged)]
+    Unknown(String)
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+pub struct VolumeInfo {
+    name: Option<String>,
+    catalog_name: Option<String>,
+    schema_name: Option<String>,
+    full_name: Option<String>,
+    volume_id: Option<String>,
+    volume_type: Option<VolumeType>,
+    storage_location: Option<String>,
+    comment: Option<String>,
+    owner: Option<String>,
+    created_at: Option<i64>,
+    updated_at: Option<i64>,
+    #[serde(flatten)]
+    extra: HashMap<String, serde_json::Value>
+}
+
+impl VolumeInfo {
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn catalog_name(&self) -> Option<&str> {
+        self.catalog_name.as_deref()
+    }
+
+    pub fn schema_name(&self) -> Option<&str> {
+        self.schema_name.as_deref()
+    }
+
+    pub fn full_name(&self) -> Option<&str> {
+        self.full_name.as_deref()
+    }
+
+    pub fn volume_id(&self) -> Option<&str> {
+        self.volume_id.as_deref()
+    }
+
+    pub fn volume_type(&self) -> Option<&VolumeType> {
+        self.volume_type.as_ref()
+    }
+
+    pub fn storage_location(&self) -> Option<&str> {
+        self.storage_location.as_deref()
+    }
+
+    pub fn comment(&self) -> Option<&str> {
+        self.comment.as_deref()
+    }
+
+    pub fn owner(&self) -> Option<&str> {
+        self.owner.as_deref()
+    }
+
+    pub fn created_at(&self) -> Option<i64> {
+        self.created_at
+    }
+
+    pub fn updated_at(&self) -> Option<i64> {
+        self.updated_at
+    }
+
+    /// Fields returned by the server that this crate doesn't model yet.
+    pub fn extra(&self) -> &HashMap<String, serde_json::Value> {
+        &self.extra
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_get() -> UCRSResult<()> {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/volumes/unity.default.landing"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "name": "landing",
+                "volume_id": "v-1",
+                "volume_type": "EXTERNAL",
+                "storage_location": "s3://lake/landing"
+            })))
+            .mount(&server)
+            .await;
+
+        let rc = RequestClient::new(&server.uri(), true)?;
+        let client = VolumesClient::new(&rc);
+        let volume = client.get("unity.default.landing").await?;
+        assert_eq!(volume.volume_id(), Some("v-1"));
+        assert_eq!(volume.volume_type(), Some(&VolumeType::EXTERNAL));
+        assert!(matches!(client.get("unity.default.missing").await, Err(UCRSError::VolumeNotFound(_))));
+        Ok(())
+    }
+}
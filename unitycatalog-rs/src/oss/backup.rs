@@ -0,0 +1,528 @@
+use crate::errors::{UCRSError, UCRSResult};
+use crate::oss::api::catalogs::{CatalogsClient, CreateCatalog, UpdateCatalog};
+use crate::oss::api::schemas::{CreateSchema, SchemasClient, UpdateSchema};
+use crate::oss::api::constraints::TableConstraint;
+use crate::oss::api::tables::{CreateChecks, TableDefinition, TableInfo, TablesClient};
+use crate::oss::walk::{CatalogEntry, Walker};
+use crate::request::RequestClient;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+
+pub const BACKUP_VERSION: u32 = 1;
+
+/// Catalogs, schemas and tables (with their columns) as a JSON document.
+///
+/// Functions are not included. Tables keep the names of the functions their
+/// row filters and column masks call, so those functions must exist on the
+/// target before restoring; tables whose functions are missing are listed
+/// as failed.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MetastoreBackup {
+    pub version: u32,
+    pub catalogs: Vec<CatalogEntry>
+}
+
+/// What `restore` does with an object that already exists on the server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConflictPolicy {
+    #[default]
+    Skip,
+    /// Update catalogs and schemas in place; drop and recreate views and
+    /// external tables. Managed tables are skipped, since dropping one
+    /// deletes its data.
+    Overwrite,
+    Fail
+}
+
+/// Full names of the objects a restore touched. Tables the backup cannot
+/// recreate, or that fail the client-side checks of `TablesClient::create`,
+/// are listed in `failed` and the restore carries on without them.
+#[derive(Debug, Default)]
+pub struct RestoreReport {
+    pub created: Vec<String>,
+    pub updated: Vec<String>,
+    pub skipped: Vec<String>,
+    pub failed: Vec<(String, UCRSError)>
+}
+
+fn is_conflict(e: &UCRSError) -> bool {
+    matches!(e,
+        UCRSError::DuplicateCatalogName(_)
+        | UCRSError::DuplicateSchemaName(_)
+        | UCRSError::DuplicateTableName(_))
+}
+
+impl RestoreReport {
+    async fn apply<T, U, F>(&mut self, policy: ConflictPolicy, key: String, create: impl Future<Output = UCRSResult<T>>, overwrite: F) -> UCRSResult<()>
+        where
+            F: FnOnce() -> U,
+            U: Future<Output = UCRSResult<()>> {
+        match create.await {
+            Ok(_) => self.created.push(key),
+            Err(e) if is_conflict(&e) => match policy {
+                ConflictPolicy::Skip => self.skipped.push(key),
+                ConflictPolicy::Overwrite => {
+                    overwrite().await?;
+                    self.updated.push(key);
+                },
+                ConflictPolicy::Fail => return Err(e)
+            },
+            Err(e) => return Err(e)
+        }
+        Ok(())
+    }
+}
+
+impl MetastoreBackup {
+    /// Exports everything the walker visits.
+    pub async fn export(walker: &Walker<'_>) -> UCRSResult<Self> {
+        Ok(Self {
+            version: BACKUP_VERSION,
+            catalogs: walker.snapshot().await?.catalogs
+        })
+    }
+
+    pub fn to_json(&self) -> UCRSResult<String> {
+        serde_json::to_string_pretty(self)
+            .map_err(UCRSError::JSONFormattingError)
+    }
+
+    pub fn from_json(json: &str) -> UCRSResult<Self> {
+        let backup: Self = serde_json::from_str(json)
+            .map_err(UCRSError::JSONParsingError)?;
+        if backup.version > BACKUP_VERSION {
+            return Err(UCRSError::UnsupportedBackupVersion(backup.version));
+        }
+        Ok(backup)
+    }
+
+    /// Recreates every catalog, then its schemas, then their tables, stopping
+    /// at the first server error. A schema's tables are created after the
+    /// tables their foreign keys reference. Each function and parent table
+    /// the tables' checks need is fetched once per restore.
+    pub async fn restore(&self, client: &RequestClient, policy: ConflictPolicy) -> UCRSResult<RestoreReport> {
+        let catalogs = CatalogsClient::new(client);
+        let schemas = SchemasClient::new(client);
+        let tables = TablesClient::new(client);
+        let checks = CreateChecks::default();
+        let mut report = RestoreReport::default();
+
+        for catalog in &self.catalogs {
+            let catalog_name = catalog.info.name().unwrap_or_default();
+            report.apply(policy, catalog_name.to_owned(),
+                catalogs.create(CreateCatalog::from(&catalog.info)),
+                || async {
+                    catalogs.update(catalog_name, UpdateCatalog::from(&catalog.info)).await.map(|_| ())
+                }).await?;
+
+            for schema in &catalog.schemas {
+                let full_name = SchemasClient::full_name(catalog_name, schema.info.name().unwrap_or_default());
+                report.apply(policy, full_name.clone(),
+                    schemas.create(CreateSchema::from(&schema.info)),
+                    || async {
+                        schemas.update(&full_name, UpdateSchema::from(&schema.info)).await.map(|_| ())
+                    }).await?;
+
+                for table in parents_first(catalog_name, schema.info.name().unwrap_or_default(), &schema.tables) {
+                    let full_name = TablesClient::full_name(catalog_name, schema.info.name().unwrap_or_default(), table.name().unwrap_or_default());
+                    let definition = match TableDefinition::try_from(table) {
+                        Ok(definition) => definition,
+                        Err(e) => {
+                            report.failed.push((full_name, e));
+                            continue;
+                        }
+                    };
+                    let policy = match policy {
+                        ConflictPolicy::Overwrite if !definition.recreatable() => ConflictPolicy::Skip,
+                        policy => policy
+                    };
+                    let res = report.apply(policy, full_name.clone(),
+                        tables.create_definition_checked(definition.clone(), &checks),
+                        || async {
+                            tables.delete(&full_name).await?;
+                            tables.create_definition_checked(definition, &checks).await.map(|_| ())
+                        }).await;
+                    match res {
+                        Err(e @ (UCRSError::FunctionNotFound(_) | UCRSError::InvalidConstraint(_, _) | UCRSError::TableNotFound(_))) =>
+                            report.failed.push((full_name, e)),
+                        res => res?
+                    }
+                }
+            }
+        }
+        Ok(report)
+    }
+}
+
+/// `tables` ordered so each comes after the tables of the same schema its
+/// foreign keys reference, since creating a table checks that its parents
+/// exist. Tables in a reference cycle keep their backup order.
+fn parents_first<'t>(catalog_name: &str, schema_name: &str, tables: &'t [TableInfo]) -> Vec<&'t TableInfo> {
+    let names = tables.iter()
+        .map(|t| TablesClient::full_name(catalog_name, schema_name, t.name().unwrap_or_default()))
+        .collect::<Vec<_>>();
+    let mut placed = vec![false; tables.len()];
+    let mut ordered = Vec::with_capacity(tables.len());
+    while ordered.len() < tables.len() {
+        let before = ordered.len();
+        for (i, table) in tables.iter().enumerate() {
+            let waiting = table.table_constraints().iter().any(|c| match c {
+                TableConstraint::ForeignKey(fk) => fk.parent_table() != names[i]
+                    && names.iter().zip(&placed).any(|(name, placed)| !placed && name == fk.parent_table()),
+                _ => false
+            });
+            if !placed[i] && !waiting {
+                placed[i] = true;
+                ordered.push(table);
+            }
+        }
+        if ordered.len() == before {
+            ordered.extend(tables.iter().zip(&placed).filter(|(_, placed)| !**placed).map(|(t, _)| t));
+        }
+    }
+    ordered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_partial_json, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const BACKUP: &str = r#"{
+        "version": 1,
+        "catalogs": [{
+            "info": {"name": "unity", "comment": "Main catalog", "properties": {"team": "data"}},
+            "schemas": [{
+                "info": {"name": "default", "catalog_name": "unity", "comment": "Default schema"},
+                "tables": [{
+                    "name": "marksheet",
+                    "catalog_name": "unity",
+                    "schema_name": "default",
+                    "table_type": "EXTERNAL",
+                    "data_source_format": "DELTA",
+                    "storage_location": "s3://bucket/marksheet",
+                    "columns": [{"name": "id", "type_name": "INT", "type_text": "int", "position": 0, "nullable": false}]
+                }, {
+                    "name": "scores",
+                    "catalog_name": "unity",
+                    "schema_name": "default",
+                    "table_type": "MANAGED",
+                    "data_source_format": "DELTA",
+                    "storage_location": "s3://source-metastore/tables/1234",
+                    "columns": [{"name": "score", "type_name": "DOUBLE"}]
+                }, {
+                    "name": "unknown_format",
+                    "catalog_name": "unity",
+                    "schema_name": "default",
+                    "table_type": "EXTERNAL",
+                    "storage_location": "s3://bucket/unknown_format"
+                }]
+            }]
+        }]
+    }"#;
+
+    #[tokio::test]
+    async fn test_export() -> UCRSResult<()> {
+        let server = MockServer::start().await;
+        let respond = |body: serde_json::Value| ResponseTemplate::new(200).set_body_json(body);
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/catalogs"))
+            .respond_with(respond(serde_json::json!({"catalogs": [{"name": "unity", "comment": "Main catalog"}]})))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/schemas"))
+            .respond_with(respond(serde_json::json!({"schemas": [{"name": "default", "catalog_name": "unity"}]})))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/tables"))
+            .and(query_param("schema_name", "default"))
+            .respond_with(respond(serde_json::json!({"tables": [{
+                "name": "marksheet",
+                "catalog_name": "unity",
+                "schema_name": "default",
+                "table_type": "MANAGED",
+                "data_source_format": "DELTA",
+                "columns": [{"name": "id", "type_name": "INT"}]
+            }]})))
+            .mount(&server)
+            .await;
+
+        let rc = RequestClient::new(&server.uri(), true)?;
+        let backup = MetastoreBackup::export(&Walker::new(&rc)).await?;
+        let restored = MetastoreBackup::from_json(&backup.to_json()?)?;
+
+        assert_eq!(restored.version, BACKUP_VERSION);
+        let table = &restored.catalogs[0].schemas[0].tables[0];
+        assert_eq!(table.name(), Some("marksheet"));
+        assert_eq!(serde_json::to_value(table).unwrap()["columns"][0]["type_name"], "INT");
+        Ok(())
+    }
+
+    #[test]
+    fn test_newer_version_is_rejected() {
+        let res = MetastoreBackup::from_json(r#"{"version": 99, "catalogs": []}"#);
+        assert!(matches!(res, Err(UCRSError::UnsupportedBackupVersion(99))));
+    }
+
+    async fn mock_target(exists: bool) -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/2.1/unity-catalog/catalogs"))
+            .respond_with(ResponseTemplate::new(if exists { 409 } else { 200 })
+                .set_body_json(serde_json::json!({"name": "unity"})))
+            .mount(&server)
+            .await;
+        Mock::given(method("PATCH"))
+            .and(path("/api/2.1/unity-catalog/catalogs/unity"))
+            .and(body_partial_json(serde_json::json!({"comment": "Main catalog", "properties": {"team": "data"}})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"name": "unity"})))
+            .expect(if exists { 1 } else { 0 })
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/2.1/unity-catalog/schemas"))
+            .and(body_partial_json(serde_json::json!({"name": "default", "catalog_name": "unity"})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"name": "default"})))
+            .mount(&server)
+            .await;
+        if exists {
+            // Skip and Overwrite both see marksheet exist; Overwrite then recreates it.
+            Mock::given(method("POST"))
+                .and(path("/api/2.1/unity-catalog/tables"))
+                .and(body_partial_json(serde_json::json!({"name": "marksheet"})))
+                .respond_with(ResponseTemplate::new(409))
+                .up_to_n_times(2)
+                .mount(&server)
+                .await;
+        }
+        Mock::given(method("POST"))
+            .and(path("/api/2.1/unity-catalog/tables"))
+            .and(body_partial_json(serde_json::json!({
+                "name": "marksheet",
+                "table_type": "EXTERNAL",
+                "storage_location": "s3://bucket/marksheet"
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"name": "marksheet"})))
+            .mount(&server)
+            .await;
+        Mock::given(method("DELETE"))
+            .and(path("/api/2.1/unity-catalog/tables/unity.default.marksheet"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("200 OK"))
+            .expect(if exists { 1 } else { 0 })
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/2.1/unity-catalog/tables"))
+            .and(body_partial_json(serde_json::json!({"name": "scores", "table_type": "MANAGED", "storage_location": null})))
+            .respond_with(if exists {
+                ResponseTemplate::new(409)
+            } else {
+                ResponseTemplate::new(200).set_body_json(serde_json::json!({"name": "scores"}))
+            })
+            .mount(&server)
+            .await;
+        Mock::given(method("DELETE"))
+            .and(path("/api/2.1/unity-catalog/tables/unity.default.scores"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&server)
+            .await;
+        server
+    }
+
+    fn failed_names(report: &RestoreReport) -> Vec<&str> {
+        report.failed.iter().map(|(name, _)| name.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_restore() -> UCRSResult<()> {
+        let backup = MetastoreBackup::from_json(BACKUP)?;
+
+        let server = mock_target(false).await;
+        let rc = RequestClient::new(&server.uri(), true)?;
+        let report = backup.restore(&rc, ConflictPolicy::Fail).await?;
+        assert_eq!(report.created, vec!["unity", "unity.default", "unity.default.marksheet", "unity.default.scores"]);
+        assert_eq!(failed_names(&report), vec!["unity.default.unknown_format"]);
+        assert!(matches!(report.failed[0].1, UCRSError::InvalidTableDefinition(_, _)));
+
+        let server = mock_target(true).await;
+        let rc = RequestClient::new(&server.uri(), true)?;
+        let report = backup.restore(&rc, ConflictPolicy::Skip).await?;
+        assert_eq!(report.skipped, vec!["unity", "unity.default.marksheet", "unity.default.scores"]);
+        let report = backup.restore(&rc, ConflictPolicy::Overwrite).await?;
+        assert_eq!(report.updated, vec!["unity", "unity.default.marksheet"]);
+        assert_eq!(report.skipped, vec!["unity.default.scores"]);
+        assert_eq!(failed_names(&report), vec!["unity.default.unknown_format"]);
+        let res = backup.restore(&rc, ConflictPolicy::Fail).await;
+        assert!(matches!(res, Err(UCRSError::DuplicateCatalogName(ref n)) if n == "unity"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_restore_view() -> UCRSResult<()> {
+        let backup = MetastoreBackup::from_json(r#"{
+            "version": 1,
+            "catalogs": [{
+                "info": {"name": "unity"},
+                "schemas": [{
+                    "info": {"name": "default", "catalog_name": "unity"},
+                    "tables": [{
+                        "name": "top_scores",
+                        "catalog_name": "unity",
+                        "schema_name": "default",
+                        "table_type": "VIEW",
+                        "view_definition": "SELECT * FROM unity.default.scores ORDER BY score DESC LIMIT 10",
+                        "storage_location": "s3://bucket/views/top_scores",
+                        "columns": [{"name": "score", "type_name": "DOUBLE"}]
+                    }]
+                }]
+            }]
+        }"#)?;
+
+        let server = MockServer::start().await;
+        let respond = |body: serde_json::Value| ResponseTemplate::new(200).set_body_json(body);
+        Mock::given(method("POST"))
+            .and(path("/api/2.1/unity-catalog/catalogs"))
+            .respond_with(respond(serde_json::json!({"name": "unity"})))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/2.1/unity-catalog/schemas"))
+            .respond_with(respond(serde_json::json!({"name": "default"})))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/2.1/unity-catalog/tables"))
+            .and(body_partial_json(serde_json::json!({
+                "name": "top_scores",
+                "table_type": "VIEW",
+                "view_definition": "SELECT * FROM unity.default.scores ORDER BY score DESC LIMIT 10",
+                "storage_location": null
+            })))
+            .respond_with(respond(serde_json::json!({"name": "top_scores"})))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let rc = RequestClient::new(&server.uri(), true)?;
+        let report = backup.restore(&rc, ConflictPolicy::Fail).await?;
+        assert_eq!(report.created, vec!["unity", "unity.default", "unity.default.top_scores"]);
+        assert!(report.failed.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_foreign_key_parents_are_restored_first() -> UCRSResult<()> {
+        let table = |name: &str, constraints: serde_json::Value| serde_json::json!({
+            "name": name,
+            "catalog_name": "unity",
+            "schema_name": "default",
+            "table_type": "EXTERNAL",
+            "data_source_format": "DELTA",
+            "storage_location": format!("s3://bucket/{}", name),
+            "columns": [{"name": "id", "type_name": "INT"}, {"name": "parent_id", "type_name": "INT"}],
+            "table_constraints": constraints
+        });
+        let fk = |parent: &str| serde_json::json!([{"foreign_key_constraint": {
+            "name": "fk", "child_columns": ["parent_id"], "parent_table": parent, "parent_columns": ["id"]
+        }}]);
+        let backup: MetastoreBackup = serde_json::from_value(serde_json::json!({
+            "version": 1,
+            "catalogs": [{
+                "info": {"name": "unity"},
+                "schemas": [{
+                    "info": {"name": "default", "catalog_name": "unity"},
+                    "tables": [
+                        table("line_items", fk("unity.default.orders")),
+                        table("orders", fk("unity.default.customers")),
+                        table("returns", fk("unity.archive.orders")),
+                        table("customers", serde_json::json!([]))
+                    ]
+                }]
+            }]
+        })).unwrap();
+
+        let server = MockServer::start().await;
+        let respond = |body: serde_json::Value| ResponseTemplate::new(200).set_body_json(body);
+        for route in ["/api/2.1/unity-catalog/catalogs", "/api/2.1/unity-catalog/schemas", "/api/2.1/unity-catalog/tables"] {
+            Mock::given(method("POST"))
+                .and(path(route))
+                .respond_with(respond(serde_json::json!({})))
+                .mount(&server)
+                .await;
+        }
+        for parent in ["customers", "orders"] {
+            Mock::given(method("GET"))
+                .and(path(format!("/api/2.1/unity-catalog/tables/unity.default.{}", parent)))
+                .respond_with(respond(table(parent, serde_json::json!([]))))
+                .mount(&server)
+                .await;
+        }
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/tables/unity.archive.orders"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+
+        let rc = RequestClient::new(&server.uri(), true)?;
+        let report = backup.restore(&rc, ConflictPolicy::Fail).await?;
+        assert_eq!(report.created, vec![
+            "unity",
+            "unity.default",
+            "unity.default.customers",
+            "unity.default.orders",
+            "unity.default.line_items"
+        ]);
+        assert_eq!(failed_names(&report), vec!["unity.default.returns"]);
+        assert!(matches!(report.failed[0].1, UCRSError::TableNotFound(ref n) if n == "unity.archive.orders"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_missing_function_fails_the_table() -> UCRSResult<()> {
+        let backup = MetastoreBackup::from_json(r#"{
+            "version": 1,
+            "catalogs": [{
+                "info": {"name": "unity"},
+                "schemas": [{
+                    "info": {"name": "default", "catalog_name": "unity"},
+                    "tables": [{
+                        "name": "customers",
+                        "catalog_name": "unity",
+                        "schema_name": "default",
+                        "table_type": "EXTERNAL",
+                        "data_source_format": "DELTA",
+                        "storage_location": "s3://bucket/customers",
+                        "columns": [{"name": "region", "type_name": "STRING"}],
+                        "row_filter": {"function_name": "unity.default.same_region", "input_column_names": ["region"]}
+                    }]
+                }]
+            }]
+        }"#)?;
+
+        let server = MockServer::start().await;
+        for route in ["/api/2.1/unity-catalog/catalogs", "/api/2.1/unity-catalog/schemas"] {
+            Mock::given(method("POST"))
+                .and(path(route))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({})))
+                .mount(&server)
+                .await;
+        }
+        Mock::given(method("POST"))
+            .and(path("/api/2.1/unity-catalog/tables"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        let rc = RequestClient::new(&server.uri(), true)?;
+        let report = backup.restore(&rc, ConflictPolicy::Fail).await?;
+        assert_eq!(report.created, vec!["unity", "unity.default"]);
+        assert_eq!(failed_names(&report), vec!["unity.default.customers"]);
+        assert!(matches!(report.failed[0].1, UCRSError::FunctionNotFound(ref n) if n == "unity.default.same_region"));
+        Ok(())
+    }
+}
@@ -0,0 +1,496 @@
+use crate::errors::{UCRSError, UCRSResult};
+use crate::oss::api::tables::{ColumnInfo, ColumnInfoBuilder, ColumnTypeName, CreateTable, DataSourceFormat, TableType, TablesClient};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+/// The body of an AWS Glue `GetTables` response, as written by
+/// `aws glue get-tables`.
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "PascalCase")]
+pub struct GetTablesResponse {
+    pub table_list: Vec<GlueTable>,
+    pub next_token: Option<String>
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "PascalCase")]
+pub struct GlueTable {
+    pub name: String,
+    pub database_name: Option<String>,
+    pub description: Option<String>,
+    pub table_type: Option<String>,
+    pub parameters: Option<HashMap<String, String>>,
+    pub storage_descriptor: Option<StorageDescriptor>,
+    pub partition_keys: Option<Vec<GlueColumn>>
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "PascalCase")]
+pub struct StorageDescriptor {
+    pub columns: Option<Vec<GlueColumn>>,
+    pub location: Option<String>,
+    pub input_format: Option<String>,
+    pub output_format: Option<String>,
+    pub serde_info: Option<SerDeInfo>
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "PascalCase")]
+pub struct SerDeInfo {
+    pub serialization_library: Option<String>,
+    pub parameters: Option<HashMap<String, String>>
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "PascalCase")]
+pub struct GlueColumn {
+    pub name: String,
+    pub r#type: Option<String>,
+    pub comment: Option<String>
+}
+
+impl GetTablesResponse {
+    pub fn from_json(json: &str) -> UCRSResult<Self> {
+        serde_json::from_str(json)
+            .map_err(UCRSError::JSONParsingError)
+    }
+
+    /// Converts every table into `catalog_name`, keeping each result so one
+    /// unsupported table doesn't hide the rest.
+    pub fn to_create_tables(&self, catalog_name: &str) -> Vec<UCRSResult<CreateTable>> {
+        self.table_list.iter()
+            .map(|t| t.to_create_table(catalog_name))
+            .collect()
+    }
+}
+
+impl GlueTable {
+    /// Builds a `CreateTable` for an external table in `catalog_name`, using the
+    /// Glue database as the schema. Partition keys become trailing columns with
+    /// a `partition_index`.
+    pub fn to_create_table(&self, catalog_name: &str) -> UCRSResult<CreateTable> {
+        let full_name = TablesClient::full_name(catalog_name, self.database_name.as_deref().unwrap_or_default(), &self.name);
+        let invalid = |reason: String| UCRSError::InvalidTableDefinition(full_name.clone(), reason);
+
+        let schema_name = self.database_name.as_deref()
+            .ok_or_else(|| invalid("missing DatabaseName".to_owned()))?;
+
+        if self.table_type.as_deref() == Some("VIRTUAL_VIEW") {
+            return Err(invalid("views cannot be imported from Glue".to_owned()));
+        }
+        let storage = self.storage_descriptor.as_ref()
+            .ok_or_else(|| invalid("missing StorageDescriptor".to_owned()))?;
+        let location = storage.location.clone()
+            .ok_or_else(|| invalid("missing StorageDescriptor.Location".to_owned()))?;
+        let format = self.data_source_format()
+            .ok_or_else(|| invalid("unrecognised storage format".to_owned()))?;
+
+        // Spark registers Delta tables in Glue with a single placeholder
+        // column; the real schema lives in the Delta log.
+        let placeholder = |c: &GlueColumn| format == DataSourceFormat::DELTA
+            && c.name == "col"
+            && c.r#type.as_deref() == Some("array<string>");
+        let data_columns = storage.columns.iter().flatten()
+            .filter(|c| !placeholder(c))
+            .map(|c| (c, None));
+        let partition_columns = self.partition_keys.iter().flatten()
+            .enumerate()
+            .map(|(i, c)| (c, Some(i as i32)));
+        let columns = data_columns.chain(partition_columns)
+            .enumerate()
+            .map(|(position, (column, partition_index))| glue_column(column, position as u32, partition_index))
+            .collect::<Result<Vec<_>, String>>()
+            .map_err(invalid)?;
+
+        Ok(CreateTable::builder()
+            .name(self.name.clone())
+            .catalog_name(catalog_name.to_owned())
+            .schema_name(schema_name.to_owned())
+            .table_type(TableType::EXTERNAL)
+            .data_source_format(format)
+            .columns(columns)
+            .storage_location(Some(location))
+            .comment(self.description.clone())
+            .properties(self.parameters.clone())
+            .build()
+            .expect("all CreateTable fields are set"))
+    }
+
+    fn data_source_format(&self) -> Option<DataSourceFormat> {
+        let parameter = |key: &str| self.parameters.as_ref()
+            .and_then(|p| p.get(key))
+            .map(|v| v.to_ascii_lowercase());
+        if parameter("spark.sql.sources.provider").as_deref() == Some("delta")
+            || parameter("table_type").as_deref() == Some("delta") {
+            return Some(DataSourceFormat::DELTA);
+        }
+
+        let storage = self.storage_descriptor.as_ref()?;
+        let input_format = storage.input_format.as_deref().unwrap_or_default();
+        let serde = storage.serde_info.as_ref()
+            .and_then(|s| s.serialization_library.as_deref())
+            .unwrap_or_default();
+        if input_format.contains("Parquet") || serde.contains("Parquet") {
+            Some(DataSourceFormat::PARQUET)
+        } else if input_format.contains("Orc") || serde.contains("Orc") {
+            Some(DataSourceFormat::ORC)
+        } else if input_format.contains("Avro") || serde.contains("Avro") {
+            Some(DataSourceFormat::AVRO)
+        } else if serde.contains("JsonSerDe") {
+            Some(DataSourceFormat::JSON)
+        } else if serde.contains("OpenCSVSerde") {
+            Some(DataSourceFormat::CSV)
+        } else if input_format.contains("TextInputFormat") {
+            let delimiter = storage.serde_info.as_ref()
+                .and_then(|s| s.parameters.as_ref())
+                .and_then(|p| p.get("field.delim"));
+            match (delimiter.map(String::as_str), parameter("classification").as_deref()) {
+                (Some(","), _) | (_, Some("csv")) => Some(DataSourceFormat::CSV),
+                _ => Some(DataSourceFormat::TEXT)
+            }
+        } else {
+            match parameter("classification")?.as_str() {
+                "parquet" => Some(DataSourceFormat::PARQUET),
+                "orc" => Some(DataSourceFormat::ORC),
+                "avro" => Some(DataSourceFormat::AVRO),
+                "json" => Some(DataSourceFormat::JSON),
+                "csv" => Some(DataSourceFormat::CSV),
+                _ => None
+            }
+        }
+    }
+}
+
+fn glue_column(column: &GlueColumn, position: u32, partition_index: Option<i32>) -> Result<ColumnInfo, String> {
+    let hive_type = column.r#type.as_deref()
+        .ok_or_else(|| format!("column {} has no type", column.name))?
+        .trim();
+    let parsed = HiveType::parse(hive_type)
+        .ok_or_else(|| format!("column {} has unsupported type {}", column.name, hive_type))?;
+
+    let type_json = json!({
+        "name": column.name,
+        "type": parsed.spark_json(),
+        "nullable": true,
+        "metadata": {}
+    });
+    let (precision, scale) = match parsed {
+        HiveType::Decimal(p, s) => (p, s),
+        _ => (0, 0)
+    };
+    let mut builder = ColumnInfoBuilder::default();
+    builder
+        .name(column.name.clone())
+        .type_text(hive_type.to_owned())
+        .type_json(type_json.to_string())
+        .type_name(parsed.type_name())
+        .type_precision(precision)
+        .type_scale(scale)
+        .position(position)
+        .nullable(true);
+    if let Some(comment) = &column.comment {
+        builder.comment(comment.clone());
+    }
+    if let Some(partition_index) = partition_index {
+        builder.partition_index(partition_index);
+    }
+    Ok(builder.build().expect("ColumnInfo fields all have defaults"))
+}
+
+/// A parsed Hive column type, e.g. `map<string,array<decimal(10,2)>>`.
+#[derive(Debug, Clone, PartialEq)]
+enum HiveType {
+    Primitive(ColumnTypeName, &'static str),
+    Decimal(i32, i32),
+    Array(Box<HiveType>),
+    Map(Box<HiveType>, Box<HiveType>),
+    Struct(Vec<(String, HiveType)>)
+}
+
+impl HiveType {
+    fn parse(input: &str) -> Option<Self> {
+        let (parsed, rest) = Self::parse_prefix(input)?;
+        rest.trim().is_empty().then_some(parsed)
+    }
+
+    fn parse_prefix(input: &str) -> Option<(Self, &str)> {
+        let input = input.trim_start();
+        let end = input.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(input.len());
+        let (name, rest) = input.split_at(end);
+        let rest = rest.trim_start();
+        // Keywords are case-insensitive; struct field names keep their case.
+        let name = name.to_ascii_lowercase();
+
+        let primitive = |type_name, spark| Some((HiveType::Primitive(type_name, spark), rest));
+        match name.as_str() {
+            "boolean" => primitive(ColumnTypeName::BOOLEAN, "boolean"),
+            "tinyint" => primitive(ColumnTypeName::BYTE, "byte"),
+            "smallint" => primitive(ColumnTypeName::SHORT, "short"),
+            "int" | "integer" => primitive(ColumnTypeName::INT, "integer"),
+            "bigint" => primitive(ColumnTypeName::LONG, "long"),
+            "float" => primitive(ColumnTypeName::FLOAT, "float"),
+            "double" => primitive(ColumnTypeName::DOUBLE, "double"),
+            "date" => primitive(ColumnTypeName::DATE, "date"),
+            "timestamp" => primitive(ColumnTypeName::TIMESTAMP, "timestamp"),
+            "binary" => primitive(ColumnTypeName::BINARY, "binary"),
+            "string" => primitive(ColumnTypeName::STRING, "string"),
+            "varchar" | "char" => {
+                // Length is dropped: Spark reads both as plain strings. A bare
+                // `char` means char(1).
+                let rest = if rest.starts_with('(') { Self::parenthesised(rest)?.1 } else { rest };
+                let type_name = if name == "char" { ColumnTypeName::CHAR } else { ColumnTypeName::STRING };
+                Some((HiveType::Primitive(type_name, "string"), rest))
+            },
+            "decimal" | "numeric" => match rest.strip_prefix('(') {
+                Some(_) => {
+                    let (args, rest) = Self::parenthesised(rest)?;
+                    let mut args = args.split(',').map(|a| a.trim().parse::<i32>());
+                    let precision = args.next()?.ok()?;
+                    let scale = args.next().unwrap_or(Ok(0)).ok()?;
+                    Some((HiveType::Decimal(precision, scale), rest))
+                },
+                None => Some((HiveType::Decimal(10, 0), rest))
+            },
+            "array" => {
+                let rest = rest.strip_prefix('<')?;
+                let (element, rest) = Self::parse_prefix(rest)?;
+                let rest = rest.trim_start().strip_prefix('>')?;
+                Some((HiveType::Array(Box::new(element)), rest))
+            },
+            "map" => {
+                let rest = rest.strip_prefix('<')?;
+                let (key, rest) = Self::parse_prefix(rest)?;
+                let rest = rest.trim_start().strip_prefix(',')?;
+                let (value, rest) = Self::parse_prefix(rest)?;
+                let rest = rest.trim_start().strip_prefix('>')?;
+                Some((HiveType::Map(Box::new(key), Box::new(value)), rest))
+            },
+            "struct" => {
+                let mut rest = rest.strip_prefix('<')?;
+                let mut fields = vec![];
+                loop {
+                    rest = rest.trim_start();
+                    if let Some(after) = rest.strip_prefix('>') {
+                        return Some((HiveType::Struct(fields), after));
+                    }
+                    let (field_name, after) = rest.split_once(':')?;
+                    let (field_type, after) = Self::parse_prefix(after)?;
+                    fields.push((field_name.trim().trim_matches('`').to_owned(), field_type));
+                    let after = after.trim_start();
+                    rest = after.strip_prefix(',').unwrap_or(after);
+                }
+            },
+            _ => None
+        }
+    }
+
+    /// Splits `(args)rest` into `args` and `rest`.
+    fn parenthesised(input: &str) -> Option<(&str, &str)> {
+        let inner = input.strip_prefix('(')?;
+        let (args, rest) = inner.split_once(')')?;
+        Some((args, rest))
+    }
+
+    fn type_name(&self) -> ColumnTypeName {
+        match self {
+            HiveType::Primitive(type_name, _) => type_name.clone(),
+            HiveType::Decimal(_, _) => ColumnTypeName::DECIMAL,
+            HiveType::Array(_) => ColumnTypeName::ARRAY,
+            HiveType::Map(_, _) => ColumnTypeName::MAP,
+            HiveType::Struct(_) => ColumnTypeName::STRUCT
+        }
+    }
+
+    /// The Spark `DataType` JSON that UC stores in `type_json`.
+    fn spark_json(&self) -> Value {
+        match self {
+            HiveType::Primitive(_, spark) => json!(spark),
+            HiveType::Decimal(p, s) => json!(format!("decimal({},{})", p, s)),
+            HiveType::Array(element) => json!({
+                "type": "array",
+                "elementType": element.spark_json(),
+                "containsNull": true
+            }),
+            HiveType::Map(key, value) => json!({
+                "type": "map",
+                "keyType": key.spark_json(),
+                "valueType": value.spark_json(),
+                "valueContainsNull": true
+            }),
+            HiveType::Struct(fields) => json!({
+                "type": "struct",
+                "fields": fields.iter().map(|(name, t)| json!({
+                    "name": name,
+                    "type": t.spark_json(),
+                    "nullable": true,
+                    "metadata": {}
+                })).collect::<Vec<_>>()
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GET_TABLES: &str = r#"{
+        "TableList": [
+            {
+                "Name": "events",
+                "DatabaseName": "analytics",
+                "Description": "Raw events",
+                "TableType": "EXTERNAL_TABLE",
+                "Parameters": {"classification": "parquet", "EXTERNAL": "TRUE"},
+                "StorageDescriptor": {
+                    "Columns": [
+                        {"Name": "id", "Type": "bigint"},
+                        {"Name": "amount", "Type": "decimal(12,2)", "Comment": "In cents"},
+                        {"Name": "tags", "Type": "map<string,array<int>>"},
+                        {"Name": "device", "Type": "struct<os:string,version:varchar(10)>"}
+                    ],
+                    "Location": "s3://lake/analytics/events/",
+                    "InputFormat": "org.apache.hadoop.hive.ql.io.parquet.MapredParquetInputFormat",
+                    "OutputFormat": "org.apache.hadoop.hive.ql.io.parquet.MapredParquetOutputFormat",
+                    "SerdeInfo": {"SerializationLibrary": "org.apache.hadoop.hive.ql.io.parquet.serde.ParquetHiveSerDe"}
+                },
+                "PartitionKeys": [
+                    {"Name": "year", "Type": "int"},
+                    {"Name": "day", "Type": "date"}
+                ]
+            },
+            {
+                "Name": "users",
+                "DatabaseName": "analytics",
+                "TableType": "EXTERNAL_TABLE",
+                "StorageDescriptor": {
+                    "Columns": [{"Name": "email", "Type": "string"}],
+                    "Location": "s3://lake/analytics/users/",
+                    "InputFormat": "org.apache.hadoop.mapred.TextInputFormat",
+                    "SerdeInfo": {
+                        "SerializationLibrary": "org.apache.hadoop.hive.serde2.lazy.LazySimpleSerDe",
+                        "Parameters": {"field.delim": ","}
+                    }
+                }
+            },
+            {
+                "Name": "orders",
+                "DatabaseName": "analytics",
+                "TableType": "EXTERNAL_TABLE",
+                "Parameters": {"spark.sql.sources.provider": "delta"},
+                "StorageDescriptor": {
+                    "Columns": [{"Name": "col", "Type": "array<string>"}],
+                    "Location": "s3://lake/analytics/orders",
+                    "SerdeInfo": {"SerializationLibrary": "org.apache.hadoop.hive.serde2.lazy.LazySimpleSerDe"}
+                }
+            },
+            {
+                "Name": "recent_orders",
+                "DatabaseName": "analytics",
+                "TableType": "VIRTUAL_VIEW",
+                "ViewOriginalText": "SELECT * FROM orders"
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn test_import_get_tables() -> UCRSResult<()> {
+        let tables = GetTablesResponse::from_json(GET_TABLES)?.to_create_tables("legacy");
+        assert_eq!(tables.len(), 4);
+
+        let events = serde_json::to_value(tables[0].as_ref().unwrap()).unwrap();
+        assert_eq!(events["catalog_name"], "legacy");
+        assert_eq!(events["schema_name"], "analytics");
+        assert_eq!(events["table_type"], "EXTERNAL");
+        assert_eq!(events["data_source_format"], "PARQUET");
+        assert_eq!(events["storage_location"], "s3://lake/analytics/events/");
+        assert_eq!(events["comment"], "Raw events");
+        assert_eq!(events["properties"]["classification"], "parquet");
+
+        let columns = events["columns"].as_array().unwrap();
+        let summary = columns.iter()
+            .map(|c| (c["name"].as_str().unwrap(), c["type_name"].as_str().unwrap(), c["position"].as_u64().unwrap(), c["partition_index"].as_i64()))
+            .collect::<Vec<_>>();
+        assert_eq!(summary, vec![
+            ("id", "LONG", 0, None),
+            ("amount", "DECIMAL", 1, None),
+            ("tags", "MAP", 2, None),
+            ("device", "STRUCT", 3, None),
+            ("year", "INT", 4, Some(0)),
+            ("day", "DATE", 5, Some(1))
+        ]);
+        assert_eq!(columns[1]["type_precision"], 12);
+        assert_eq!(columns[1]["type_scale"], 2);
+        assert_eq!(columns[1]["comment"], "In cents");
+
+        let tags_json: Value = serde_json::from_str(columns[2]["type_json"].as_str().unwrap()).unwrap();
+        assert_eq!(tags_json, json!({
+            "name": "tags",
+            "type": {
+                "type": "map",
+                "keyType": "string",
+                "valueType": {"type": "array", "elementType": "integer", "containsNull": true},
+                "valueContainsNull": true
+            },
+            "nullable": true,
+            "metadata": {}
+        }));
+        let device_json: Value = serde_json::from_str(columns[3]["type_json"].as_str().unwrap()).unwrap();
+        assert_eq!(device_json["type"]["fields"][1]["type"], "string");
+
+        let users = serde_json::to_value(tables[1].as_ref().unwrap()).unwrap();
+        assert_eq!(users["data_source_format"], "CSV");
+        let orders = serde_json::to_value(tables[2].as_ref().unwrap()).unwrap();
+        assert_eq!(orders["data_source_format"], "DELTA");
+        assert_eq!(orders["columns"], json!([]));
+
+        assert!(matches!(&tables[3], Err(UCRSError::InvalidTableDefinition(name, _)) if name == "legacy.analytics.recent_orders"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_database_name() {
+        let table = GlueTable {
+            name: "events".to_owned(),
+            ..Default::default()
+        };
+        let res = table.to_create_table("legacy");
+        assert!(matches!(res, Err(UCRSError::InvalidTableDefinition(_, ref reason)) if reason == "missing DatabaseName"));
+    }
+
+    #[test]
+    fn test_hive_types() {
+        assert_eq!(HiveType::parse("decimal"), Some(HiveType::Decimal(10, 0)));
+        assert_eq!(HiveType::parse("char"), Some(HiveType::Primitive(ColumnTypeName::CHAR, "string")));
+        assert_eq!(HiveType::parse("char(3)"), HiveType::parse("char"));
+        assert_eq!(HiveType::parse("array< struct< a : int , b:string > >").map(|t| t.spark_json()), Some(json!({
+            "type": "array",
+            "elementType": {"type": "struct", "fields": [
+                {"name": "a", "type": "integer", "nullable": true, "metadata": {}},
+                {"name": "b", "type": "string", "nullable": true, "metadata": {}}
+            ]},
+            "containsNull": true
+        })));
+        assert_eq!(HiveType::parse("uniontype<int,string>"), None);
+        assert_eq!(HiveType::parse("map<string>"), None);
+        assert_eq!(HiveType::parse("int extra"), None);
+    }
+
+    #[test]
+    fn test_struct_field_case_is_kept() -> Result<(), String> {
+        let column = GlueColumn {
+            name: "user".to_owned(),
+            r#type: Some("STRUCT<userId:INT>".to_owned()),
+            comment: None
+        };
+        let info = glue_column(&column, 0, None)?;
+        assert_eq!(info.type_text(), Some("STRUCT<userId:INT>"));
+        let type_json: Value = serde_json::from_str(info.type_json().unwrap()).unwrap();
+        assert_eq!(type_json["type"], json!({
+            "type": "struct",
+            "fields": [{"name": "userId", "type": "integer", "nullable": true, "metadata": {}}]
+        }));
+        Ok(())
+    }
+}
@@ -1 +1,6 @@
-mod api;
\ No newline at end of file
+pub mod api;
+pub mod backup;
+pub mod glue;
+pub mod search;
+pub mod sync;
+pub mod walk;
\ No newline at end of file
@@ -0,0 +1,355 @@
+use crate::errors::{UCRSError, UCRSResult};
+use crate::oss::api::schemas::SchemasClient;
+use crate::oss::api::tables::{ColumnInfo, ColumnTypeName, TablesClient};
+use crate::oss::api::tags::Tag;
+use crate::oss::walk::{TreeNode, Walker};
+use futures_util::TryStreamExt;
+use globset::{Glob, GlobMatcher};
+use regex::Regex;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchKind {
+    Catalog,
+    Schema,
+    Table
+}
+
+/// An object that satisfied every predicate of a `Search`. Higher scores rank first.
+#[derive(Debug)]
+pub struct SearchHit {
+    pub full_name: String,
+    pub kind: SearchKind,
+    pub score: u32,
+    /// Columns that satisfied a column predicate, in table order.
+    pub matched_columns: Vec<String>,
+    pub node: TreeNode
+}
+
+#[derive(Debug)]
+enum NameMatcher {
+    Glob(GlobMatcher),
+    Regex(Regex)
+}
+
+impl NameMatcher {
+    fn is_match(&self, name: &str) -> bool {
+        match self {
+            NameMatcher::Glob(g) => g.is_match(name),
+            NameMatcher::Regex(r) => r.is_match(name)
+        }
+    }
+
+    fn is_exact(&self, name: &str) -> bool {
+        match self {
+            NameMatcher::Glob(g) => g.glob().glob() == name,
+            NameMatcher::Regex(r) => r.find(name).is_some_and(|m| m.len() == name.len())
+        }
+    }
+}
+
+#[derive(Debug)]
+enum ColumnPredicate {
+    Name(GlobMatcher),
+    Type(ColumnTypeName)
+}
+
+impl ColumnPredicate {
+    fn matches(&self, column: &ColumnInfo) -> bool {
+        match self {
+            ColumnPredicate::Name(g) => column.name().is_some_and(|n| g.is_match(n)),
+            ColumnPredicate::Type(t) => column.type_name() == Some(t)
+        }
+    }
+}
+
+fn glob(pattern: &str) -> UCRSResult<GlobMatcher> {
+    Glob::new(pattern)
+        .map(|g| g.compile_matcher())
+        .map_err(|e| UCRSError::InvalidPattern(pattern.to_owned(), e.to_string()))
+}
+
+/// Client-side search over what a `Walker` visits.
+///
+/// Every predicate given must match. Name patterns apply to an object's own
+/// name, comment text matches case-insensitively on every word, and column
+/// predicates only ever match tables.
+pub struct Search<'a> {
+    walker: Walker<'a>,
+    name: Option<NameMatcher>,
+    comment_terms: Vec<String>,
+    properties: Vec<(String, Option<String>)>,
+    tags: Vec<(String, Option<String>)>,
+    columns: Vec<ColumnPredicate>
+}
+
+impl<'a> Search<'a> {
+    pub fn new(walker: Walker<'a>) -> Self {
+        Self {
+            walker,
+            name: None,
+            comment_terms: vec![],
+            properties: vec![],
+            tags: vec![],
+            columns: vec![]
+        }
+    }
+
+    pub fn name_glob(mut self, pattern: &str) -> UCRSResult<Self> {
+        self.name = Some(NameMatcher::Glob(glob(pattern)?));
+        Ok(self)
+    }
+
+    pub fn name_regex(mut self, pattern: &str) -> UCRSResult<Self> {
+        let regex = Regex::new(pattern)
+            .map_err(|e| UCRSError::InvalidPattern(pattern.to_owned(), e.to_string()))?;
+        self.name = Some(NameMatcher::Regex(regex));
+        Ok(self)
+    }
+
+    pub fn comment(mut self, text: &str) -> Self {
+        self.comment_terms = text.split_whitespace().map(str::to_lowercase).collect();
+        self
+    }
+
+    /// Requires property `key`, with `value` if one is given.
+    pub fn property(mut self, key: &str, value: Option<&str>) -> Self {
+        self.properties.push((key.to_owned(), value.map(str::to_owned)));
+        self
+    }
+
+    /// Requires tag `key`, with `value` if one is given. Only tags present on
+    /// the listed objects are seen; see `TagsClient` for fetching them.
+    pub fn tag(mut self, key: &str, value: Option<&str>) -> Self {
+        self.tags.push((key.to_owned(), value.map(str::to_owned)));
+        self
+    }
+
+    pub fn column_name(mut self, pattern: &str) -> UCRSResult<Self> {
+        self.columns.push(ColumnPredicate::Name(glob(pattern)?));
+        Ok(self)
+    }
+
+    pub fn column_type(mut self, type_name: ColumnTypeName) -> Self {
+        self.columns.push(ColumnPredicate::Type(type_name));
+        self
+    }
+
+    /// Walks the tree and returns every hit, best first. Fails on the first
+    /// listing error.
+    pub async fn run(&self) -> UCRSResult<Vec<SearchHit>> {
+        let mut hits = vec![];
+        let mut nodes = self.walker.walk();
+        while let Some(node) = nodes.try_next().await? {
+            if let Some(hit) = self.score(node) {
+                hits.push(hit);
+            }
+        }
+        hits.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.full_name.cmp(&b.full_name)));
+        Ok(hits)
+    }
+
+    fn score(&self, node: TreeNode) -> Option<SearchHit> {
+        let (kind, full_name, name, comment, properties, tags, columns) = match &node {
+            TreeNode::Catalog(c) => {
+                let name = c.info.name().unwrap_or_default();
+                (SearchKind::Catalog, name.to_owned(), name, c.info.comment(), c.info.properties(), c.info.tags(), None)
+            },
+            TreeNode::Schema(s) => {
+                let name = s.info.name().unwrap_or_default();
+                (SearchKind::Schema, SchemasClient::full_name(&s.catalog_name, name), name, s.info.comment(), s.info.properties(),
+                    s.info.tags(), None)
+            },
+            TreeNode::Table(t) => {
+                let name = t.info.name().unwrap_or_default();
+                (SearchKind::Table, TablesClient::full_name(&t.catalog_name, &t.schema_name, name), name,
+                    t.info.comment(), t.info.properties(), t.info.tags(), Some(t.info.columns()))
+            }
+        };
+
+        let mut score = 0;
+        if let Some(matcher) = &self.name {
+            if !matcher.is_match(name) {
+                return None;
+            }
+            score += if matcher.is_exact(name) { 10 } else { 5 };
+        }
+        score += self.score_comment(comment)?;
+        score += self.score_properties(properties)?;
+        score += self.score_tags(tags)?;
+        let matched_columns = match columns {
+            Some(columns) => self.match_columns(columns)?,
+            None if self.columns.is_empty() => vec![],
+            None => return None
+        };
+        score += matched_columns.len() as u32;
+
+        Some(SearchHit {
+            full_name,
+            kind,
+            score,
+            matched_columns,
+            node
+        })
+    }
+
+    fn score_comment(&self, comment: Option<&str>) -> Option<u32> {
+        if self.comment_terms.is_empty() {
+            return Some(0);
+        }
+        let comment = comment?.to_lowercase();
+        if !self.comment_terms.iter().all(|t| comment.contains(t.as_str())) {
+            return None;
+        }
+        let phrase_bonus = if comment.contains(&self.comment_terms.join(" ")) { 3 } else { 0 };
+        Some(2 * self.comment_terms.len() as u32 + phrase_bonus)
+    }
+
+    fn score_properties(&self, properties: Option<&HashMap<String, String>>) -> Option<u32> {
+        let matched = self.properties.iter()
+            .all(|(key, value)| match (properties.and_then(|p| p.get(key)), value) {
+                (Some(actual), Some(expected)) => actual == expected,
+                (Some(_), None) => true,
+                (None, _) => false
+            });
+        matched.then_some(3 * self.properties.len() as u32)
+    }
+
+    fn score_tags(&self, tags: &[Tag]) -> Option<u32> {
+        let matched = self.tags.iter()
+            .all(|(key, value)| tags.iter().any(|t| t.key() == key && (value.is_none() || t.value() == value.as_deref())));
+        matched.then_some(3 * self.tags.len() as u32)
+    }
+
+    /// Names of the columns matching any predicate, or `None` when some
+    /// predicate matches no column at all.
+    fn match_columns(&self, columns: &[ColumnInfo]) -> Option<Vec<String>> {
+        if !self.columns.iter().all(|p| columns.iter().any(|c| p.matches(c))) {
+            return None;
+        }
+        Some(columns.iter()
+            .filter(|c| self.columns.iter().any(|p| p.matches(c)))
+            .map(|c| c.name().unwrap_or_default().to_owned())
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::request::RequestClient;
+    use serde_json::json;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    async fn mock_metastore() -> MockServer {
+        let server = MockServer::start().await;
+        let respond = |body: serde_json::Value| ResponseTemplate::new(200).set_body_json(body);
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/catalogs"))
+            .respond_with(respond(json!({"catalogs": [
+                {"name": "payments", "comment": "Card payments", "properties": {"owner_team": "payments"}}
+            ]})))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/schemas"))
+            .respond_with(respond(json!({"schemas": [
+                {"name": "customers", "catalog_name": "payments", "comment": "Customer data"}
+            ]})))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/tables"))
+            .and(query_param("schema_name", "customers"))
+            .respond_with(respond(json!({"tables": [
+                {
+                    "name": "customers",
+                    "comment": "One row per customer email address",
+                    "properties": {"owner_team": "payments", "pii": "true"},
+                    "tags": [{"key": "domain", "value": "finance"}, {"key": "gold"}],
+                    "columns": [
+                        {"name": "id", "type_name": "LONG"},
+                        {"name": "email", "type_name": "STRING"},
+                        {"name": "backup_email", "type_name": "STRING"}
+                    ]
+                },
+                {
+                    "name": "contacts",
+                    "comment": "Email address book",
+                    "properties": {"owner_team": "growth"},
+                    "columns": [{"name": "email", "type_name": "STRING"}]
+                },
+                {
+                    "name": "ledger",
+                    "properties": {"owner_team": "payments"},
+                    "columns": [{"name": "amount", "type_name": "DECIMAL"}]
+                }
+            ]})))
+            .mount(&server)
+            .await;
+        server
+    }
+
+    fn names(hits: &[SearchHit]) -> Vec<&str> {
+        hits.iter().map(|h| h.full_name.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_search() -> UCRSResult<()> {
+        let server = mock_metastore().await;
+        let rc = RequestClient::new(&server.uri(), true)?;
+
+        let hits = Search::new(Walker::new(&rc))
+            .column_name("*email*")?
+            .run()
+            .await?;
+        assert_eq!(names(&hits), vec!["payments.customers.customers", "payments.customers.contacts"]);
+        assert_eq!(hits[0].matched_columns, vec!["email", "backup_email"]);
+        assert_eq!(hits[0].kind, SearchKind::Table);
+
+        let hits = Search::new(Walker::new(&rc))
+            .property("owner_team", Some("payments"))
+            .run()
+            .await?;
+        assert_eq!(names(&hits), vec!["payments", "payments.customers.customers", "payments.customers.ledger"]);
+
+        let hits = Search::new(Walker::new(&rc))
+            .comment("EMAIL address")
+            .run()
+            .await?;
+        assert_eq!(names(&hits), vec!["payments.customers.contacts", "payments.customers.customers"]);
+        assert_eq!(hits[0].score, 7);
+
+        let hits = Search::new(Walker::new(&rc))
+            .name_regex("^c")?
+            .column_type(ColumnTypeName::STRING)
+            .property("pii", None)
+            .run()
+            .await?;
+        assert_eq!(names(&hits), vec!["payments.customers.customers"]);
+
+        let hits = Search::new(Walker::new(&rc))
+            .tag("domain", Some("finance"))
+            .tag("gold", None)
+            .run()
+            .await?;
+        assert_eq!(names(&hits), vec!["payments.customers.customers"]);
+
+        let hits = Search::new(Walker::new(&rc))
+            .name_glob("customers")?
+            .run()
+            .await?;
+        assert_eq!(names(&hits), vec!["payments.customers", "payments.customers.customers"]);
+        assert!(hits.iter().all(|h| h.score == 10));
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_patterns() -> UCRSResult<()> {
+        let rc = RequestClient::new("http://localhost", true)?;
+        assert!(matches!(Search::new(Walker::new(&rc)).name_regex("("), Err(UCRSError::InvalidPattern(_, _))));
+        assert!(matches!(Search::new(Walker::new(&rc)).column_name("[a"), Err(UCRSError::InvalidPattern(_, _))));
+        Ok(())
+    }
+}
@@ -0,0 +1,489 @@
+use crate::errors::{UCRSError, UCRSResult};
+use crate::oss::api::catalogs::{CatalogInfo, CatalogsClient, CreateCatalog, UpdateCatalogBuilder};
+use crate::oss::api::schemas::{CreateSchema, SchemaInfo, SchemasClient, UpdateSchemaBuilder};
+use crate::oss::api::tables::{TableDefinition, TableInfo, TablesClient};
+use crate::oss::walk::{TreeNode, Walker};
+use crate::request::RequestClient;
+use futures_util::StreamExt;
+use std::collections::HashMap;
+
+/// Full names, in the target's naming, of the objects a sync touched.
+/// Objects below a failed catalog or schema are left alone and not listed.
+/// `drifted` lists tables that differ but were left as they are because they
+/// are managed on one side.
+#[derive(Debug, Default)]
+pub struct SyncReport {
+    pub created: Vec<String>,
+    pub updated: Vec<String>,
+    pub skipped: Vec<String>,
+    pub drifted: Vec<String>,
+    pub failed: Vec<(String, UCRSError)>
+}
+
+/// The fields a catalog or schema update can change, with no properties
+/// counting the same as empty ones.
+fn updatable<'i>(comment: Option<&'i str>, properties: Option<&'i HashMap<String, String>>) -> (Option<&'i str>, Option<&'i HashMap<String, String>>) {
+    (comment, properties.filter(|p| !p.is_empty()))
+}
+
+enum Outcome {
+    Created,
+    Updated,
+    Skipped,
+    Drifted
+}
+
+/// Mirrors what a `Walker` visits on one server into another.
+///
+/// Objects missing from the target are created, ones that differ are updated
+/// and identical ones are skipped, so an interrupted sync can simply be run
+/// again. Catalogs and schemas are compared on their comment and properties,
+/// the only fields an update can change. Tables have no update endpoint, so
+/// differing views and external tables are dropped and recreated; managed
+/// tables are never dropped, since that would delete their data. View
+/// definitions are copied verbatim, so catalog renames don't apply to the
+/// SQL. Functions are not synced either: those called by row filters and
+/// column masks must already exist on the target, or the table is listed as
+/// failed.
+pub struct MetastoreSync<'a> {
+    source: Walker<'a>,
+    target: &'a RequestClient,
+    catalog_renames: HashMap<String, String>,
+    location_rewrites: Vec<(String, String)>
+}
+
+impl<'a> MetastoreSync<'a> {
+    pub fn new(source: Walker<'a>, target: &'a RequestClient) -> Self {
+        Self {
+            source,
+            target,
+            catalog_renames: HashMap::new(),
+            location_rewrites: vec![]
+        }
+    }
+
+    pub fn rename_catalog(mut self, from: &str, to: &str) -> Self {
+        self.catalog_renames.insert(from.to_owned(), to.to_owned());
+        self
+    }
+
+    /// Replaces `from_prefix` with `to_prefix` in table storage locations.
+    /// The first matching rewrite wins.
+    pub fn rewrite_location(mut self, from_prefix: &str, to_prefix: &str) -> Self {
+        self.location_rewrites.push((from_prefix.to_owned(), to_prefix.to_owned()));
+        self
+    }
+
+    fn target_catalog<'n>(&'n self, name: &'n str) -> &'n str {
+        self.catalog_renames.get(name).map(String::as_str).unwrap_or(name)
+    }
+
+    /// `full_name` with its catalog renamed.
+    fn target_name(&self, full_name: &str) -> String {
+        match full_name.split_once('.') {
+            Some((catalog, rest)) => format!("{}.{}", self.target_catalog(catalog), rest),
+            None => self.target_catalog(full_name).to_owned()
+        }
+    }
+
+    fn rewrite_location_of(&self, location: Option<&str>) -> Option<String> {
+        let location = location?;
+        let rewritten = self.location_rewrites.iter()
+            .find_map(|(from, to)| location.strip_prefix(from.as_str()).map(|rest| format!("{}{}", to, rest)));
+        Some(rewritten.unwrap_or_else(|| location.to_owned()))
+    }
+
+    pub async fn run(&self) -> SyncReport {
+        let mut report = SyncReport::default();
+        let mut failed_prefix: Option<String> = None;
+
+        let mut nodes = self.source.walk();
+        while let Some(node) = nodes.next().await {
+            let node = match node {
+                Ok(node) => node,
+                Err(e) => {
+                    report.failed.push((self.target_name(&e.parent), *e.error));
+                    continue;
+                }
+            };
+            let key = match &node {
+                TreeNode::Catalog(c) => self.target_catalog(c.info.name().unwrap_or_default()).to_owned(),
+                TreeNode::Schema(s) => SchemasClient::full_name(self.target_catalog(&s.catalog_name), s.info.name().unwrap_or_default()),
+                TreeNode::Table(t) => TablesClient::full_name(self.target_catalog(&t.catalog_name), &t.schema_name, t.info.name().unwrap_or_default())
+            };
+            if failed_prefix.as_ref().is_some_and(|p| key.starts_with(p.as_str())) {
+                continue;
+            }
+
+            let outcome = match &node {
+                TreeNode::Catalog(c) => self.sync_catalog(&c.info).await,
+                TreeNode::Schema(s) => self.sync_schema(&s.catalog_name, &s.info).await,
+                TreeNode::Table(t) => self.sync_table(&t.catalog_name, &t.info).await
+            };
+            match outcome {
+                Ok(Outcome::Created) => report.created.push(key),
+                Ok(Outcome::Updated) => report.updated.push(key),
+                Ok(Outcome::Skipped) => report.skipped.push(key),
+                Ok(Outcome::Drifted) => report.drifted.push(key),
+                Err(e) => {
+                    if !matches!(node, TreeNode::Table(_)) {
+                        failed_prefix = Some(format!("{}.", key));
+                    }
+                    report.failed.push((key, e));
+                }
+            }
+        }
+        report
+    }
+
+    async fn sync_catalog(&self, info: &CatalogInfo) -> UCRSResult<Outcome> {
+        let catalogs = CatalogsClient::new(self.target);
+        let name = self.target_catalog(info.name().unwrap_or_default());
+        let desired = updatable(info.comment(), info.properties());
+        match catalogs.get(name).await {
+            Ok(existing) if updatable(existing.comment(), existing.properties()) == desired => Ok(Outcome::Skipped),
+            Ok(_) => {
+                // Only modeled fields: the source's unknown ones may not mean
+                // the same on the target.
+                let update = UpdateCatalogBuilder::default()
+                    .new_name(None)
+                    .comment(info.comment().map(str::to_owned))
+                    .properties(info.properties().cloned())
+                    .build()
+                    .expect("every UpdateCatalog field is set");
+                catalogs.update(name, update).await?;
+                Ok(Outcome::Updated)
+            },
+            Err(UCRSError::CatalogNotFound(_)) => {
+                catalogs.create(CreateCatalog::from(info).rename(name)).await?;
+                Ok(Outcome::Created)
+            },
+            Err(e) => Err(e)
+        }
+    }
+
+    async fn sync_schema(&self, catalog_name: &str, info: &SchemaInfo) -> UCRSResult<Outcome> {
+        let schemas = SchemasClient::new(self.target);
+        let create = CreateSchema::from(info).in_catalog(self.target_catalog(catalog_name));
+        let full_name = create.full_name();
+        let desired = updatable(info.comment(), info.properties());
+        match schemas.get(&full_name).await {
+            Ok(existing) if updatable(existing.comment(), existing.properties()) == desired => Ok(Outcome::Skipped),
+            Ok(_) => {
+                let update = UpdateSchemaBuilder::default()
+                    .name(info.name().unwrap_or_default().to_owned())
+                    .new_name(None)
+                    .comment(info.comment().map(str::to_owned))
+                    .properties(info.properties().cloned())
+                    .build()
+                    .expect("every UpdateSchema field is set");
+                schemas.update(&full_name, update).await?;
+                Ok(Outcome::Updated)
+            },
+            Err(UCRSError::SchemaNotFound(_)) => {
+                schemas.create(create).await?;
+                Ok(Outcome::Created)
+            },
+            Err(e) => Err(e)
+        }
+    }
+
+    async fn sync_table(&self, catalog_name: &str, info: &TableInfo) -> UCRSResult<Outcome> {
+        let tables = TablesClient::new(self.target);
+        let desired = TableDefinition::try_from(info)?
+            .in_catalog(self.target_catalog(catalog_name));
+        let location = self.rewrite_location_of(desired.storage_location());
+        let desired = desired.with_storage_location(location);
+        let full_name = desired.full_name();
+        match tables.get(&full_name).await {
+            Ok(existing) => {
+                let existing = TableDefinition::try_from(&existing);
+                if existing.as_ref().is_ok_and(|e| e.same_definition(&desired)) {
+                    return Ok(Outcome::Skipped);
+                }
+                if !desired.recreatable() || !existing.as_ref().is_ok_and(TableDefinition::recreatable) {
+                    return Ok(Outcome::Drifted);
+                }
+                tables.delete(&full_name).await?;
+                tables.create_definition(desired).await?;
+                Ok(Outcome::Updated)
+            },
+            Err(UCRSError::TableNotFound(_)) => {
+                tables.create_definition(desired).await?;
+                Ok(Outcome::Created)
+            },
+            Err(e) => Err(e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flavor::Flavor;
+    use serde_json::json;
+    use wiremock::matchers::{body_partial_json, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn table(catalog: &str, name: &str, location: &str) -> serde_json::Value {
+        json!({
+            "name": name,
+            "catalog_name": catalog,
+            "schema_name": "default",
+            "table_type": "EXTERNAL",
+            "data_source_format": "PARQUET",
+            "storage_location": location,
+            "columns": [{"name": "id", "type_name": "INT"}]
+        })
+    }
+
+    fn managed_table(catalog: &str, name: &str, comment: &str) -> serde_json::Value {
+        json!({
+            "name": name,
+            "catalog_name": catalog,
+            "schema_name": "default",
+            "table_type": "MANAGED",
+            "data_source_format": "DELTA",
+            "comment": comment,
+            "columns": [{"name": "id", "type_name": "INT"}]
+        })
+    }
+
+    async fn mock_source() -> MockServer {
+        let server = MockServer::start().await;
+        let respond = |body: serde_json::Value| ResponseTemplate::new(200).set_body_json(body);
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/catalogs"))
+            .respond_with(respond(json!({"catalogs": [{"name": "unity"}, {"name": "broken"}]})))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/schemas"))
+            .and(query_param("catalog_name", "unity"))
+            .respond_with(respond(json!({"schemas": [
+                {"name": "default", "catalog_name": "unity", "comment": "Default"},
+                {"name": "sales", "catalog_name": "unity"}
+            ]})))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/schemas"))
+            .and(query_param("catalog_name", "broken"))
+            .respond_with(respond(json!({"schemas": [{"name": "default", "catalog_name": "broken"}]})))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/tables"))
+            .and(query_param("schema_name", "sales"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/tables"))
+            .and(query_param("catalog_name", "unity"))
+            .respond_with(respond(json!({"tables": [
+                table("unity", "same", "s3://old/same"),
+                table("unity", "moved", "s3://old/moved"),
+                table("unity", "new", "s3://elsewhere/new"),
+                managed_table("unity", "scores", "Latest scores"),
+                json!({
+                    "name": "recent",
+                    "catalog_name": "unity",
+                    "schema_name": "default",
+                    "table_type": "VIEW",
+                    "view_definition": "SELECT * FROM new"
+                })
+            ]})))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/tables"))
+            .and(query_param("catalog_name", "broken"))
+            .respond_with(respond(json!({"tables": [table("broken", "t", "s3://old/t")]})))
+            .mount(&server)
+            .await;
+        server
+    }
+
+    async fn mock_target() -> MockServer {
+        let server = MockServer::start().await;
+        let respond = |body: serde_json::Value| ResponseTemplate::new(200).set_body_json(body);
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/catalogs/prod"))
+            .respond_with(respond(json!({"name": "prod"})))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/catalogs/broken"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/schemas/prod.default"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/2.1/unity-catalog/schemas"))
+            .and(body_partial_json(json!({"name": "default", "catalog_name": "prod", "comment": "Default"})))
+            .respond_with(respond(json!({"name": "default"})))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/schemas/prod.sales"))
+            .respond_with(respond(json!({"name": "sales", "catalog_name": "prod"})))
+            .mount(&server)
+            .await;
+        let mut same = table("prod", "same", "s3://new/same");
+        same["properties"] = json!({});
+        same["columns"] = json!([{"name": "id", "type_name": "INT", "type_json": "{}", "position": 0, "nullable": true}]);
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/tables/prod.default.same"))
+            .respond_with(respond(same))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/tables/prod.default.scores"))
+            .respond_with(respond(managed_table("prod", "scores", "Old scores")))
+            .mount(&server)
+            .await;
+        Mock::given(method("DELETE"))
+            .and(path("/api/2.1/unity-catalog/tables/prod.default.scores"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/tables/prod.default.moved"))
+            .respond_with(respond(table("prod", "moved", "s3://old/moved")))
+            .mount(&server)
+            .await;
+        Mock::given(method("DELETE"))
+            .and(path("/api/2.1/unity-catalog/tables/prod.default.moved"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("200 OK"))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/tables/prod.default.new"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/tables/prod.default.recent"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/2.1/unity-catalog/tables"))
+            .and(body_partial_json(json!({"name": "recent", "catalog_name": "prod", "table_type": "VIEW", "view_definition": "SELECT * FROM new"})))
+            .respond_with(respond(json!({"name": "recent"})))
+            .expect(1)
+            .mount(&server)
+            .await;
+        for (name, location) in [("moved", "s3://new/moved"), ("new", "s3://elsewhere/new")] {
+            Mock::given(method("POST"))
+                .and(path("/api/2.1/unity-catalog/tables"))
+                .and(body_partial_json(json!({"name": name, "catalog_name": "prod", "storage_location": location})))
+                .respond_with(respond(table("prod", name, location)))
+                .expect(1)
+                .mount(&server)
+                .await;
+        }
+        server
+    }
+
+    #[tokio::test]
+    async fn test_sync() -> UCRSResult<()> {
+        let source = mock_source().await;
+        let target = mock_target().await;
+        let source_rc = RequestClient::new(&source.uri(), true)?;
+        let target_rc = RequestClient::new(&target.uri(), true)?;
+
+        let report = MetastoreSync::new(Walker::new(&source_rc), &target_rc)
+            .rename_catalog("unity", "prod")
+            .rewrite_location("s3://old/", "s3://new/")
+            .run()
+            .await;
+
+        assert_eq!(report.created, vec!["prod.default", "prod.default.new", "prod.default.recent"]);
+        assert_eq!(report.updated, vec!["prod.default.moved"]);
+        assert_eq!(report.skipped, vec!["prod", "prod.default.same", "prod.sales"]);
+        assert_eq!(report.drifted, vec!["prod.default.scores"]);
+        assert_eq!(report.failed.iter().map(|(k, _)| k.as_str()).collect::<Vec<_>>(), vec!["prod.sales", "broken"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rerun_only_updates_what_an_update_can_change() -> UCRSResult<()> {
+        let source = MockServer::start().await;
+        let target = MockServer::start().await;
+        let respond = |body: serde_json::Value| ResponseTemplate::new(200).set_body_json(body);
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/catalogs"))
+            .respond_with(respond(json!({"catalogs": [
+                {"name": "same", "comment": "Same", "catalog_type": "MANAGED_CATALOG", "isolation_mode": "OPEN"},
+                {"name": "changed", "comment": "New", "properties": {"team": "data"}, "isolation_mode": "OPEN"}
+            ]})))
+            .mount(&source)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/schemas"))
+            .and(query_param("catalog_name", "same"))
+            .respond_with(respond(json!({"schemas": [{"name": "default", "catalog_name": "same"}]})))
+            .mount(&source)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/schemas"))
+            .and(query_param("catalog_name", "changed"))
+            .respond_with(respond(json!({"schemas": []})))
+            .mount(&source)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/tables"))
+            .respond_with(respond(json!({"tables": []})))
+            .mount(&source)
+            .await;
+
+        // The target echoes empty properties and differs only in fields an
+        // update can't change.
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/catalogs/same"))
+            .respond_with(respond(json!({"name": "same", "comment": "Same", "properties": {}, "isolation_mode": "ISOLATED"})))
+            .mount(&target)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/schemas/same.default"))
+            .respond_with(respond(json!({"name": "default", "catalog_name": "same", "properties": {}})))
+            .mount(&target)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/catalogs/changed"))
+            .respond_with(respond(json!({"name": "changed", "comment": "Old", "properties": {"team": "data"}})))
+            .mount(&target)
+            .await;
+        Mock::given(method("PATCH"))
+            .and(path("/api/2.1/unity-catalog/catalogs/changed"))
+            .and(body_partial_json(json!({"comment": "New", "properties": {"team": "data"}})))
+            .respond_with(respond(json!({"name": "changed"})))
+            .expect(1)
+            .mount(&target)
+            .await;
+
+        let source_rc = RequestClient::new(&source.uri(), true)?;
+        let target_rc = RequestClient::new(&target.uri(), true)?.with_flavor(Flavor::Databricks);
+        let report = MetastoreSync::new(Walker::new(&source_rc), &target_rc).run().await;
+
+        assert_eq!(report.skipped, vec!["same", "same.default"]);
+        assert_eq!(report.updated, vec!["changed"]);
+        assert!(report.failed.is_empty());
+        let patch = target.received_requests().await.unwrap()
+            .into_iter()
+            .find(|r| r.method.as_str() == "PATCH")
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&patch.body).unwrap();
+        assert!(body.get("isolation_mode").is_none());
+        Ok(())
+    }
+}
@@ -0,0 +1,424 @@
+use crate::errors::{UCRSError, UCRSResult};
+use crate::oss::api::catalogs::{CatalogInfo, CatalogsClient};
+use crate::oss::api::schemas::{SchemaInfo, SchemasClient};
+use crate::oss::api::tables::{TableInfo, TablesClient};
+use crate::request::RequestClient;
+use futures_util::stream::{self, BoxStream, StreamExt, TryStreamExt};
+use globset::{Glob, GlobMatcher};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug)]
+pub struct CatalogNode {
+    pub info: CatalogInfo
+}
+
+#[derive(Debug)]
+pub struct SchemaNode {
+    pub catalog_name: String,
+    pub info: SchemaInfo
+}
+
+#[derive(Debug)]
+pub struct TableNode {
+    pub catalog_name: String,
+    pub schema_name: String,
+    pub info: Box<TableInfo>
+}
+
+/// One object visited by `Walker::walk`. Parents always come before their
+/// children.
+#[derive(Debug)]
+pub enum TreeNode {
+    Catalog(CatalogNode),
+    Schema(SchemaNode),
+    Table(TableNode)
+}
+
+/// A listing that failed during a walk. `parent` is the full name of the
+/// catalog or schema whose children could not be listed, or empty when the
+/// catalogs themselves could not be.
+#[derive(Debug)]
+pub struct WalkError {
+    pub parent: String,
+    pub error: Box<UCRSError>
+}
+
+impl From<WalkError> for UCRSError {
+    fn from(e: WalkError) -> Self {
+        *e.error
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct CatalogTree {
+    pub catalogs: Vec<CatalogEntry>
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CatalogEntry {
+    pub info: CatalogInfo,
+    pub schemas: Vec<SchemaEntry>
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SchemaEntry {
+    pub info: SchemaInfo,
+    pub tables: Vec<TableInfo>
+}
+
+/// A dotted glob such as `unity.*` or `*.*.tmp_*`, matched one name part at a time.
+#[derive(Debug, Clone)]
+struct Pattern {
+    parts: Vec<GlobMatcher>
+}
+
+impl Pattern {
+    fn new(pattern: &str) -> UCRSResult<Self> {
+        let parts = pattern.split('.')
+            .map(|part| Glob::new(part)
+                .map(|g| g.compile_matcher())
+                .map_err(|e| UCRSError::InvalidPattern(pattern.to_owned(), e.to_string())))
+            .collect::<UCRSResult<Vec<_>>>()?;
+        if parts.len() > 3 {
+            return Err(UCRSError::InvalidPattern(pattern.to_owned(), "more than three name parts".to_owned()));
+        }
+        Ok(Self { parts })
+    }
+
+    /// Whether the pattern's leading parts match as much of `names` as it covers.
+    fn matches_prefix(&self, names: &[&str]) -> bool {
+        self.parts.iter().zip(names).all(|(p, n)| p.is_match(n))
+    }
+
+    /// Whether the pattern matches `names` in full, so everything below is covered too.
+    fn covers(&self, names: &[&str]) -> bool {
+        self.parts.len() <= names.len() && self.matches_prefix(names)
+    }
+}
+
+/// Depth-first traversal of catalogs, schemas and tables.
+///
+/// `include` patterns select which objects are visited; a catalog or schema is
+/// visited when it could contain a match. `exclude` patterns prune an object
+/// and everything below it. Listing errors are yielded in place and the walk
+/// carries on with the remaining objects.
+pub struct Walker<'a> {
+    client: &'a RequestClient,
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+    concurrency: usize
+}
+
+impl<'a> Walker<'a> {
+    pub fn new(client: &'a RequestClient) -> Self {
+        Self {
+            client,
+            include: vec![],
+            exclude: vec![],
+            concurrency: 1
+        }
+    }
+
+    pub fn include(mut self, pattern: &str) -> UCRSResult<Self> {
+        self.include.push(Pattern::new(pattern)?);
+        Ok(self)
+    }
+
+    pub fn exclude(mut self, pattern: &str) -> UCRSResult<Self> {
+        self.exclude.push(Pattern::new(pattern)?);
+        Ok(self)
+    }
+
+    /// Number of table listings fetched at once. Catalogs are walked one at a
+    /// time, so this bounds the requests the walk has in flight.
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    fn visits(&self, names: &[&str]) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|p| p.matches_prefix(names)))
+            && !self.exclude.iter().any(|p| p.covers(names))
+    }
+
+    pub fn walk(&self) -> BoxStream<'_, Result<TreeNode, WalkError>> {
+        let client = self.client;
+        stream::once(async move { CatalogsClient::new(client).list_all().await })
+            .flat_map(move |catalogs| match catalogs {
+                Ok(catalogs) => stream::iter(catalogs)
+                    .filter(move |c| std::future::ready(self.visits(&[c.name().unwrap_or_default()])))
+                    .flat_map(move |c| self.catalog_subtree(c))
+                    .boxed(),
+                Err(error) => stream::iter(vec![Err(WalkError { parent: String::new(), error: Box::new(error) })]).boxed()
+            })
+            .boxed()
+    }
+
+    /// The catalog, then each of its schema subtrees as soon as it is listed.
+    fn catalog_subtree(&self, catalog: CatalogInfo) -> BoxStream<'_, Result<TreeNode, WalkError>> {
+        let catalog_name = catalog.name().unwrap_or_default().to_owned();
+        let schemas = stream::once(async move {
+            let schemas = SchemasClient::new(self.client).list_all(&catalog_name).await;
+            (catalog_name, schemas)
+        })
+            .flat_map(move |(catalog_name, schemas)| match schemas {
+                Ok(schemas) => {
+                    let visited = catalog_name.clone();
+                    stream::iter(schemas)
+                        .filter(move |s| std::future::ready(self.visits(&[&visited, s.name().unwrap_or_default()])))
+                        .map(move |s| self.schema_subtree(catalog_name.clone(), s))
+                        .buffered(self.concurrency)
+                        .flat_map(stream::iter)
+                        .boxed()
+                },
+                Err(error) => stream::iter(vec![Err(WalkError { parent: catalog_name, error: Box::new(error) })]).boxed()
+            });
+        stream::once(std::future::ready(Ok(TreeNode::Catalog(CatalogNode { info: catalog }))))
+            .chain(schemas)
+            .boxed()
+    }
+
+    async fn schema_subtree(&self, catalog_name: String, schema: SchemaInfo) -> Vec<Result<TreeNode, WalkError>> {
+        let schema_name = schema.name().unwrap_or_default().to_owned();
+        let mut nodes = vec![Ok(TreeNode::Schema(SchemaNode {
+            catalog_name: catalog_name.clone(),
+            info: schema
+        }))];
+        match TablesClient::new(self.client).list_all(&catalog_name, &schema_name).await {
+            Ok(tables) => nodes.extend(tables.into_iter()
+                .filter(|t| self.visits(&[&catalog_name, &schema_name, t.name().unwrap_or_default()]))
+                .map(|t| Ok(TreeNode::Table(TableNode {
+                    catalog_name: catalog_name.clone(),
+                    schema_name: schema_name.clone(),
+                    info: Box::new(t)
+                })))),
+            Err(error) => nodes.push(Err(WalkError {
+                parent: SchemasClient::full_name(&catalog_name, &schema_name),
+                error: Box::new(error)
+            }))
+        }
+        nodes
+    }
+
+    /// Walks the whole tree into memory, failing on the first listing error.
+    pub async fn snapshot(&self) -> UCRSResult<CatalogTree> {
+        let mut tree = CatalogTree::default();
+        let mut nodes = self.walk();
+        while let Some(node) = nodes.try_next().await? {
+            match node {
+                TreeNode::Catalog(c) => tree.catalogs.push(CatalogEntry {
+                    info: c.info,
+                    schemas: vec![]
+                }),
+                TreeNode::Schema(s) => {
+                    if let Some(catalog) = tree.catalogs.last_mut() {
+                        catalog.schemas.push(SchemaEntry {
+                            info: s.info,
+                            tables: vec![]
+                        });
+                    }
+                },
+                TreeNode::Table(t) => {
+                    if let Some(schema) = tree.catalogs.last_mut().and_then(|c| c.schemas.last_mut()) {
+                        schema.tables.push(*t.info);
+                    }
+                }
+            }
+        }
+        Ok(tree)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::middleware::{Middleware, RequestContext, ResponseContext};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    async fn mock_metastore() -> MockServer {
+        let server = MockServer::start().await;
+        let respond = |body: serde_json::Value| ResponseTemplate::new(200).set_body_json(body);
+
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/catalogs"))
+            .respond_with(respond(serde_json::json!({
+                "catalogs": [{"name": "unity"}, {"name": "sandbox"}]
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/schemas"))
+            .and(query_param("catalog_name", "unity"))
+            .respond_with(respond(serde_json::json!({
+                "schemas": [{"name": "default"}, {"name": "sales"}]
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/schemas"))
+            .and(query_param("catalog_name", "sandbox"))
+            .respond_with(respond(serde_json::json!({
+                "schemas": [{"name": "scratch"}]
+            })))
+            .mount(&server)
+            .await;
+        for (catalog, schema, tables, next_page_token) in [
+            ("unity", "default", vec!["marksheet", "numbers"], None),
+            ("unity", "sales", vec!["orders"], Some("2")),
+            ("sandbox", "scratch", vec!["tmp_1"], None)
+        ] {
+            Mock::given(method("GET"))
+                .and(path("/api/2.1/unity-catalog/tables"))
+                .and(query_param("catalog_name", catalog))
+                .and(query_param("schema_name", schema))
+                .respond_with(respond(serde_json::json!({
+                    "tables": tables.iter().map(|t| serde_json::json!({"name": t})).collect::<Vec<_>>(),
+                    "next_page_token": next_page_token
+                })))
+                .up_to_n_times(1)
+                .mount(&server)
+                .await;
+        }
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/tables"))
+            .and(query_param("schema_name", "sales"))
+            .and(query_param("page_token", "2"))
+            .respond_with(respond(serde_json::json!({
+                "tables": [{"name": "customers"}]
+            })))
+            .mount(&server)
+            .await;
+        server
+    }
+
+    fn describe(node: &TreeNode) -> String {
+        match node {
+            TreeNode::Catalog(c) => c.info.name().unwrap().to_owned(),
+            TreeNode::Schema(s) => format!("{}.{}", s.catalog_name, s.info.name().unwrap()),
+            TreeNode::Table(t) => format!("{}.{}.{}", t.catalog_name, t.schema_name, t.info.name().unwrap())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_walk_depth_first() -> UCRSResult<()> {
+        let server = mock_metastore().await;
+        let rc = RequestClient::new(&server.uri(), true)?;
+
+        let nodes = Walker::new(&rc)
+            .concurrency(4)
+            .walk()
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        assert_eq!(nodes.iter().map(describe).collect::<Vec<_>>(), vec![
+            "unity",
+            "unity.default",
+            "unity.default.marksheet",
+            "unity.default.numbers",
+            "unity.sales",
+            "unity.sales.orders",
+            "unity.sales.customers",
+            "sandbox",
+            "sandbox.scratch",
+            "sandbox.scratch.tmp_1"
+        ]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_with_filters() -> UCRSResult<()> {
+        let server = mock_metastore().await;
+        let rc = RequestClient::new(&server.uri(), true)?;
+
+        let tree = Walker::new(&rc)
+            .include("unity.*.*")?
+            .include("sandbox")?
+            .exclude("unity.sales.cust*")?
+            .exclude("*.scratch")?
+            .snapshot()
+            .await?;
+
+        let summary = tree.catalogs.iter()
+            .map(|c| (
+                c.info.name().unwrap(),
+                c.schemas.iter()
+                    .map(|s| (s.info.name().unwrap(), s.tables.iter().map(|t| t.name().unwrap()).collect::<Vec<_>>()))
+                    .collect::<Vec<_>>()
+            ))
+            .collect::<Vec<_>>();
+        assert_eq!(summary, vec![
+            ("unity", vec![("default", vec!["marksheet", "numbers"]), ("sales", vec!["orders"])]),
+            ("sandbox", vec![])
+        ]);
+        Ok(())
+    }
+
+    #[derive(Default)]
+    struct InFlight {
+        now: AtomicUsize,
+        peak: AtomicUsize
+    }
+
+    impl Middleware for InFlight {
+        fn on_request(&self, _request: &mut reqwest::Request) -> UCRSResult<()> {
+            let now = self.now.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(now, Ordering::SeqCst);
+            Ok(())
+        }
+
+        fn on_response(&self, _request: &RequestContext, _response: &ResponseContext) {
+            self.now.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_walk_streams_within_concurrency() -> UCRSResult<()> {
+        let server = MockServer::start().await;
+        let respond = |body: serde_json::Value| ResponseTemplate::new(200)
+            .set_body_json(body)
+            .set_delay(Duration::from_millis(20));
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/catalogs"))
+            .respond_with(respond(serde_json::json!({"catalogs": [{"name": "a"}, {"name": "b"}]})))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/schemas"))
+            .respond_with(respond(serde_json::json!({
+                "schemas": (0..6).map(|i| serde_json::json!({"name": format!("s{}", i)})).collect::<Vec<_>>()
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/tables"))
+            .respond_with(respond(serde_json::json!({"tables": [{"name": "t"}]})))
+            .mount(&server)
+            .await;
+
+        let in_flight = Arc::new(InFlight::default());
+        let rc = RequestClient::new(&server.uri(), true)?
+            .with_middleware(in_flight.clone());
+        let walker = Walker::new(&rc).concurrency(2);
+        let mut nodes = walker.walk();
+
+        // The first catalog comes out before anything below it is listed.
+        let first = nodes.try_next().await?.unwrap();
+        assert_eq!(describe(&first), "a");
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+
+        let rest = nodes.try_collect::<Vec<_>>().await?;
+        assert_eq!(rest.len(), 6 * 2 + 1 + 6 * 2);
+        assert_eq!(in_flight.peak.load(Ordering::SeqCst), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_pattern() {
+        assert!(matches!(Pattern::new("a.b.c.d"), Err(UCRSError::InvalidPattern(_, _))));
+        assert!(matches!(Pattern::new("unity.[a"), Err(UCRSError::InvalidPattern(_, _))));
+    }
+}
@@ -0,0 +1,439 @@
+use crate::delta::{load_from_store, store_url};
+use crate::errors::{UCRSError, UCRSResult};
+use crate::oss::api::catalogs::CatalogsClient;
+use crate::oss::api::schemas::SchemasClient;
+use crate::oss::api::tables::{ColumnInfo, ColumnTypeName, DataSourceFormat, ListTablesOptions, TableInfo, TablesClient};
+use crate::oss::api::temporary_credentials::TableOperation;
+use crate::refresh::CredentialCache;
+use crate::store::table_location;
+use async_trait::async_trait;
+use datafusion::arrow::datatypes::{DataType, SchemaRef, TimeUnit};
+use datafusion::catalog::{CatalogProvider, CatalogProviderList, SchemaProvider, Session, TableProvider};
+use datafusion::datasource::file_format::parquet::ParquetFormat;
+use datafusion::datasource::listing::{ListingOptions, ListingTable, ListingTableConfig, ListingTableUrl};
+use datafusion::error::{DataFusionError, Result};
+use datafusion::logical_expr::{Expr, TableProviderFilterPushDown, TableType};
+use datafusion::physical_plan::ExecutionPlan;
+use datafusion::prelude::SessionContext;
+use deltalake::DeltaVersion;
+use object_store::ObjectStore;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use url::Url;
+
+const DEFAULT_TABLE_TTL: Duration = Duration::from_secs(5 * 60);
+
+fn external(e: UCRSError) -> DataFusionError {
+    DataFusionError::External(Box::new(e))
+}
+
+/// Exposes every catalog on the server to DataFusion. Catalog, schema and
+/// table names are listed once, up front, since DataFusion asks for them
+/// synchronously; resolved tables are cached for `table_ttl`. Catalogs
+/// whose tables or schemas can't be listed are skipped with a warning.
+#[derive(Debug)]
+pub struct UnityCatalogList {
+    catalogs: BTreeMap<String, Arc<UnityCatalogProvider>>
+}
+
+impl UnityCatalogList {
+    pub async fn try_new(credentials: Arc<CredentialCache>) -> UCRSResult<Self> {
+        Self::try_new_with_ttl(credentials, DEFAULT_TABLE_TTL).await
+    }
+
+    pub async fn try_new_with_ttl(credentials: Arc<CredentialCache>, table_ttl: Duration) -> UCRSResult<Self> {
+        let mut catalogs = BTreeMap::new();
+        for catalog in CatalogsClient::new(credentials.client()).list_all().await? {
+            let Some(name) = catalog.name() else { continue };
+            match UnityCatalogProvider::try_new_with_ttl(Arc::clone(&credentials), name, table_ttl).await {
+                Ok(provider) => { catalogs.insert(name.to_owned(), Arc::new(provider)); }
+                Err(e) => tracing::warn!("Skipping catalog {}: {}", name, e)
+            }
+        }
+        Ok(Self { catalogs })
+    }
+}
+
+impl CatalogProviderList for UnityCatalogList {
+    fn register_catalog(&self, _name: String, _catalog: Arc<dyn CatalogProvider>) -> Option<Arc<dyn CatalogProvider>> {
+        None
+    }
+
+    fn catalog_names(&self) -> Vec<String> {
+        self.catalogs.keys().cloned().collect()
+    }
+
+    fn catalog(&self, name: &str) -> Option<Arc<dyn CatalogProvider>> {
+        self.catalogs.get(name).map(|c| Arc::clone(c) as _)
+    }
+}
+
+#[derive(Debug)]
+pub struct UnityCatalogProvider {
+    schemas: BTreeMap<String, Arc<UnitySchemaProvider>>
+}
+
+impl UnityCatalogProvider {
+    pub async fn try_new(credentials: Arc<CredentialCache>, catalog_name: &str) -> UCRSResult<Self> {
+        Self::try_new_with_ttl(credentials, catalog_name, DEFAULT_TABLE_TTL).await
+    }
+
+    pub async fn try_new_with_ttl(credentials: Arc<CredentialCache>, catalog_name: &str, table_ttl: Duration) -> UCRSResult<Self> {
+        let client = credentials.client();
+        let mut table_names: HashMap<String, BTreeSet<String>> = HashMap::new();
+        let summaries = TablesClient::new(client)
+            .list_summaries(catalog_name, None, &ListTablesOptions::default())
+            .await?;
+        for summary in summaries {
+            if let Some((schema, table)) = summary.full_name()
+                .strip_prefix(catalog_name)
+                .and_then(|rest| rest.strip_prefix('.'))
+                .and_then(|rest| rest.split_once('.')) {
+                table_names.entry(schema.to_owned()).or_default().insert(table.to_owned());
+            }
+        }
+
+        let mut schemas = BTreeMap::new();
+        for schema in SchemasClient::new(client).list_all(catalog_name).await? {
+            let Some(name) = schema.name() else { continue };
+            schemas.insert(name.to_owned(), Arc::new(UnitySchemaProvider {
+                credentials: Arc::clone(&credentials),
+                catalog_name: catalog_name.to_owned(),
+                schema_name: name.to_owned(),
+                table_names: Mutex::new(table_names.remove(name).unwrap_or_default()),
+                table_ttl,
+                tables: Mutex::default()
+            }));
+        }
+        Ok(Self { schemas })
+    }
+}
+
+impl CatalogProvider for UnityCatalogProvider {
+    fn schema_names(&self) -> Vec<String> {
+        self.schemas.keys().cloned().collect()
+    }
+
+    fn schema(&self, name: &str) -> Option<Arc<dyn SchemaProvider>> {
+        self.schemas.get(name).map(|s| Arc::clone(s) as _)
+    }
+}
+
+#[derive(Debug)]
+pub struct UnitySchemaProvider {
+    credentials: Arc<CredentialCache>,
+    catalog_name: String,
+    schema_name: String,
+    table_names: Mutex<BTreeSet<String>>,
+    table_ttl: Duration,
+    tables: Mutex<HashMap<String, ResolvedTable>>
+}
+
+type ResolvedTable = (Instant, Arc<dyn TableProvider>);
+
+#[async_trait]
+impl SchemaProvider for UnitySchemaProvider {
+    fn table_names(&self) -> Vec<String> {
+        self.table_names.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Tables created after the names were listed can still be resolved,
+    /// and are listed from then on; dropped ones stop being listed.
+    async fn table(&self, name: &str) -> Result<Option<Arc<dyn TableProvider>>> {
+        if let Some((resolved_at, table)) = self.tables.lock().unwrap().get(name) {
+            if resolved_at.elapsed() < self.table_ttl {
+                return Ok(Some(Arc::clone(table)));
+            }
+        }
+
+        let full_name = TablesClient::full_name(&self.catalog_name, &self.schema_name, name);
+        let info = match TablesClient::new(self.credentials.client()).get(&full_name).await {
+            Ok(info) => info,
+            Err(UCRSError::TableNotFound(_)) => {
+                self.table_names.lock().unwrap().remove(name);
+                self.tables.lock().unwrap().remove(name);
+                return Ok(None)
+            }
+            Err(e) => return Err(external(e))
+        };
+        let table = resolve_table(&self.credentials, &full_name, &info).await?;
+        self.table_names.lock().unwrap().insert(name.to_owned());
+        self.tables.lock().unwrap().insert(name.to_owned(), (Instant::now(), Arc::clone(&table)));
+        Ok(Some(table))
+    }
+
+    fn table_exist(&self, name: &str) -> bool {
+        self.table_names.lock().unwrap().contains(name)
+    }
+}
+
+async fn resolve_table(credentials: &Arc<CredentialCache>, full_name: &str, info: &TableInfo) -> Result<Arc<dyn TableProvider>> {
+    let (table_id, _) = table_location(info).map_err(external)?;
+    let url = store_url(table_id).map_err(external)?;
+    let store = credentials.table_store(info, TableOperation::READ).await.map_err(external)?;
+    let inner = match info.data_source_format() {
+        Some(DataSourceFormat::DELTA) => delta_table(Arc::clone(&store), &url).await?,
+        Some(DataSourceFormat::PARQUET) => parquet_table(Arc::clone(&store), &url, info).await?,
+        format => return Err(DataFusionError::NotImplemented(
+            format!("{} has format {:?}; only DELTA and PARQUET tables can be read", full_name, format)
+        ))
+    };
+    Ok(Arc::new(UnityTable { inner, url, store }))
+}
+
+async fn parquet_table(store: Arc<dyn ObjectStore>, url: &Url, info: &TableInfo) -> Result<Arc<dyn TableProvider>> {
+    let mut partitions = info.columns().iter()
+        .filter_map(|c| Some((c.partition_index()?, c.name()?.to_owned(), partition_type(c))))
+        .collect::<Vec<_>>();
+    partitions.sort_by_key(|(index, _, _)| *index);
+    let options = ListingOptions::new(Arc::new(ParquetFormat::default()))
+        .with_file_extension(".parquet")
+        .with_table_partition_cols(partitions.into_iter().map(|(_, name, data_type)| (name, data_type)).collect());
+
+    let ctx = SessionContext::new();
+    ctx.register_object_store(url, store);
+    let config = ListingTableConfig::new(ListingTableUrl::parse(url)?)
+        .with_listing_options(options)
+        .infer_schema(&ctx.state())
+        .await?;
+    Ok(Arc::new(ListingTable::try_new(config)?))
+}
+
+/// The Arrow type partition values are parsed as. Types that can't be
+/// spelled in a directory name are read as strings.
+fn partition_type(column: &ColumnInfo) -> DataType {
+    match column.type_name() {
+        Some(ColumnTypeName::BOOLEAN) => DataType::Boolean,
+        Some(ColumnTypeName::BYTE) => DataType::Int8,
+        Some(ColumnTypeName::SHORT) => DataType::Int16,
+        Some(ColumnTypeName::INT) => DataType::Int32,
+        Some(ColumnTypeName::LONG) => DataType::Int64,
+        Some(ColumnTypeName::FLOAT) => DataType::Float32,
+        Some(ColumnTypeName::DOUBLE) => DataType::Float64,
+        Some(ColumnTypeName::DATE) => DataType::Date32,
+        Some(ColumnTypeName::TIMESTAMP) => DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+        Some(ColumnTypeName::TIMESTAMP_NTZ) => DataType::Timestamp(TimeUnit::Microsecond, None),
+        Some(ColumnTypeName::DECIMAL) => DataType::Decimal128(
+            column.type_precision().unwrap_or(10) as u8,
+            column.type_scale().unwrap_or(0) as i8
+        ),
+        _ => DataType::Utf8
+    }
+}
+
+async fn delta_table(store: Arc<dyn ObjectStore>, url: &Url) -> Result<Arc<dyn TableProvider>> {
+    let table = load_from_store(store, url, DeltaVersion::Newest)
+        .await
+        .map_err(|e| DataFusionError::External(Box::new(e)))?;
+    table.table_provider().await
+}
+
+/// Registers the table's store with the session running the query before
+/// handing the scan to the Delta or Parquet provider.
+#[derive(Debug)]
+struct UnityTable {
+    inner: Arc<dyn TableProvider>,
+    url: Url,
+    store: Arc<dyn ObjectStore>
+}
+
+#[async_trait]
+impl TableProvider for UnityTable {
+    fn schema(&self) -> SchemaRef {
+        self.inner.schema()
+    }
+
+    fn table_type(&self) -> TableType {
+        self.inner.table_type()
+    }
+
+    fn supports_filters_pushdown(&self, filters: &[&Expr]) -> Result<Vec<TableProviderFilterPushDown>> {
+        self.inner.supports_filters_pushdown(filters)
+    }
+
+    async fn scan(&self, state: &dyn Session, projection: Option<&Vec<usize>>, filters: &[Expr], limit: Option<usize>) -> Result<Arc<dyn ExecutionPlan>> {
+        state.runtime_env().register_object_store(&self.url, Arc::clone(&self.store));
+        self.inner.scan(state, projection, filters, limit).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::request::RequestClient;
+    use crate::testing::test_utils::{mount_responses, temp_dir};
+    use datafusion::dataframe::DataFrameWriteOptions;
+    use deltalake::DeltaTable;
+    use serde_json::{json, Value};
+    use std::path::Path;
+    use tracing_test::traced_test;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const ORDERS: &str = "/api/2.1/unity-catalog/tables/unity.default.orders";
+
+    fn parquet_info(name: &str, location: &Url) -> Value {
+        json!({
+            "name": name,
+            "table_id": format!("t-{}", name),
+            "data_source_format": "PARQUET",
+            "storage_location": location.as_str(),
+            "columns": [
+                {"name": "id", "type_name": "LONG"},
+                {"name": "region", "type_name": "STRING", "partition_index": 0},
+                {"name": "year", "type_name": "INT", "partition_index": 1}
+            ]
+        })
+    }
+
+    async fn mock_metastore(parquet: &Url, delta: &Url) -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/2.1/unity-catalog/schemas"))
+            .and(query_param("catalog_name", "restricted"))
+            .respond_with(ResponseTemplate::new(403))
+            .with_priority(1)
+            .mount(&server)
+            .await;
+        let responses = [
+            ("GET", "/api/2.1/unity-catalog/catalogs", json!({"catalogs": [{"name": "restricted"}, {"name": "unity"}]})),
+            ("GET", "/api/2.1/unity-catalog/schemas", json!({"schemas": [{"name": "default"}]})),
+            ("GET", "/api/2.1/unity-catalog/tables", json!({"tables": [
+                {"name": "orders", "table_type": "EXTERNAL"},
+                {"name": "ledger", "table_type": "EXTERNAL"},
+                {"name": "events", "table_type": "EXTERNAL"},
+                {"name": "dropped", "table_type": "EXTERNAL"}
+            ]})),
+            ("GET", ORDERS, parquet_info("orders", parquet)),
+            ("GET", "/api/2.1/unity-catalog/tables/unity.default.late", parquet_info("late", parquet)),
+            ("GET", "/api/2.1/unity-catalog/tables/unity.default.ledger", json!({
+                "name": "ledger",
+                "table_id": "t-ledger",
+                "data_source_format": "DELTA",
+                "storage_location": delta.as_str()
+            })),
+            ("GET", "/api/2.1/unity-catalog/tables/unity.default.events", json!({
+                "name": "events",
+                "table_id": "t-events",
+                "data_source_format": "AVRO",
+                "storage_location": parquet.as_str()
+            })),
+            ("POST", "/api/2.1/unity-catalog/temporary-table-credentials", json!({}))
+        ];
+        mount_responses(&server, responses).await;
+        server
+    }
+
+    async fn write_parquet(dir: &Path) -> Url {
+        for region in ["eu", "us"] {
+            let partition = dir.join(format!("region={}/year=2024", region));
+            std::fs::create_dir_all(&partition).unwrap();
+            SessionContext::new().sql("SELECT * FROM (VALUES (1), (2)) AS t(id)").await.unwrap()
+                .write_parquet(partition.join("part-0.parquet").to_str().unwrap(), DataFrameWriteOptions::new().with_single_file_output(true), None)
+                .await
+                .unwrap();
+        }
+        Url::from_directory_path(dir).unwrap()
+    }
+
+    async fn write_delta(dir: &Path) -> Url {
+        std::fs::create_dir_all(dir).unwrap();
+        let location = Url::from_directory_path(dir).unwrap();
+        DeltaTable::try_from_url(location.clone()).await.unwrap()
+            .create()
+            .with_column("id", deltalake::kernel::DataType::LONG, false, None)
+            .await
+            .unwrap();
+        location
+    }
+
+    async fn query(ctx: &SessionContext, sql: &str) -> String {
+        let batches = ctx.sql(sql).await.unwrap().collect().await.unwrap();
+        datafusion::arrow::util::pretty::pretty_format_batches(&batches).unwrap().to_string()
+    }
+
+    fn table_gets(requests: &[wiremock::Request], route: &str) -> usize {
+        requests.iter().filter(|r| r.method.as_str() == "GET" && r.url.path() == route).count()
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn test_query_tables() -> UCRSResult<()> {
+        let dir = temp_dir();
+        let parquet = write_parquet(&dir.path().join("orders")).await;
+        let delta = write_delta(&dir.path().join("ledger")).await;
+        let server = mock_metastore(&parquet, &delta).await;
+
+        let rc = Arc::new(RequestClient::new(&server.uri(), true)?);
+        let catalogs = UnityCatalogList::try_new(Arc::new(CredentialCache::new(rc))).await?;
+        assert_eq!(catalogs.catalog_names(), vec!["unity"]);
+        assert!(logs_contain("Skipping catalog restricted"));
+        let ctx = SessionContext::new();
+        ctx.register_catalog_list(Arc::new(catalogs));
+        let schema = ctx.catalog("unity").unwrap().schema("default").unwrap();
+        assert_eq!(schema.table_names(), vec!["dropped", "events", "ledger", "orders"]);
+        assert!(schema.table_exist("orders") && !schema.table_exist("missing"));
+
+        let formatted = query(&ctx, "SELECT region, count(*) AS n FROM unity.default.orders WHERE year = 2024 GROUP BY region ORDER BY region").await;
+        assert!(formatted.contains("| eu     | 2 |") && formatted.contains("| us     | 2 |"), "{}", formatted);
+        let orders = schema.table("orders").await.unwrap().unwrap().schema();
+        assert_eq!(orders.field_with_name("region").unwrap().data_type(), &DataType::Utf8);
+        assert_eq!(orders.field_with_name("year").unwrap().data_type(), &DataType::Int32);
+
+        let formatted = query(&ctx, "SELECT count(*) AS n FROM unity.default.ledger").await;
+        assert!(formatted.contains("| 0 |"), "{}", formatted);
+        let ledger = schema.table("ledger").await.unwrap().unwrap().schema();
+        assert_eq!(ledger.field_with_name("id").unwrap().data_type(), &DataType::Int64);
+
+        let res = ctx.sql("SELECT * FROM unity.default.events").await;
+        assert!(matches!(res, Err(DataFusionError::NotImplemented(_))));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_table_names_follow_resolution() -> UCRSResult<()> {
+        let dir = temp_dir();
+        let parquet = write_parquet(dir.path()).await;
+        let server = mock_metastore(&parquet, &parquet).await;
+
+        let rc = Arc::new(RequestClient::new(&server.uri(), true)?);
+        let schema = UnityCatalogProvider::try_new(Arc::new(CredentialCache::new(rc)), "unity").await?
+            .schema("default")
+            .unwrap();
+        assert!(!schema.table_exist("late"));
+        assert!(schema.table("late").await.unwrap().is_some());
+        assert!(schema.table_exist("late") && schema.table_names().contains(&"late".to_owned()));
+
+        assert!(schema.table_exist("dropped"));
+        assert!(schema.table("dropped").await.unwrap().is_none());
+        assert!(!schema.table_exist("dropped") && !schema.table_names().contains(&"dropped".to_owned()));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_table_ttl() -> UCRSResult<()> {
+        let dir = temp_dir();
+        let parquet = write_parquet(dir.path()).await;
+        let server = mock_metastore(&parquet, &parquet).await;
+        let rc = Arc::new(RequestClient::new(&server.uri(), true)?);
+        let credentials = Arc::new(CredentialCache::new(rc));
+
+        let cached = UnityCatalogProvider::try_new(Arc::clone(&credentials), "unity").await?
+            .schema("default")
+            .unwrap();
+        let first = cached.table("orders").await.unwrap().unwrap();
+        let second = cached.table("orders").await.unwrap().unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(table_gets(&server.received_requests().await.unwrap(), ORDERS), 1);
+
+        let expiring = UnityCatalogProvider::try_new_with_ttl(credentials, "unity", Duration::ZERO).await?
+            .schema("default")
+            .unwrap();
+        let first = expiring.table("orders").await.unwrap().unwrap();
+        let second = expiring.table("orders").await.unwrap().unwrap();
+        assert!(!Arc::ptr_eq(&first, &second));
+        assert_eq!(table_gets(&server.received_requests().await.unwrap(), ORDERS), 3);
+        Ok(())
+    }
+}
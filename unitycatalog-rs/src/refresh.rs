@@ -0,0 +1,410 @@
+use crate::errors::{UCRSError, UCRSResult};
+use crate::oss::api::tables::TableInfo;
+use crate::oss::api::temporary_credentials::{TableOperation, TemporaryCredentials, TemporaryCredentialsClient, VolumeOperation};
+use crate::oss::api::volumes::VolumesClient;
+use crate::request::RequestClient;
+use crate::store::{build_with, table_location, volume_location, FromVended, Providers};
+use async_trait::async_trait;
+use object_store::aws::AwsCredential;
+use object_store::azure::AzureCredential;
+use object_store::gcp::GcpCredential;
+use object_store::{CredentialProvider, ObjectStore};
+use std::collections::HashMap;
+use std::fmt;
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const DEFAULT_REFRESH_MARGIN: Duration = Duration::from_secs(5 * 60);
+
+/// What a set of vended credentials is scoped to: a table or volume id and
+/// the operation they allow.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Securable {
+    Table(String, TableOperation),
+    Volume(String, VolumeOperation)
+}
+
+struct Cached {
+    credentials: TemporaryCredentials,
+    /// `None` for credentials without an expiration time, which are kept
+    /// until the cache is dropped.
+    expires_at: Option<SystemTime>,
+    refresh_at: Option<SystemTime>
+}
+
+impl Cached {
+    /// Refreshes `margin` before expiry, but never earlier than halfway
+    /// through the lifetime left when fetched, so short-lived credentials
+    /// aren't fetched again on every call.
+    fn new(credentials: TemporaryCredentials, margin: Duration) -> Self {
+        let now = SystemTime::now();
+        let expires_at = credentials.expiration_time()
+            .map(|millis| UNIX_EPOCH + Duration::from_millis(millis.max(0) as u64));
+        let refresh_at = expires_at.map(|expires_at| {
+            let remaining = expires_at.duration_since(now).unwrap_or_default();
+            expires_at - margin.min(remaining / 2)
+        });
+        Self { credentials, expires_at, refresh_at }
+    }
+
+    fn needs_refresh(&self, now: SystemTime) -> bool {
+        self.refresh_at.is_some_and(|at| now >= at)
+    }
+
+    fn expired(&self, now: SystemTime) -> bool {
+        self.expires_at.is_some_and(|at| now >= at)
+    }
+}
+
+type Slot = Arc<tokio::sync::Mutex<Option<Cached>>>;
+
+/// Caches vended credentials per securable and refreshes them `margin`
+/// before they expire, or halfway through their lifetime if that is
+/// shorter. Concurrent callers wanting the same securable wait
+/// for a single refresh rather than each asking the server. Securables whose
+/// credentials have expired are dropped from the cache.
+pub struct CredentialCache {
+    client: Arc<RequestClient>,
+    margin: Duration,
+    slots: Mutex<HashMap<Securable, Slot>>
+}
+
+impl CredentialCache {
+    pub fn new(client: Arc<RequestClient>) -> Self {
+        Self {
+            client,
+            margin: DEFAULT_REFRESH_MARGIN,
+            slots: Mutex::default()
+        }
+    }
+
+    pub fn with_margin(mut self, margin: Duration) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    pub fn client(&self) -> &Arc<RequestClient> {
+        &self.client
+    }
+
+    pub async fn get(&self, securable: &Securable) -> UCRSResult<TemporaryCredentials> {
+        let slot = {
+            let mut slots = self.slots.lock().unwrap();
+            evict_expired(&mut slots);
+            slots.entry(securable.clone()).or_default().clone()
+        };
+        let mut cached = slot.lock().await;
+        if let Some(cached) = cached.as_ref().filter(|c| !c.needs_refresh(SystemTime::now())) {
+            return Ok(cached.credentials.clone());
+        }
+
+        let client = TemporaryCredentialsClient::new(&self.client);
+        let fresh = match securable {
+            Securable::Table(table_id, operation) => client.table(table_id, *operation).await,
+            Securable::Volume(volume_id, operation) => client.volume(volume_id, *operation).await
+        };
+        match fresh {
+            Ok(fresh) => {
+                *cached = Some(Cached::new(fresh.clone(), self.margin));
+                Ok(fresh)
+            },
+            // Inside the margin the old credentials still work, so a failed
+            // refresh is retried on the next call instead of failing this one.
+            Err(e) => match cached.as_ref().filter(|c| !c.expired(SystemTime::now())) {
+                Some(cached) => {
+                    tracing::warn!("Refreshing credentials for {:?} failed, using the cached ones: {}", securable, e);
+                    Ok(cached.credentials.clone())
+                },
+                None => Err(e)
+            }
+        }
+    }
+
+    pub fn provider<C>(self: &Arc<Self>, securable: Securable) -> RefreshingCredentialProvider<C> {
+        RefreshingCredentialProvider {
+            cache: Arc::clone(self),
+            securable,
+            credential: PhantomData
+        }
+    }
+
+    /// Like `store::table_store`, but the store keeps refreshing its
+    /// credentials through this cache.
+    pub async fn table_store(self: &Arc<Self>, table: &TableInfo, operation: TableOperation) -> UCRSResult<Arc<dyn ObjectStore>> {
+        let (table_id, location) = table_location(table)?;
+        self.store(location, Securable::Table(table_id.to_owned(), operation)).await
+    }
+
+    pub async fn volume_store(self: &Arc<Self>, full_name: &str, operation: VolumeOperation) -> UCRSResult<Arc<dyn ObjectStore>> {
+        let volume = VolumesClient::new(&self.client).get(full_name).await?;
+        let (volume_id, location) = volume_location(&volume, full_name)?;
+        self.store(location, Securable::Volume(volume_id.to_owned(), operation)).await
+    }
+
+    async fn store(self: &Arc<Self>, location: &str, securable: Securable) -> UCRSResult<Arc<dyn ObjectStore>> {
+        // Fetch up front so a securable in the wrong cloud fails here rather
+        // than on the first read.
+        let credentials = self.get(&securable).await?;
+        let missing = || UCRSError::MissingTemporaryCredentials(location.to_owned());
+        build_with(location, Providers {
+            aws: &|| {
+                AwsCredential::from_vended(&credentials).ok_or_else(missing)?;
+                Ok(Arc::new(self.provider::<AwsCredential>(securable.clone())))
+            },
+            azure: &|| {
+                AzureCredential::from_vended(&credentials).ok_or_else(missing)?;
+                Ok(Arc::new(self.provider::<AzureCredential>(securable.clone())))
+            },
+            gcp: &|| {
+                GcpCredential::from_vended(&credentials).ok_or_else(missing)?;
+                Ok(Arc::new(self.provider::<GcpCredential>(securable.clone())))
+            }
+        })
+    }
+}
+
+/// Drops slots nobody is using whose credentials have expired, or that were
+/// never filled because fetching failed. A slot cloned out of the map is in
+/// use, so a caller waiting on it never loses it.
+fn evict_expired(slots: &mut HashMap<Securable, Slot>) {
+    let now = SystemTime::now();
+    slots.retain(|_, slot| {
+        Arc::strong_count(slot) > 1 || slot.try_lock()
+            .map_or(true, |cached| cached.as_ref().is_some_and(|c| !c.expired(now)))
+    });
+}
+
+impl fmt::Debug for CredentialCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CredentialCache")
+            .field("base_url", &self.client.base_url.as_str())
+            .field("margin", &self.margin)
+            .finish()
+    }
+}
+
+/// An `object_store` `CredentialProvider` serving `C` from a shared
+/// `CredentialCache`.
+pub struct RefreshingCredentialProvider<C> {
+    cache: Arc<CredentialCache>,
+    securable: Securable,
+    credential: PhantomData<fn() -> C>
+}
+
+impl<C> fmt::Debug for RefreshingCredentialProvider<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RefreshingCredentialProvider")
+            .field("securable", &self.securable)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl<C: FromVended + Send + Sync> CredentialProvider for RefreshingCredentialProvider<C> {
+    type Credential = C;
+
+    async fn get_credential(&self) -> object_store::Result<Arc<C>> {
+        let generic = |e: UCRSError| object_store::Error::Generic {
+            store: "unity-catalog",
+            source: Box::new(e)
+        };
+        let credentials = self.cache.get(&self.securable).await.map_err(generic)?;
+        C::from_vended(&credentials)
+            .map(Arc::new)
+            .ok_or_else(|| generic(UCRSError::MissingTemporaryCredentials(format!("{:?}", self.securable))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn expiring_in(duration: Duration) -> i64 {
+        (SystemTime::now() + duration).duration_since(UNIX_EPOCH).unwrap().as_millis() as i64
+    }
+
+    fn aws_credentials_until(key_id: &str, expiration_time: i64) -> serde_json::Value {
+        json!({
+            "aws_temp_credentials": {"access_key_id": key_id, "secret_access_key": "shh", "session_token": "tok"},
+            "expiration_time": expiration_time
+        })
+    }
+
+    fn aws_credentials(key_id: &str, expires_in: Duration) -> serde_json::Value {
+        aws_credentials_until(key_id, expiring_in(expires_in))
+    }
+
+    fn expired() -> i64 {
+        (SystemTime::now() - Duration::from_secs(60)).duration_since(UNIX_EPOCH).unwrap().as_millis() as i64
+    }
+
+    #[tokio::test]
+    async fn test_refresh_margin() -> UCRSResult<()> {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/2.1/unity-catalog/temporary-table-credentials"))
+            .and(body_partial_json(json!({"table_id": "long"})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(aws_credentials("long", Duration::from_secs(3600))))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/2.1/unity-catalog/temporary-table-credentials"))
+            .and(body_partial_json(json!({"table_id": "short"})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(aws_credentials("short", Duration::from_secs(60))))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/2.1/unity-catalog/temporary-table-credentials"))
+            .and(body_partial_json(json!({"table_id": "expired"})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(aws_credentials_until("expired", expired())))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        // The 60s credentials are well inside the 5 minute margin, but are
+        // still reused until halfway through their lifetime.
+        let cache = Arc::new(CredentialCache::new(Arc::new(RequestClient::new(&server.uri(), true)?)));
+        let long = cache.provider::<AwsCredential>(Securable::Table("long".to_owned(), TableOperation::READ));
+        let short = cache.provider::<AwsCredential>(Securable::Table("short".to_owned(), TableOperation::READ));
+        let expired = cache.provider::<AwsCredential>(Securable::Table("expired".to_owned(), TableOperation::READ));
+        for _ in 0..2 {
+            assert_eq!(long.get_credential().await.unwrap().key_id, "long");
+            assert_eq!(short.get_credential().await.unwrap().key_id, "short");
+            assert_eq!(expired.get_credential().await.unwrap().key_id, "expired");
+        }
+
+        let gcp = cache.provider::<GcpCredential>(Securable::Table("long".to_owned(), TableOperation::READ));
+        assert!(gcp.get_credential().await.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_refresh_at() {
+        let credentials = |expires_in| serde_json::from_value::<TemporaryCredentials>(aws_credentials("k", expires_in)).unwrap();
+        let margin = Duration::from_secs(300);
+        let now = SystemTime::now();
+
+        let long = Cached::new(credentials(Duration::from_secs(3600)), margin);
+        assert!(!long.needs_refresh(now + Duration::from_secs(3200)));
+        assert!(long.needs_refresh(now + Duration::from_secs(3301)));
+
+        let short = Cached::new(credentials(Duration::from_secs(60)), margin);
+        assert!(!short.needs_refresh(now + Duration::from_secs(25)));
+        assert!(short.needs_refresh(now + Duration::from_secs(31)));
+        assert!(!short.expired(now + Duration::from_secs(31)));
+        assert!(short.expired(now + Duration::from_secs(61)));
+    }
+
+    #[tokio::test]
+    async fn test_failed_refresh_keeps_valid_credentials() -> UCRSResult<()> {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/2.1/unity-catalog/temporary-table-credentials"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(aws_credentials("first", Duration::from_secs(60))))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/2.1/unity-catalog/temporary-table-credentials"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&server)
+            .await;
+
+        let cache = CredentialCache::new(Arc::new(RequestClient::new(&server.uri(), true)?));
+        let securable = Securable::Table("t".to_owned(), TableOperation::READ);
+        cache.get(&securable).await?;
+        // Bring the refresh forward: the credentials are due but not expired.
+        {
+            let slots = cache.slots.lock().unwrap();
+            let mut slot = slots[&securable].try_lock().unwrap();
+            slot.as_mut().unwrap().refresh_at = Some(SystemTime::now());
+        }
+
+        let credentials = cache.get(&securable).await?;
+        assert_eq!(credentials.aws_temp_credentials().unwrap().access_key_id(), "first");
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+
+        let other = Securable::Table("other".to_owned(), TableOperation::READ);
+        assert!(cache.get(&other).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_expired_slots_are_evicted() -> UCRSResult<()> {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/2.1/unity-catalog/temporary-table-credentials"))
+            .and(body_partial_json(json!({"table_id": "gone"})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(aws_credentials_until("gone", expired())))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/2.1/unity-catalog/temporary-table-credentials"))
+            .and(body_partial_json(json!({"table_id": "kept"})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(aws_credentials("kept", Duration::from_secs(3600))))
+            .mount(&server)
+            .await;
+
+        let cache = CredentialCache::new(Arc::new(RequestClient::new(&server.uri(), true)?));
+        cache.get(&Securable::Table("gone".to_owned(), TableOperation::READ)).await?;
+        assert_eq!(cache.slots.lock().unwrap().len(), 1);
+        cache.get(&Securable::Table("kept".to_owned(), TableOperation::READ)).await?;
+        let slots = cache.slots.lock().unwrap();
+        assert_eq!(slots.keys().collect::<Vec<_>>(), vec![&Securable::Table("kept".to_owned(), TableOperation::READ)]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_single_flight() -> UCRSResult<()> {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/2.1/unity-catalog/temporary-table-credentials"))
+            .respond_with(ResponseTemplate::new(200)
+                .set_body_json(aws_credentials("shared", Duration::from_secs(3600)))
+                .set_delay(Duration::from_millis(100)))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let cache = Arc::new(CredentialCache::new(Arc::new(RequestClient::new(&server.uri(), true)?)));
+        let provider = Arc::new(cache.provider::<AwsCredential>(Securable::Table("t-1".to_owned(), TableOperation::READ)));
+        let handles = (0..8).map(|_| {
+            let provider = Arc::clone(&provider);
+            tokio::spawn(async move { provider.get_credential().await.map(|c| c.key_id.clone()) })
+        }).collect::<Vec<_>>();
+        for handle in handles {
+            assert_eq!(handle.await.unwrap().unwrap(), "shared");
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_table_store() -> UCRSResult<()> {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/2.1/unity-catalog/temporary-table-credentials"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "azure_user_delegation_sas": {"sas_token": "sv=2024&sig=abc"},
+                "expiration_time": expiring_in(Duration::from_secs(3600))
+            })))
+            .mount(&server)
+            .await;
+
+        let cache = Arc::new(CredentialCache::new(Arc::new(RequestClient::new(&server.uri(), true)?)));
+        let table = |location: &str| serde_json::from_value::<TableInfo>(json!({
+            "name": "orders",
+            "table_id": "t-1",
+            "storage_location": location
+        })).unwrap();
+        assert!(cache.table_store(&table("abfss://lake@acct.dfs.core.windows.net/orders"), TableOperation::READ).await.is_ok());
+        let res = cache.table_store(&table("s3://lake/orders"), TableOperation::READ).await;
+        assert!(matches!(res, Err(UCRSError::MissingTemporaryCredentials(_))));
+        Ok(())
+    }
+}
@@ -1,124 +1,293 @@
 use reqwest::{Client, header::HeaderMap, Method};
 use url::Url;
 use crate::errors::{UCRSError, UCRSResult};
+use crate::flavor::Flavor;
+use crate::limits::{Limiter, RequestLimits};
+use crate::middleware::{Middleware, MiddlewareChain, RequestContext, ResponseContext};
+use std::borrow::Cow;
+use std::sync::Arc;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 
 pub struct RequestClient {
     pub base_url: Url,
-    client: Client
+    client: Client,
+    limiter: Limiter,
+    middleware: MiddlewareChain,
+    flavor: Flavor
 }
 
 impl RequestClient {
     pub fn new(base_url: &str, disable_ssl: bool) -> UCRSResult<Self> {
         let base_url = Url::parse(base_url)
-            .map_err(|e| UCRSError::MalformedURL(e))?;
+            .map_err(UCRSError::MalformedURL)?;
 
         let client = Client::builder()
             .danger_accept_invalid_certs(disable_ssl)
             .build()
-            .map_err(|e| {
-                UCRSError::ClientBuildError(e)
-            })?;
+            .map_err(UCRSError::ClientBuildError)?;
 
         Ok(Self {
             base_url,
-            client
+            client,
+            limiter: Limiter::default(),
+            middleware: MiddlewareChain::default(),
+            flavor: Flavor::default()
         })
     }
 
     pub fn new_with_headers(base_url: &str, disable_ssl: bool, headers: HeaderMap) -> UCRSResult<Self> {
         let base_url = Url::parse(base_url)
-            .map_err(|e| UCRSError::MalformedURL(e))?;
+            .map_err(UCRSError::MalformedURL)?;
 
         let client = Client::builder()
             .danger_accept_invalid_certs(disable_ssl)
             .default_headers(headers)
             .build()
-            .map_err(|e| {
-                UCRSError::ClientBuildError(e)
-            })?;
+            .map_err(UCRSError::ClientBuildError)?;
 
         Ok(Self {
             base_url,
-            client
+            client,
+            limiter: Limiter::default(),
+            middleware: MiddlewareChain::default(),
+            flavor: Flavor::default()
         })
     }
 
     pub fn new_with_client(base_url: &str, client: Client) -> UCRSResult<Self> {
         let base_url = Url::parse(base_url)
-            .map_err(|e| UCRSError::MalformedURL(e))?;
+            .map_err(UCRSError::MalformedURL)?;
 
         Ok(Self {
             base_url,
-            client
+            client,
+            limiter: Limiter::default(),
+            middleware: MiddlewareChain::default(),
+            flavor: Flavor::default()
         })
 
     }
 
+    /// Applies rate and in-flight limits to every request made through this
+    /// client, including those from sub-clients sharing it.
+    pub fn with_limits(mut self, limits: RequestLimits) -> Self {
+        self.limiter = Limiter::new(&limits);
+        self
+    }
+
+    /// Appends a middleware to the chain run around every request.
+    pub fn with_middleware(mut self, middleware: impl Middleware + 'static) -> Self {
+        self.middleware.push(Arc::new(middleware));
+        self
+    }
+
+    pub fn with_flavor(mut self, flavor: Flavor) -> Self {
+        self.flavor = flavor;
+        self
+    }
+
+    pub fn flavor(&self) -> Flavor {
+        self.flavor
+    }
+
     pub async fn get<B, R>(&self, route: Url, body: Option<B>) -> UCRSResult<R>
         where 
             B: Serialize + std::fmt::Debug,
             R: DeserializeOwned {
-        self.request(route, Method::GET,  body).await
+        let operation = default_operation(&Method::GET, &route);
+        self.request(operation.into(), route, Method::GET, body, parse_json).await
+    }
+
+    /// `get`, reported to metrics and middleware as `operation`.
+    pub async fn get_op<B, R>(&self, operation: &'static str, route: Url, body: Option<B>) -> UCRSResult<R>
+        where 
+            B: Serialize + std::fmt::Debug,
+            R: DeserializeOwned {
+        self.request(operation.into(), route, Method::GET, body, parse_json).await
     }
 
     pub async fn post<B, R>(&self, route: Url, body: Option<B>) -> UCRSResult<R>
         where 
             B: Serialize + std::fmt::Debug,
             R: DeserializeOwned {
-        self.request(route, Method::POST,  body).await
+        let operation = default_operation(&Method::POST, &route);
+        self.request(operation.into(), route, Method::POST, body, parse_json).await
     }
 
-    pub async fn delete<B, R>(&self, route: Url, body: Option<B>) -> UCRSResult<R>
+    /// `post`, reported to metrics and middleware as `operation`.
+    pub async fn post_op<B, R>(&self, operation: &'static str, route: Url, body: Option<B>) -> UCRSResult<R>
         where 
             B: Serialize + std::fmt::Debug,
             R: DeserializeOwned {
-        self.request(route, Method::DELETE,  body).await
+        self.request(operation.into(), route, Method::POST, body, parse_json).await
+    }
+
+    /// DELETE responses carry no data; their body is only checked as the
+    /// client's `Flavor` expects.
+    pub async fn delete<B>(&self, route: Url, body: Option<B>) -> UCRSResult<()>
+        where 
+            B: Serialize + std::fmt::Debug {
+        let operation = default_operation(&Method::DELETE, &route);
+        let flavor = self.flavor;
+        self.request(operation.into(), route, Method::DELETE, body, |b: &str| flavor.check_delete_body(b)).await
+    }
+
+    /// `delete`, reported to metrics and middleware as `operation`.
+    pub async fn delete_op<B>(&self, operation: &'static str, route: Url, body: Option<B>) -> UCRSResult<()>
+        where 
+            B: Serialize + std::fmt::Debug {
+        let flavor = self.flavor;
+        self.request(operation.into(), route, Method::DELETE, body, |b: &str| flavor.check_delete_body(b)).await
     }
 
     pub async fn patch<B, R>(&self, route: Url, body: Option<B>) -> UCRSResult<R>
         where 
             B: Serialize + std::fmt::Debug,
             R: DeserializeOwned {
-        self.request(route, Method::PATCH,  body).await
+        let operation = default_operation(&Method::PATCH, &route);
+        self.request(operation.into(), route, Method::PATCH, body, parse_json).await
     }
 
-    #[tracing::instrument(skip(self))]
-    async fn request<B, R>(&self, route: Url, method: reqwest::Method, body: Option<B>) -> UCRSResult<R> 
+    /// `patch`, reported to metrics and middleware as `operation`.
+    pub async fn patch_op<B, R>(&self, operation: &'static str, route: Url, body: Option<B>) -> UCRSResult<R>
         where 
             B: Serialize + std::fmt::Debug,
-            R: DeserializeOwned{
+            R: DeserializeOwned {
+        self.request(operation.into(), route, Method::PATCH, body, parse_json).await
+    }
+
+    #[tracing::instrument(skip(self, decode))]
+    async fn request<B, R, D>(&self, operation: Cow<'static, str>, route: Url, method: reqwest::Method, body: Option<B>, decode: D) -> UCRSResult<R> 
+        where 
+            B: Serialize + std::fmt::Debug,
+            D: FnOnce(&str) -> UCRSResult<R> {
+        let _permit = self.limiter.acquire((&method).into()).await;
+        // Anything that fails from here on is reported through the same
+        // metrics and `on_error` hooks, whether or not it was sent.
+        let mut context = RequestContext::unsent(operation.clone(), &method, &route);
+        let res = match self.prepare(method, route, body) {
+            Ok(request) => {
+                context = RequestContext::new(operation, &request);
+                self.execute(request, &context, decode).await
+            },
+            Err(e) => Err(e)
+        };
+        if let Err(e) = &res {
+            #[cfg(feature = "metrics")]
+            crate::instrumentation::record_error(&context, e);
+            self.middleware.on_error(&context, e);
+        }
+        res
+    }
+
+    /// Builds the request and runs the `on_request` hooks over it.
+    fn prepare<B>(&self, method: reqwest::Method, route: Url, body: Option<B>) -> UCRSResult<reqwest::Request>
+        where B: Serialize + std::fmt::Debug {
         let request = self.client.request(method, route);
+        // Logged in Debug form, which request types use to redact secrets.
+        if let Some(b) = &body {
+            tracing::debug!("Body is {:?}", b);
+        }
         let body = body.map(|b| {
-            serde_json::to_string(&b).map_err(|be| {
-                UCRSError::JSONFormattingError(be)
-            })
+            serde_json::to_string(&b).map_err(UCRSError::JSONFormattingError)
         });
 
         let request = match body {
             Some(b) => {
-                let b = b?;
-                eprintln!("Body is {}", b);
                 request
-                    .body(b)
+                    .body(b?)
                     .header("Content-Type", "application/json")
                     .header("Accept", "application/json")
             },
             None => request
         };
-        
-        let response = request.send().await
-            .map_err(|e| UCRSError::RequestError(e))?;
-
-        if let Err(e) = response.error_for_status_ref() {
-            let response_body = response.text().await
-                .map_err(|e| UCRSError::RequestError(e))?;
-            Err(UCRSError::RequestErrorWithResponse(e, response_body))
+
+        let mut request = request.build()
+            .map_err(UCRSError::RequestError)?;
+        self.middleware.on_request(&mut request)?;
+        Ok(request)
+    }
+
+    async fn execute<R, D>(&self, request: reqwest::Request, context: &RequestContext, decode: D) -> UCRSResult<R>
+        where D: FnOnce(&str) -> UCRSResult<R> {
+        let response = match self.middleware.respond(context) {
+            Some(response) => reqwest::Response::from(response?),
+            None => self.client.execute(request).await
+                .map_err(UCRSError::RequestError)?
+        };
+
+        let status_error = response.error_for_status_ref().err();
+        let response = ResponseContext {
+            status: response.status(),
+            headers: response.headers().clone(),
+            body: response.text().await
+                .map_err(UCRSError::RequestError)?
+        };
+        #[cfg(feature = "metrics")]
+        crate::instrumentation::record_response(context, &response);
+        self.middleware.on_response(context, &response);
+
+        if let Some(e) = status_error {
+            Err(UCRSError::RequestErrorWithResponse(e, response.body))
         } else {
-            let response_body = response.json::<R>().await
-                .map_err(|e| UCRSError::JSONParsingError(e))?;
-            Ok(response_body)
+            decode(&response.body)
         }
     }
-}
\ No newline at end of file
+}
+
+fn parse_json<R: DeserializeOwned>(body: &str) -> UCRSResult<R> {
+    serde_json::from_str(body)
+        .map_err(UCRSError::JSONParsingError)
+}
+
+/// Name for calls made without one: the resource the route is under and the
+/// method, e.g. `tables.get`, so it stays a low-cardinality metrics label.
+fn default_operation(method: &Method, route: &Url) -> String {
+    let resource = route.path_segments().into_iter().flatten()
+        .find(|s| !s.is_empty() && *s != "api" && *s != "unity-catalog" && !s.starts_with(|c: char| c.is_ascii_digit()))
+        .unwrap_or("request");
+    format!("{}.{}", resource, method.as_str().to_ascii_lowercase())
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::oss::api::credentials::{AzureServicePrincipal, CreateStorageCredential};
+    use tracing_test::traced_test;
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    #[traced_test]
+    async fn test_secrets_are_not_logged() -> UCRSResult<()> {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/2.1/unity-catalog/storage-credentials"))
+            .and(body_partial_json(serde_json::json!({"azure_service_principal": {"client_secret": "hunter2"}})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"name": "lake"})))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let rc = RequestClient::new(&server.uri(), true)?;
+        let body = CreateStorageCredential::builder()
+            .name("lake".to_owned())
+            .azure_service_principal(AzureServicePrincipal::new("tenant-1", "app-1", "hunter2"))
+            .build()
+            .unwrap();
+        let route = rc.base_url.join("/api/2.1/unity-catalog/storage-credentials").unwrap();
+        let _: serde_json::Value = rc.post(route, Some(&body)).await?;
+
+        assert!(logs_contain("app-1"));
+        assert!(!logs_contain("hunter2"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_default_operation() {
+        let url = |path: &str| Url::parse("http://localhost:8080").unwrap().join(path).unwrap();
+        assert_eq!(default_operation(&Method::GET, &url("/api/2.1/unity-catalog/tables/unity.default.t")), "tables.get");
+        assert_eq!(default_operation(&Method::POST, &url("/api/2.1/unity-catalog/temporary-table-credentials")), "temporary-table-credentials.post");
+        assert_eq!(default_operation(&Method::DELETE, &url("/")), "request.delete");
+    }
+}
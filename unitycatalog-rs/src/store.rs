@@ -0,0 +1,296 @@
+use crate::errors::{UCRSError, UCRSResult};
+use crate::oss::api::tables::TableInfo;
+use crate::oss::api::temporary_credentials::{TableOperation, TemporaryCredentials, TemporaryCredentialsClient, VolumeOperation};
+use crate::oss::api::volumes::{VolumeInfo, VolumesClient};
+use crate::request::RequestClient;
+use async_trait::async_trait;
+use futures_util::stream::{self, BoxStream};
+use futures_util::{StreamExt, TryStreamExt};
+use object_store::aws::{AmazonS3Builder, AwsCredential, AwsCredentialProvider};
+use object_store::azure::{AzureCredential, AzureCredentialProvider, MicrosoftAzureBuilder};
+use object_store::gcp::{GcpCredential, GcpCredentialProvider, GoogleCloudStorageBuilder};
+use object_store::local::LocalFileSystem;
+use object_store::path::Path;
+use object_store::prefix::PrefixStore;
+use object_store::{CopyOptions, GetOptions, GetResult, ListResult, MultipartUpload, ObjectMeta, ObjectStore, PutMultipartOptions, PutOptions, PutPayload, PutResult, RenameOptions, StaticCredentialProvider};
+use std::fmt;
+use std::sync::Arc;
+use url::Url;
+
+/// An `ObjectStore` rooted at the table's `storage_location`, using
+/// credentials vended for `operation`.
+pub async fn table_store(client: &RequestClient, table: &TableInfo, operation: TableOperation) -> UCRSResult<Arc<dyn ObjectStore>> {
+    let (table_id, location) = table_location(table)?;
+    let credentials = TemporaryCredentialsClient::new(client).table(table_id, operation).await?;
+    build_store(location, &credentials)
+}
+
+/// An `ObjectStore` rooted at the storage location of the volume `full_name`.
+pub async fn volume_store(client: &RequestClient, full_name: &str, operation: VolumeOperation) -> UCRSResult<Arc<dyn ObjectStore>> {
+    let volume = VolumesClient::new(client).get(full_name).await?;
+    let (volume_id, location) = volume_location(&volume, full_name)?;
+    let credentials = TemporaryCredentialsClient::new(client).volume(volume_id, operation).await?;
+    build_store(location, &credentials)
+}
+
+/// The id and storage location of `table`; views and foreign tables have none.
+pub(crate) fn table_location(table: &TableInfo) -> UCRSResult<(&str, &str)> {
+    match (table.table_id(), table.storage_location()) {
+        (Some(table_id), Some(location)) => Ok((table_id, location)),
+        _ => {
+            let name = table.full_name().or(table.name()).unwrap_or_default();
+            Err(UCRSError::UnsupportedStorageLocation(name.to_owned()))
+        }
+    }
+}
+
+pub(crate) fn volume_location<'v>(volume: &'v VolumeInfo, full_name: &str) -> UCRSResult<(&'v str, &'v str)> {
+    match (volume.volume_id(), volume.storage_location()) {
+        (Some(volume_id), Some(location)) => Ok((volume_id, location)),
+        _ => Err(UCRSError::UnsupportedStorageLocation(full_name.to_owned()))
+    }
+}
+
+/// Builds a store for `s3://`, `abfss://`, `gs://` or `file://` locations.
+/// Settings the server doesn't vend, such as the AWS region, are read from
+/// the environment.
+pub fn build_store(storage_location: &str, credentials: &TemporaryCredentials) -> UCRSResult<Arc<dyn ObjectStore>> {
+    let missing = || UCRSError::MissingTemporaryCredentials(storage_location.to_owned());
+    build_with(storage_location, Providers {
+        aws: &|| {
+            let credential = AwsCredential::from_vended(credentials).ok_or_else(missing)?;
+            Ok(Arc::new(StaticCredentialProvider::new(credential)))
+        },
+        azure: &|| {
+            let credential = AzureCredential::from_vended(credentials).ok_or_else(missing)?;
+            Ok(Arc::new(StaticCredentialProvider::new(credential)))
+        },
+        gcp: &|| {
+            let credential = GcpCredential::from_vended(credentials).ok_or_else(missing)?;
+            Ok(Arc::new(StaticCredentialProvider::new(credential)))
+        }
+    })
+}
+
+/// Makes the credential provider for whichever cloud a location turns out
+/// to be in; only that one is called.
+pub(crate) struct Providers<'p> {
+    pub(crate) aws: &'p dyn Fn() -> UCRSResult<AwsCredentialProvider>,
+    pub(crate) azure: &'p dyn Fn() -> UCRSResult<AzureCredentialProvider>,
+    pub(crate) gcp: &'p dyn Fn() -> UCRSResult<GcpCredentialProvider>
+}
+
+pub(crate) fn build_with(storage_location: &str, providers: Providers<'_>) -> UCRSResult<Arc<dyn ObjectStore>> {
+    let url = Url::parse(storage_location).map_err(UCRSError::MalformedURL)?;
+    let store: Arc<dyn ObjectStore> = match url.scheme() {
+        "file" => {
+            let path = url.to_file_path()
+                .map_err(|_| UCRSError::UnsupportedStorageLocation(storage_location.to_owned()))?;
+            let local = LocalFileSystem::new_with_prefix(path).map_err(UCRSError::ObjectStoreError)?;
+            return Ok(Arc::new(SortedListing(local)));
+        },
+        "s3" | "s3a" => Arc::new(AmazonS3Builder::from_env()
+            .with_url(storage_location)
+            .with_credentials((providers.aws)()?)
+            .build()
+            .map_err(UCRSError::ObjectStoreError)?),
+        "abfss" | "abfs" | "az" => Arc::new(MicrosoftAzureBuilder::from_env()
+            .with_url(storage_location)
+            .with_credentials((providers.azure)()?)
+            .build()
+            .map_err(UCRSError::ObjectStoreError)?),
+        "gs" => Arc::new(GoogleCloudStorageBuilder::from_env()
+            .with_url(storage_location)
+            .with_credentials((providers.gcp)()?)
+            .build()
+            .map_err(UCRSError::ObjectStoreError)?),
+        _ => return Err(UCRSError::UnsupportedStorageLocation(storage_location.to_owned()))
+    };
+
+    let prefix = location_prefix(&url, storage_location)?;
+    if prefix.as_ref().is_empty() {
+        Ok(store)
+    } else {
+        Ok(Arc::new(PrefixStore::new(store, prefix)))
+    }
+}
+
+/// The path under the bucket or container. `Url::path` is percent-encoded,
+/// so it is decoded rather than encoded a second time.
+fn location_prefix(url: &Url, storage_location: &str) -> UCRSResult<Path> {
+    Path::from_url_path(url.path())
+        .map_err(|_| UCRSError::UnsupportedStorageLocation(storage_location.to_owned()))
+}
+
+/// A local store whose listings come back sorted, as they do from the cloud
+/// stores. Tables are opened under a scheme delta-rs can't tell is local,
+/// so it relies on that order when reading the log.
+#[derive(Debug)]
+struct SortedListing(LocalFileSystem);
+
+impl SortedListing {
+    fn sorted(listing: BoxStream<'static, object_store::Result<ObjectMeta>>) -> BoxStream<'static, object_store::Result<ObjectMeta>> {
+        stream::once(async move {
+            let mut objects: Vec<ObjectMeta> = listing.try_collect().await?;
+            objects.sort_by(|a, b| a.location.cmp(&b.location));
+            Ok::<_, object_store::Error>(stream::iter(objects.into_iter().map(Ok)))
+        })
+        .try_flatten()
+        .boxed()
+    }
+}
+
+impl fmt::Display for SortedListing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[async_trait]
+impl ObjectStore for SortedListing {
+    async fn put_opts(&self, location: &Path, payload: PutPayload, opts: PutOptions) -> object_store::Result<PutResult> {
+        self.0.put_opts(location, payload, opts).await
+    }
+
+    async fn put_multipart_opts(&self, location: &Path, opts: PutMultipartOptions) -> object_store::Result<Box<dyn MultipartUpload>> {
+        self.0.put_multipart_opts(location, opts).await
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> object_store::Result<GetResult> {
+        self.0.get_opts(location, options).await
+    }
+
+    fn delete_stream(&self, locations: BoxStream<'static, object_store::Result<Path>>) -> BoxStream<'static, object_store::Result<Path>> {
+        self.0.delete_stream(locations)
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'static, object_store::Result<ObjectMeta>> {
+        Self::sorted(self.0.list(prefix))
+    }
+
+    fn list_with_offset(&self, prefix: Option<&Path>, offset: &Path) -> BoxStream<'static, object_store::Result<ObjectMeta>> {
+        Self::sorted(self.0.list_with_offset(prefix, offset))
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> object_store::Result<ListResult> {
+        self.0.list_with_delimiter(prefix).await
+    }
+
+    async fn copy_opts(&self, from: &Path, to: &Path, options: CopyOptions) -> object_store::Result<()> {
+        self.0.copy_opts(from, to, options).await
+    }
+
+    async fn rename_opts(&self, from: &Path, to: &Path, options: RenameOptions) -> object_store::Result<()> {
+        self.0.rename_opts(from, to, options).await
+    }
+}
+
+/// An `object_store` credential that can be taken from vended credentials.
+pub trait FromVended: Sized {
+    fn from_vended(credentials: &TemporaryCredentials) -> Option<Self>;
+}
+
+impl FromVended for AwsCredential {
+    fn from_vended(credentials: &TemporaryCredentials) -> Option<Self> {
+        credentials.aws_temp_credentials().map(|aws| AwsCredential {
+            key_id: aws.access_key_id().to_owned(),
+            secret_key: aws.secret_access_key().to_owned(),
+            token: aws.session_token().map(str::to_owned)
+        })
+    }
+}
+
+impl FromVended for AzureCredential {
+    fn from_vended(credentials: &TemporaryCredentials) -> Option<Self> {
+        credentials.azure_user_delegation_sas().map(|sas| AzureCredential::SASToken(sas_pairs(sas.sas_token())))
+    }
+}
+
+impl FromVended for GcpCredential {
+    fn from_vended(credentials: &TemporaryCredentials) -> Option<Self> {
+        credentials.gcp_oauth_token().map(|gcp| GcpCredential { bearer: gcp.oauth_token().to_owned() })
+    }
+}
+
+fn sas_pairs(sas_token: &str) -> Vec<(String, String)> {
+    url::form_urlencoded::parse(sas_token.trim_start_matches('?').as_bytes())
+        .into_owned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::test_utils::temp_dir;
+    use object_store::ObjectStoreExt;
+    use serde_json::json;
+    use wiremock::matchers::{body_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn credentials(value: serde_json::Value) -> TemporaryCredentials {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_build_store() {
+        let aws = credentials(json!({"aws_temp_credentials": {"access_key_id": "AKIA", "secret_access_key": "shh"}}));
+        assert!(build_store("s3://lake/raw/orders", &aws).is_ok());
+        assert!(matches!(build_store("gs://lake/raw/orders", &aws), Err(UCRSError::MissingTemporaryCredentials(_))));
+        assert!(matches!(build_store("hdfs://nn/raw", &aws), Err(UCRSError::UnsupportedStorageLocation(_))));
+
+        let azure = credentials(json!({"azure_user_delegation_sas": {"sas_token": "?sv=2024&sig=abc%2B"}}));
+        assert!(build_store("abfss://lake@acct.dfs.core.windows.net/raw", &azure).is_ok());
+        assert_eq!(sas_pairs("?sv=2024&sig=abc%2B"), vec![
+            ("sv".to_owned(), "2024".to_owned()),
+            ("sig".to_owned(), "abc+".to_owned())
+        ]);
+    }
+
+    #[test]
+    fn test_escaped_location_prefix() -> UCRSResult<()> {
+        let location = "s3://lake/raw/my%20table";
+        let url = Url::parse(location).map_err(UCRSError::MalformedURL)?;
+        let prefix = location_prefix(&url, location)?;
+        assert_eq!(prefix, Path::from_iter(["raw", "my table"]));
+        assert!(!prefix.as_ref().contains("%25"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_table_store() -> UCRSResult<()> {
+        let dir = temp_dir();
+        let location = Url::from_directory_path(dir.path()).unwrap();
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/2.1/unity-catalog/temporary-table-credentials"))
+            .and(body_json(json!({"table_id": "t-1", "operation": "READ_WRITE"})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let rc = RequestClient::new(&server.uri(), true)?;
+        let table: TableInfo = serde_json::from_value(json!({
+            "name": "orders",
+            "table_id": "t-1",
+            "storage_location": location.as_str()
+        })).unwrap();
+        let store = table_store(&rc, &table, TableOperation::READ_WRITE).await?;
+        for part in ["part-2", "part-0", "part-1"] {
+            store.put(&Path::from(format!("{}.parquet", part)), "data".into()).await.map_err(UCRSError::ObjectStoreError)?;
+        }
+        assert!(dir.path().join("part-0.parquet").exists());
+        let listed = store.list_with_offset(None, &Path::from("part-0.parquet"))
+            .map_ok(|meta| meta.location.to_string())
+            .try_collect::<Vec<_>>()
+            .await
+            .map_err(UCRSError::ObjectStoreError)?;
+        assert_eq!(listed, vec!["part-1.parquet", "part-2.parquet"]);
+
+        let view: TableInfo = serde_json::from_value(json!({"name": "orders_v"})).unwrap();
+        let res = table_store(&rc, &view, TableOperation::READ).await;
+        assert!(matches!(res, Err(UCRSError::UnsupportedStorageLocation(ref n)) if n == "orders_v"));
+        Ok(())
+    }
+}
@@ -1,10 +1,12 @@
 #[cfg(test)]
 pub mod test_utils {
-    use std::{process::Stdio, thread, time::Duration};
+    use std::{path::PathBuf, process::Stdio, sync::Arc, thread, time::Duration};
     use futures_util::FutureExt; 
     use tokio::process::Command;
     use std::panic::AssertUnwindSafe;
     use port_scanner::request_open_port;
+    use crate::cassette::{Recorder, Replayer};
+    use crate::request::RequestClient;
 
     pub fn cleanup_user_model() -> Vec<(&'static str, &'static str)> {
         vec![
@@ -62,4 +64,62 @@ pub mod test_utils {
             stop_uc(child).await;
             res.expect("Error occurred during test function")
         }
+
+    /// Answers each `(method, path)` on `server` with a 200 carrying the
+    /// given JSON body.
+    #[cfg(feature = "deltalake")]
+    pub async fn mount_responses<'a>(server: &wiremock::MockServer, responses: impl IntoIterator<Item = (&'a str, &'a str, serde_json::Value)>) {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, ResponseTemplate};
+        for (verb, route, body) in responses {
+            Mock::given(method(verb))
+                .and(path(route))
+                .respond_with(ResponseTemplate::new(200).set_body_json(body))
+                .mount(server)
+                .await;
+        }
+    }
+
+    /// A fresh directory, removed when the returned handle is dropped.
+    pub fn temp_dir() -> tempfile::TempDir {
+        tempfile::Builder::new()
+            .prefix("ucrs-")
+            .tempdir()
+            .expect("Could not create temporary directory")
+    }
+
+    pub fn cassette_path(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("cassettes")
+            .join(format!("{}.json", name))
+    }
+
+    /// Replays the named cassette if it exists. Otherwise, or when
+    /// `UC_RECORD_CASSETTES` is set, runs against a fresh server container and
+    /// records a new cassette.
+    pub async fn test_with_cassette<F, Fut, R>(name: &str, callback: F) -> R
+    where
+        F: FnOnce(RequestClient) -> Fut,
+        Fut: std::future::Future<Output = R>
+        {
+            let path = cassette_path(name);
+            if path.exists() && std::env::var_os("UC_RECORD_CASSETTES").is_none() {
+                let replayer = Replayer::load(&path)
+                    .expect("Could not load cassette");
+                let rc = RequestClient::new("http://localhost:8080", true)
+                    .expect("Could not build client")
+                    .with_middleware(replayer);
+                return callback(rc).await;
+            }
+
+            let recorder = Arc::new(Recorder::new(&path));
+            let res = test_with_uc(|port| {
+                let rc = RequestClient::new(&format!("http://localhost:{}", port), true)
+                    .expect("Could not build client")
+                    .with_middleware(recorder.clone());
+                callback(rc)
+            }).await;
+            recorder.save().expect("Could not save cassette");
+            res
+        }
 }